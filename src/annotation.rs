@@ -0,0 +1,82 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/annotation.rs - 标注与检测结果的导入导出 (COCO JSON 与 YOLO txt)
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use thiserror::Error;
+
+mod coco;
+mod yolo;
+pub use coco::{CocoAnnotation, CocoCategory, CocoDataset, CocoImage};
+pub use yolo::{YoloLabel, format_yolo, parse_yolo};
+
+#[derive(Debug, Error)]
+pub enum AnnotationError {
+  #[error("解析错误: {0}")]
+  ParseError(String),
+  #[error("无效的输入: {0}")]
+  InvalidInput(String),
+  #[error("JSON 错误: {0}")]
+  JsonError(#[from] serde_json::Error),
+}
+
+/// 类别名称表，类别索引即名称在表中的位置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassNames {
+  names: Vec<String>,
+}
+
+impl ClassNames {
+  pub fn new(names: Vec<String>) -> Self {
+    Self { names }
+  }
+
+  /// 从每行一个名称的文本 (如 YOLO 的 classes.txt) 读取，忽略首尾空白与空行
+  pub fn from_lines(text: &str) -> Self {
+    Self {
+      names: text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect(),
+    }
+  }
+
+  /// 转换为每行一个名称的文本
+  pub fn to_lines(&self) -> String {
+    self
+      .names
+      .iter()
+      .map(|name| format!("{}\n", name))
+      .collect()
+  }
+
+  pub fn name(&self, class_id: u32) -> Option<&str> {
+    self.names.get(class_id as usize).map(String::as_str)
+  }
+
+  pub fn class_id(&self, name: &str) -> Option<u32> {
+    self.names.iter().position(|n| n == name).map(|i| i as u32)
+  }
+
+  pub fn names(&self) -> &[String] {
+    &self.names
+  }
+
+  pub fn len(&self) -> usize {
+    self.names.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.names.is_empty()
+  }
+}
@@ -0,0 +1,426 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/annotation/coco.rs - COCO JSON 标注与检测结果格式
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use serde_json::{Map, Value, json};
+
+use crate::{
+  annotation::{AnnotationError, ClassNames},
+  eval::GroundTruth,
+  postprocess::detection::{BoxUnit, Detection, DetectionSet},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CocoImage {
+  pub id: u64,
+  pub file_name: String,
+  pub width: u32,
+  pub height: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CocoCategory {
+  pub id: u64,
+  pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CocoAnnotation {
+  pub id: u64,
+  pub image_id: u64,
+  pub category_id: u64,
+  /// 像素坐标的边界框 (x, y, w, h)
+  pub bbox: [f32; 4],
+  pub area: f32,
+  /// COCO 的 iscrowd
+  pub crowd: bool,
+}
+
+/// COCO 格式的数据集标注
+///
+/// COCO 的类别编号通常不连续，本库的类别索引为类别在 categories 中的位置，categories 按编号升序排列
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CocoDataset {
+  pub images: Vec<CocoImage>,
+  pub categories: Vec<CocoCategory>,
+  pub annotations: Vec<CocoAnnotation>,
+}
+
+impl CocoDataset {
+  /// 由类别名称表创建空数据集，第 i 个类别的编号为 i + 1
+  pub fn new(class_names: &ClassNames) -> Self {
+    Self {
+      images: Vec::new(),
+      categories: class_names
+        .names()
+        .iter()
+        .enumerate()
+        .map(|(i, name)| CocoCategory {
+          id: i as u64 + 1,
+          name: name.clone(),
+        })
+        .collect(),
+      annotations: Vec::new(),
+    }
+  }
+
+  /// 解析 COCO JSON 标注，只读取 images、categories 与 annotations 中的检测相关字段
+  pub fn parse(json: &str) -> Result<Self, AnnotationError> {
+    let root: Value = serde_json::from_str(json)?;
+
+    let images = array(&root, "images")?
+      .iter()
+      .map(|image| {
+        Ok(CocoImage {
+          id: integer(image, "id")?,
+          file_name: string(image, "file_name")?,
+          width: dimension(image, "width")?,
+          height: dimension(image, "height")?,
+        })
+      })
+      .collect::<Result<Vec<_>, AnnotationError>>()?;
+
+    let mut categories = array(&root, "categories")?
+      .iter()
+      .map(|category| {
+        Ok(CocoCategory {
+          id: integer(category, "id")?,
+          name: string(category, "name")?,
+        })
+      })
+      .collect::<Result<Vec<_>, AnnotationError>>()?;
+    categories.sort_by_key(|c| c.id);
+
+    // 检测结果文件等只包含部分字段的数据集中 annotations 可以缺省
+    let annotations = match root.get("annotations") {
+      None => Vec::new(),
+      Some(_) => array(&root, "annotations")?
+        .iter()
+        .map(|annotation| {
+          let bbox = bbox(annotation)?;
+          Ok(CocoAnnotation {
+            id: integer(annotation, "id")?,
+            image_id: integer(annotation, "image_id")?,
+            category_id: integer(annotation, "category_id")?,
+            bbox,
+            area: annotation
+              .get("area")
+              .and_then(Value::as_f64)
+              .map_or(bbox[2] * bbox[3], |v| v as f32),
+            crowd: annotation
+              .get("iscrowd")
+              .and_then(Value::as_u64)
+              .is_some_and(|v| v != 0),
+          })
+        })
+        .collect::<Result<Vec<_>, AnnotationError>>()?,
+    };
+
+    Ok(Self {
+      images,
+      categories,
+      annotations,
+    })
+  }
+
+  /// 输出 COCO JSON 文本
+  pub fn to_json(&self) -> String {
+    let images: Vec<Value> = self
+      .images
+      .iter()
+      .map(|image| {
+        json!({
+          "id": image.id,
+          "file_name": image.file_name,
+          "width": image.width,
+          "height": image.height,
+        })
+      })
+      .collect();
+    let categories: Vec<Value> = self
+      .categories
+      .iter()
+      .map(|category| json!({ "id": category.id, "name": category.name }))
+      .collect();
+    let annotations: Vec<Value> = self
+      .annotations
+      .iter()
+      .map(|annotation| {
+        json!({
+          "id": annotation.id,
+          "image_id": annotation.image_id,
+          "category_id": annotation.category_id,
+          "bbox": annotation.bbox.map(number),
+          "area": number(annotation.area),
+          "iscrowd": annotation.crowd as u32,
+        })
+      })
+      .collect();
+    json!({
+      "images": images,
+      "categories": categories,
+      "annotations": annotations,
+    })
+    .to_string()
+  }
+
+  /// 类别名称表，顺序与类别索引一致
+  pub fn class_names(&self) -> ClassNames {
+    ClassNames::new(self.categories.iter().map(|c| c.name.clone()).collect())
+  }
+
+  /// COCO 类别编号对应的类别索引
+  pub fn class_id(&self, category_id: u64) -> Option<u32> {
+    self
+      .categories
+      .iter()
+      .position(|c| c.id == category_id)
+      .map(|i| i as u32)
+  }
+
+  /// 类别索引对应的 COCO 类别编号
+  pub fn category_id(&self, class_id: u32) -> Option<u64> {
+    self.categories.get(class_id as usize).map(|c| c.id)
+  }
+
+  pub fn image(&self, image_id: u64) -> Option<&CocoImage> {
+    self.images.iter().find(|image| image.id == image_id)
+  }
+
+  /// 读取一张图像的标注，坐标按 unit 输出，归一化时使用图像的宽高
+  pub fn ground_truths(
+    &self,
+    image_id: u64,
+    unit: BoxUnit,
+  ) -> Result<Vec<GroundTruth>, AnnotationError> {
+    let image = self.find_image(image_id)?;
+    self
+      .annotations
+      .iter()
+      .filter(|annotation| annotation.image_id == image_id)
+      .map(|annotation| {
+        let class_id = self.class_id(annotation.category_id).ok_or_else(|| {
+          AnnotationError::InvalidInput(format!("未知的类别编号 {}", annotation.category_id))
+        })?;
+        let [x, y, w, h] = annotation.bbox;
+        Ok(GroundTruth {
+          bbox: scale([x, y, x + w, y + h], image, BoxUnit::Pixel, unit)?,
+          class_id,
+          crowd: annotation.crowd,
+        })
+      })
+      .collect()
+  }
+
+  /// 添加一张图像及其标注，标注编号在已有标注之后递增
+  /// unit 为 Normalized 时按图像的宽高转换为像素坐标
+  pub fn add_image(
+    &mut self,
+    image: CocoImage,
+    ground_truths: &[GroundTruth],
+    unit: BoxUnit,
+  ) -> Result<(), AnnotationError> {
+    if self.image(image.id).is_some() {
+      return Err(AnnotationError::InvalidInput(format!(
+        "图像编号 {} 已存在",
+        image.id
+      )));
+    }
+    let first_id = self.annotations.iter().map(|a| a.id).max().unwrap_or(0) + 1;
+    let annotations = (first_id..)
+      .zip(ground_truths.iter())
+      .map(|(id, gt)| {
+        let [xmin, ymin, xmax, ymax] = scale(gt.bbox, &image, unit, BoxUnit::Pixel)?;
+        Ok(CocoAnnotation {
+          id,
+          image_id: image.id,
+          category_id: self.find_category(gt.class_id)?,
+          bbox: [xmin, ymin, xmax - xmin, ymax - ymin],
+          area: (xmax - xmin) * (ymax - ymin),
+          crowd: gt.crowd,
+        })
+      })
+      .collect::<Result<Vec<_>, AnnotationError>>()?;
+    self.annotations.extend(annotations);
+    self.images.push(image);
+    Ok(())
+  }
+
+  /// 将检测结果输出为 COCO 检测结果格式的 JSON 数组
+  /// image_ids: 批次中每张图像对应的 COCO 图像编号，归一化坐标按图像的宽高转换为像素坐标
+  pub fn results_to_json(
+    &self,
+    detections: &DetectionSet,
+    image_ids: &[u64],
+  ) -> Result<String, AnnotationError> {
+    if image_ids.len() != detections.batch_size() {
+      return Err(AnnotationError::InvalidInput(format!(
+        "图像编号数量 {} 与检测结果的批次大小 {} 不一致",
+        image_ids.len(),
+        detections.batch_size()
+      )));
+    }
+
+    let mut results = Vec::with_capacity(detections.len());
+    for (b, &image_id) in image_ids.iter().enumerate() {
+      let image = self.find_image(image_id)?;
+      for d in detections.batch(b) {
+        let [xmin, ymin, xmax, ymax] = scale(d.bbox, image, detections.unit(), BoxUnit::Pixel)?;
+        let bbox = [xmin, ymin, xmax - xmin, ymax - ymin].map(number);
+        results.push(json!({
+          "image_id": image_id,
+          "category_id": self.find_category(d.class_id)?,
+          "bbox": bbox,
+          "score": number(d.score),
+        }));
+      }
+    }
+    Ok(Value::Array(results).to_string())
+  }
+
+  /// 读取 COCO 检测结果格式的 JSON 数组，图像在批次中的位置由 image_ids 给出，坐标按 unit 输出
+  pub fn parse_results(
+    &self,
+    json: &str,
+    image_ids: &[u64],
+    unit: BoxUnit,
+  ) -> Result<DetectionSet, AnnotationError> {
+    let root: Value = serde_json::from_str(json)?;
+    let Some(results) = root.as_array() else {
+      return Err(AnnotationError::ParseError(
+        "检测结果应为 JSON 数组".to_string(),
+      ));
+    };
+
+    let mut detections = Vec::with_capacity(results.len());
+    for result in results {
+      let image_id = integer(result, "image_id")?;
+      let Some(batch_index) = image_ids.iter().position(|&id| id == image_id) else {
+        return Err(AnnotationError::InvalidInput(format!(
+          "图像编号 {} 不在给定的图像列表中",
+          image_id
+        )));
+      };
+      let image = self.find_image(image_id)?;
+      let category_id = integer(result, "category_id")?;
+      let class_id = self
+        .class_id(category_id)
+        .ok_or_else(|| AnnotationError::InvalidInput(format!("未知的类别编号 {}", category_id)))?;
+      let [x, y, w, h] = bbox(result)?;
+      detections.push(Detection {
+        bbox: scale([x, y, x + w, y + h], image, BoxUnit::Pixel, unit)?,
+        score: float(result, "score")?,
+        class_id,
+        batch_index,
+      });
+    }
+    Ok(DetectionSet::new(detections, image_ids.len(), unit))
+  }
+
+  fn find_image(&self, image_id: u64) -> Result<&CocoImage, AnnotationError> {
+    self
+      .image(image_id)
+      .ok_or_else(|| AnnotationError::InvalidInput(format!("未知的图像编号 {}", image_id)))
+  }
+
+  fn find_category(&self, class_id: u32) -> Result<u64, AnnotationError> {
+    self
+      .category_id(class_id)
+      .ok_or_else(|| AnnotationError::InvalidInput(format!("类别索引 {} 超出类别表", class_id)))
+  }
+}
+
+/// 在像素坐标与归一化坐标之间转换边界框，需要转换时图像宽高不能为 0
+fn scale(
+  bbox: [f32; 4],
+  image: &CocoImage,
+  from: BoxUnit,
+  to: BoxUnit,
+) -> Result<[f32; 4], AnnotationError> {
+  if from == to {
+    return Ok(bbox);
+  }
+  if image.width == 0 || image.height == 0 {
+    return Err(AnnotationError::InvalidInput(format!(
+      "图像 {} 的尺寸为 {}x{}，无法转换归一化坐标",
+      image.id, image.width, image.height
+    )));
+  }
+  let (w, h) = (image.width as f32, image.height as f32);
+  Ok(match to {
+    BoxUnit::Normalized => [bbox[0] / w, bbox[1] / h, bbox[2] / w, bbox[3] / h],
+    BoxUnit::Pixel => [bbox[0] * w, bbox[1] * h, bbox[2] * w, bbox[3] * h],
+  })
+}
+
+/// 以 f32 的最短十进制表示输出数值，避免转换为 f64 后出现多余的位数
+fn number(v: f32) -> Value {
+  v.to_string()
+    .parse::<f64>()
+    .ok()
+    .and_then(serde_json::Number::from_f64)
+    .map_or(Value::Null, Value::Number)
+}
+
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value, AnnotationError> {
+  value
+    .as_object()
+    .and_then(|object: &Map<String, Value>| object.get(key))
+    .ok_or_else(|| AnnotationError::ParseError(format!("缺少字段 {}", key)))
+}
+
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>, AnnotationError> {
+  field(value, key)?
+    .as_array()
+    .ok_or_else(|| AnnotationError::ParseError(format!("字段 {} 应为数组", key)))
+}
+
+fn integer(value: &Value, key: &str) -> Result<u64, AnnotationError> {
+  field(value, key)?
+    .as_u64()
+    .ok_or_else(|| AnnotationError::ParseError(format!("字段 {} 应为非负整数", key)))
+}
+
+/// 读取图像宽高等不超过 u32 范围的非负整数
+fn dimension(value: &Value, key: &str) -> Result<u32, AnnotationError> {
+  u32::try_from(integer(value, key)?)
+    .map_err(|_| AnnotationError::ParseError(format!("字段 {} 超出 u32 范围", key)))
+}
+
+fn float(value: &Value, key: &str) -> Result<f32, AnnotationError> {
+  field(value, key)?
+    .as_f64()
+    .map(|v| v as f32)
+    .ok_or_else(|| AnnotationError::ParseError(format!("字段 {} 应为数值", key)))
+}
+
+fn string(value: &Value, key: &str) -> Result<String, AnnotationError> {
+  field(value, key)?
+    .as_str()
+    .map(String::from)
+    .ok_or_else(|| AnnotationError::ParseError(format!("字段 {} 应为字符串", key)))
+}
+
+/// 读取 (x, y, w, h) 格式的 bbox 字段
+fn bbox(value: &Value) -> Result<[f32; 4], AnnotationError> {
+  let values = array(value, "bbox")?;
+  let parsed: Option<Vec<f32>> = values
+    .iter()
+    .map(|v| v.as_f64().map(|v| v as f32))
+    .collect();
+  match parsed.as_deref() {
+    Some(&[x, y, w, h]) => Ok([x, y, w, h]),
+    _ => Err(AnnotationError::ParseError(
+      "字段 bbox 应为 4 个数值".to_string(),
+    )),
+  }
+}
@@ -0,0 +1,156 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/annotation/yolo.rs - YOLO txt 标注格式
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use crate::{
+  annotation::AnnotationError,
+  eval::GroundTruth,
+  postprocess::detection::{BoxUnit, Detection},
+};
+
+/// YOLO txt 中的一行，文件中为 `class cx cy w h [score]`，坐标相对于图像宽高归一化
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YoloLabel {
+  pub class_id: u32,
+  /// 归一化的边界框 (xmin, ymin, xmax, ymax)
+  pub bbox: [f32; 4],
+  /// 检测得分，标注文件中通常不包含
+  pub score: Option<f32>,
+}
+
+impl YoloLabel {
+  /// 由检测结果构造，unit 为 Pixel 时 width/height 为坐标所在的图像尺寸且不能为 0，为 Normalized 时忽略
+  pub fn from_detection(
+    detection: &Detection,
+    unit: BoxUnit,
+    width: u32,
+    height: u32,
+  ) -> Result<Self, AnnotationError> {
+    Ok(Self {
+      class_id: detection.class_id,
+      bbox: to_normalized(detection.bbox, unit, width, height)?,
+      score: Some(detection.score),
+    })
+  }
+
+  /// 转换为检测结果，坐标按 unit 输出，没有得分时为 1
+  pub fn to_detection(
+    &self,
+    batch_index: usize,
+    unit: BoxUnit,
+    width: u32,
+    height: u32,
+  ) -> Detection {
+    Detection {
+      bbox: from_normalized(self.bbox, unit, width, height),
+      score: self.score.unwrap_or(1.0),
+      class_id: self.class_id,
+      batch_index,
+    }
+  }
+
+  /// 转换为评估使用的标注，坐标按 unit 输出
+  pub fn to_ground_truth(&self, unit: BoxUnit, width: u32, height: u32) -> GroundTruth {
+    GroundTruth::new(
+      from_normalized(self.bbox, unit, width, height),
+      self.class_id,
+    )
+  }
+}
+
+/// 解析 YOLO txt 文本，忽略空行
+pub fn parse_yolo(text: &str) -> Result<Vec<YoloLabel>, AnnotationError> {
+  let mut labels = Vec::new();
+  for (line_no, line) in text.lines().enumerate() {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.is_empty() {
+      continue;
+    }
+    if fields.len() != 5 && fields.len() != 6 {
+      return Err(AnnotationError::ParseError(format!(
+        "第 {} 行应包含 5 或 6 个字段，实际为 {}",
+        line_no + 1,
+        fields.len()
+      )));
+    }
+
+    let class_id = fields[0].parse::<u32>().map_err(|e| {
+      AnnotationError::ParseError(format!("第 {} 行类别索引无效: {}", line_no + 1, e))
+    })?;
+    let mut values = [0.0f32; 5];
+    for (v, field) in values.iter_mut().zip(fields[1..].iter()) {
+      *v = field.parse::<f32>().map_err(|e| {
+        AnnotationError::ParseError(format!("第 {} 行数值无效: {}", line_no + 1, e))
+      })?;
+    }
+
+    let [cx, cy, w, h, score] = values;
+    labels.push(YoloLabel {
+      class_id,
+      bbox: [cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0],
+      score: (fields.len() == 6).then_some(score),
+    });
+  }
+  Ok(labels)
+}
+
+/// 输出 YOLO txt 文本，每个标注一行，包含得分时追加在最后一列
+pub fn format_yolo(labels: &[YoloLabel]) -> String {
+  labels
+    .iter()
+    .map(|label| {
+      let [xmin, ymin, xmax, ymax] = label.bbox;
+      let mut line = format!(
+        "{} {:.6} {:.6} {:.6} {:.6}",
+        label.class_id,
+        (xmin + xmax) / 2.0,
+        (ymin + ymax) / 2.0,
+        xmax - xmin,
+        ymax - ymin
+      );
+      if let Some(score) = label.score {
+        line.push_str(&format!(" {:.6}", score));
+      }
+      line.push('\n');
+      line
+    })
+    .collect()
+}
+
+fn to_normalized(
+  bbox: [f32; 4],
+  unit: BoxUnit,
+  width: u32,
+  height: u32,
+) -> Result<[f32; 4], AnnotationError> {
+  match unit {
+    BoxUnit::Normalized => Ok(bbox),
+    BoxUnit::Pixel if width == 0 || height == 0 => Err(AnnotationError::InvalidInput(format!(
+      "图像尺寸为 {}x{}，无法转换归一化坐标",
+      width, height
+    ))),
+    BoxUnit::Pixel => {
+      let (w, h) = (width as f32, height as f32);
+      Ok([bbox[0] / w, bbox[1] / h, bbox[2] / w, bbox[3] / h])
+    }
+  }
+}
+
+fn from_normalized(bbox: [f32; 4], unit: BoxUnit, width: u32, height: u32) -> [f32; 4] {
+  match unit {
+    BoxUnit::Normalized => bbox,
+    BoxUnit::Pixel => {
+      let (w, h) = (width as f32, height as f32);
+      [bbox[0] * w, bbox[1] * h, bbox[2] * w, bbox[3] * h]
+    }
+  }
+}
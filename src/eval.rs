@@ -0,0 +1,538 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/eval.rs - COCO 风格的目标检测评估 (mAP 与 PR 曲线)
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use thiserror::Error;
+
+use crate::postprocess::{
+  detection::{Detection, DetectionSet},
+  geometry::{box_area, box_intersection, box_iou},
+};
+
+#[derive(Debug, Error)]
+pub enum EvalError {
+  #[error("无效的输入: {0}")]
+  InvalidInput(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+}
+
+/// 单个标注框
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundTruth {
+  /// 边界框 (xmin, ymin, xmax, ymax)
+  pub bbox: [f32; 4],
+  /// 类别索引
+  pub class_id: u32,
+  /// 是否为人群区域 (COCO 的 iscrowd)，与其重叠的检测既不计为正确也不计为错误
+  pub crowd: bool,
+}
+
+impl GroundTruth {
+  pub fn new(bbox: [f32; 4], class_id: u32) -> Self {
+    Self {
+      bbox,
+      class_id,
+      crowd: false,
+    }
+  }
+}
+
+/// 召回率插值点的数量，与 COCO 一致为 0 到 1 的 101 个点
+const RECALL_POINTS: usize = 101;
+
+/// 评估使用的面积范围，依次为 all、small、medium、large
+const AREA_RANGES: [(f32, f32); 4] = [
+  (0.0, 1e10),
+  (0.0, 32.0 * 32.0),
+  (32.0 * 32.0, 96.0 * 96.0),
+  (96.0 * 96.0, 1e10),
+];
+
+pub struct EvaluatorConfig {
+  iou_thresholds: Vec<f32>,
+  max_detections: usize,
+}
+
+impl Default for EvaluatorConfig {
+  fn default() -> Self {
+    Self {
+      iou_thresholds: (0..10).map(|i| 0.5 + 0.05 * i as f32).collect(),
+      max_detections: 100,
+    }
+  }
+}
+
+impl EvaluatorConfig {
+  /// 交并比阈值，默认为 0.5:0.05:0.95；AP50/AP75 需要阈值中包含 0.5/0.75
+  pub fn with_iou_thresholds(mut self, iou_thresholds: Vec<f32>) -> Self {
+    self.iou_thresholds = iou_thresholds;
+    self
+  }
+
+  /// 每张图像每个类别最多参与评估的检测数量
+  pub fn with_max_detections(mut self, max_detections: usize) -> Self {
+    self.max_detections = max_detections;
+    self
+  }
+
+  pub fn build(self) -> Result<Evaluator, EvalError> {
+    if self.iou_thresholds.is_empty()
+      || self.iou_thresholds.iter().any(|t| !(0.0..=1.0).contains(t))
+    {
+      return Err(EvalError::InvalidConfig(
+        "交并比阈值不能为空且必须在 [0, 1] 内".to_string(),
+      ));
+    }
+    if self.max_detections == 0 {
+      return Err(EvalError::InvalidConfig(
+        "max_detections 必须大于 0".to_string(),
+      ));
+    }
+    Ok(Evaluator {
+      iou_thresholds: self.iou_thresholds,
+      max_detections: self.max_detections,
+      images: Vec::new(),
+    })
+  }
+}
+
+struct Image {
+  detections: Vec<Detection>,
+  ground_truths: Vec<GroundTruth>,
+}
+
+/// 单张图像在某个类别与面积范围下的匹配结果
+struct ImageEval {
+  /// 检测得分，按降序排列
+  scores: Vec<f32>,
+  /// 每个交并比阈值下检测是否匹配到标注，[T][D]
+  matched: Vec<Vec<bool>>,
+  /// 每个交并比阈值下检测是否被忽略，[T][D]
+  ignored: Vec<Vec<bool>>,
+  /// 未被忽略的标注数量
+  num_gt: usize,
+}
+
+/// 目标检测评估器，逐张图像累积检测结果与标注，计算 COCO 风格的指标
+///
+/// 匹配规则与 pycocotools 的 COCOeval 一致：检测按得分降序依次匹配交并比最大且未被占用的标注，
+/// 面积超出范围或为人群区域的标注被忽略，匹配到被忽略标注的检测以及面积超出范围的未匹配检测不参与统计。
+/// 面积范围按 COCO 以像素为单位划分，评估 small/medium/large 时坐标应为像素坐标。
+pub struct Evaluator {
+  iou_thresholds: Vec<f32>,
+  max_detections: usize,
+  images: Vec<Image>,
+}
+
+impl Evaluator {
+  /// 添加一张图像的检测结果与标注
+  pub fn add_image(&mut self, detections: &[Detection], ground_truths: &[GroundTruth]) {
+    self.images.push(Image {
+      detections: detections.to_vec(),
+      ground_truths: ground_truths.to_vec(),
+    });
+  }
+
+  /// 添加一个批次的检测结果 (如 DetectionSet::from_candidates 读取的 NMS 输出)
+  /// ground_truths: 每张图像的标注，长度需与批次大小一致
+  pub fn add_set(
+    &mut self,
+    detections: &DetectionSet,
+    ground_truths: &[Vec<GroundTruth>],
+  ) -> Result<(), EvalError> {
+    if detections.batch_size() != ground_truths.len() {
+      return Err(EvalError::InvalidInput(format!(
+        "标注的图像数量 {} 与检测结果的批次大小 {} 不一致",
+        ground_truths.len(),
+        detections.batch_size()
+      )));
+    }
+    for (b, gts) in ground_truths.iter().enumerate() {
+      self.add_image(detections.batch(b), gts);
+    }
+    Ok(())
+  }
+
+  /// 已添加的图像数量
+  pub fn len(&self) -> usize {
+    self.images.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.images.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.images.clear();
+  }
+
+  /// 计算所有已添加图像上的评估指标
+  pub fn evaluate(&self) -> EvalResult {
+    let mut classes: Vec<u32> = self
+      .images
+      .iter()
+      .flat_map(|image| {
+        image
+          .detections
+          .iter()
+          .map(|d| d.class_id)
+          .chain(image.ground_truths.iter().map(|g| g.class_id))
+      })
+      .collect();
+    classes.sort_unstable();
+    classes.dedup();
+
+    let t_dim = self.iou_thresholds.len();
+    let k_dim = classes.len();
+    let a_dim = AREA_RANGES.len();
+    let mut precision = vec![-1.0; t_dim * RECALL_POINTS * k_dim * a_dim];
+    let mut scores = vec![-1.0; t_dim * RECALL_POINTS * k_dim * a_dim];
+    let mut recall = vec![-1.0; t_dim * k_dim * a_dim];
+    let recall_thresholds: Vec<f32> = (0..RECALL_POINTS)
+      .map(|r| r as f32 / (RECALL_POINTS - 1) as f32)
+      .collect();
+
+    for (k, &class_id) in classes.iter().enumerate() {
+      for (a, &range) in AREA_RANGES.iter().enumerate() {
+        let evals: Vec<ImageEval> = self
+          .images
+          .iter()
+          .filter_map(|image| self.evaluate_image(image, class_id, range))
+          .collect();
+        let num_gt: usize = evals.iter().map(|e| e.num_gt).sum();
+        if num_gt == 0 {
+          continue;
+        }
+
+        // 合并所有图像的检测并按得分稳定降序排列
+        let entries: Vec<(usize, usize)> = evals
+          .iter()
+          .enumerate()
+          .flat_map(|(i, e)| (0..e.scores.len()).map(move |d| (i, d)))
+          .collect();
+        let mut order: Vec<usize> = (0..entries.len()).collect();
+        order.sort_by(|&x, &y| {
+          let (ix, dx) = entries[x];
+          let (iy, dy) = entries[y];
+          evals[iy].scores[dy].total_cmp(&evals[ix].scores[dx])
+        });
+        let sorted_scores: Vec<f32> = order
+          .iter()
+          .map(|&o| evals[entries[o].0].scores[entries[o].1])
+          .collect();
+
+        for t in 0..t_dim {
+          let mut tp = 0usize;
+          let mut fp = 0usize;
+          let mut rc = Vec::with_capacity(order.len());
+          let mut pr = Vec::with_capacity(order.len());
+          for &o in &order {
+            let (i, d) = entries[o];
+            if !evals[i].ignored[t][d] {
+              if evals[i].matched[t][d] {
+                tp += 1;
+              } else {
+                fp += 1;
+              }
+            }
+            rc.push(tp as f32 / num_gt as f32);
+            pr.push(tp as f32 / (tp + fp).max(1) as f32);
+          }
+
+          recall[(t * k_dim + k) * a_dim + a] = rc.last().copied().unwrap_or(0.0);
+
+          // 精度取右侧最大值，使曲线单调不增
+          for i in (1..pr.len()).rev() {
+            pr[i - 1] = pr[i - 1].max(pr[i]);
+          }
+          for (r, &threshold) in recall_thresholds.iter().enumerate() {
+            let pos = rc.partition_point(|&v| v < threshold);
+            let offset = ((t * RECALL_POINTS + r) * k_dim + k) * a_dim + a;
+            if pos < pr.len() {
+              precision[offset] = pr[pos];
+              scores[offset] = sorted_scores[pos];
+            } else {
+              precision[offset] = 0.0;
+              scores[offset] = 0.0;
+            }
+          }
+        }
+      }
+    }
+
+    EvalResult {
+      iou_thresholds: self.iou_thresholds.clone(),
+      classes,
+      precision,
+      scores,
+      recall,
+    }
+  }
+
+  /// 匹配一张图像在某个类别与面积范围下的检测与标注，没有检测也没有标注时返回 None
+  fn evaluate_image(&self, image: &Image, class_id: u32, range: (f32, f32)) -> Option<ImageEval> {
+    let outside = |bbox: [f32; 4]| {
+      let area = box_area(bbox);
+      area < range.0 || area > range.1
+    };
+
+    // 未被忽略的标注排在前面
+    let mut gts: Vec<(GroundTruth, bool)> = image
+      .ground_truths
+      .iter()
+      .filter(|g| g.class_id == class_id)
+      .map(|g| (*g, g.crowd || outside(g.bbox)))
+      .collect();
+    gts.sort_by_key(|&(_, ignore)| ignore);
+
+    let mut dts: Vec<&Detection> = image
+      .detections
+      .iter()
+      .filter(|d| d.class_id == class_id)
+      .collect();
+    dts.sort_by(|a, b| b.score.total_cmp(&a.score));
+    dts.truncate(self.max_detections);
+
+    if gts.is_empty() && dts.is_empty() {
+      return None;
+    }
+
+    let ious: Vec<Vec<f32>> = dts
+      .iter()
+      .map(|d| {
+        gts
+          .iter()
+          .map(|(g, _)| overlap(d.bbox, g.bbox, g.crowd))
+          .collect()
+      })
+      .collect();
+
+    let t_dim = self.iou_thresholds.len();
+    let mut matched = vec![vec![false; dts.len()]; t_dim];
+    let mut ignored = vec![vec![false; dts.len()]; t_dim];
+    for (t, &threshold) in self.iou_thresholds.iter().enumerate() {
+      let mut gt_taken = vec![false; gts.len()];
+      for (d, detection) in dts.iter().enumerate() {
+        let mut best = threshold;
+        let mut m: Option<usize> = None;
+        for (g, &(gt, ignore)) in gts.iter().enumerate() {
+          // 人群区域可以被多次匹配
+          if gt_taken[g] && !gt.crowd {
+            continue;
+          }
+          // 已匹配到有效标注时不再考虑被忽略的标注
+          if let Some(current) = m
+            && !gts[current].1
+            && ignore
+          {
+            break;
+          }
+          if ious[d][g] < best {
+            continue;
+          }
+          best = ious[d][g];
+          m = Some(g);
+        }
+        match m {
+          Some(g) => {
+            gt_taken[g] = true;
+            matched[t][d] = true;
+            ignored[t][d] = gts[g].1;
+          }
+          None => ignored[t][d] = outside(detection.bbox),
+        }
+      }
+    }
+
+    Some(ImageEval {
+      scores: dts.iter().map(|d| d.score).collect(),
+      matched,
+      ignored,
+      num_gt: gts.iter().filter(|(_, ignore)| !ignore).count(),
+    })
+  }
+}
+
+/// 面积范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaRange {
+  All,
+  /// 面积小于 32²
+  Small,
+  /// 面积在 32² 与 96² 之间
+  Medium,
+  /// 面积大于 96²
+  Large,
+}
+
+impl AreaRange {
+  fn index(self) -> usize {
+    match self {
+      AreaRange::All => 0,
+      AreaRange::Small => 1,
+      AreaRange::Medium => 2,
+      AreaRange::Large => 3,
+    }
+  }
+}
+
+/// 单个类别的评估结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClassResult {
+  pub class_id: u32,
+  /// AP@[.5:.95]，没有标注时为 -1
+  pub ap: f32,
+  /// AP@.5，没有标注或阈值中不包含 0.5 时为 -1
+  pub ap50: f32,
+}
+
+/// 插值后的精度-召回率曲线
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrCurve {
+  /// 召回率插值点，为 0 到 1 的 101 个点
+  pub recall: Vec<f32>,
+  /// 各召回率下的最大精度
+  pub precision: Vec<f32>,
+  /// 达到该召回率时的得分阈值
+  pub score: Vec<f32>,
+}
+
+/// 评估结果，保存 [T, R, K, A] 的插值精度，T/R/K/A 分别为交并比阈值、召回率插值点、类别与面积范围
+#[derive(Debug, Clone)]
+pub struct EvalResult {
+  iou_thresholds: Vec<f32>,
+  classes: Vec<u32>,
+  precision: Vec<f32>,
+  scores: Vec<f32>,
+  recall: Vec<f32>,
+}
+
+impl EvalResult {
+  /// 参与评估的类别，升序排列
+  pub fn classes(&self) -> &[u32] {
+    &self.classes
+  }
+
+  /// COCO mAP@[.5:.95]，没有任何标注时为 -1
+  pub fn map(&self) -> f32 {
+    self.average(None, None, AreaRange::All)
+  }
+
+  /// mAP@.5，阈值中不包含 0.5 时为 -1
+  pub fn map50(&self) -> f32 {
+    self
+      .threshold_index(0.5)
+      .map_or(-1.0, |t| self.average(Some(t), None, AreaRange::All))
+  }
+
+  /// mAP@.75，阈值中不包含 0.75 时为 -1
+  pub fn map75(&self) -> f32 {
+    self
+      .threshold_index(0.75)
+      .map_or(-1.0, |t| self.average(Some(t), None, AreaRange::All))
+  }
+
+  /// 指定面积范围内的 mAP@[.5:.95]，该范围内没有标注时为 -1
+  pub fn map_area(&self, area: AreaRange) -> f32 {
+    self.average(None, None, area)
+  }
+
+  /// 每个类别的 AP
+  pub fn per_class(&self) -> Vec<ClassResult> {
+    let t50 = self.threshold_index(0.5);
+    (0..self.classes.len())
+      .map(|k| ClassResult {
+        class_id: self.classes[k],
+        ap: self.average(None, Some(k), AreaRange::All),
+        ap50: t50.map_or(-1.0, |t| self.average(Some(t), Some(k), AreaRange::All)),
+      })
+      .collect()
+  }
+
+  /// 某个类别在指定交并比阈值下的最终召回率，类别没有标注时为 -1，类别不存在或阈值不在评估范围内时返回 None
+  pub fn recall(&self, class_id: u32, iou_threshold: f32) -> Option<f32> {
+    let k = self.classes.binary_search(&class_id).ok()?;
+    let t = self.threshold_index(iou_threshold)?;
+    let a_dim = AREA_RANGES.len();
+    Some(self.recall[(t * self.classes.len() + k) * a_dim])
+  }
+
+  /// 某个类别在指定交并比阈值下的 PR 曲线，类别没有标注或阈值不在评估范围内时返回 None
+  pub fn pr_curve(&self, class_id: u32, iou_threshold: f32) -> Option<PrCurve> {
+    let k = self.classes.binary_search(&class_id).ok()?;
+    let t = self.threshold_index(iou_threshold)?;
+    let offset = |r: usize| ((t * RECALL_POINTS + r) * self.classes.len() + k) * AREA_RANGES.len();
+    if self.precision[offset(0)] < 0.0 {
+      return None;
+    }
+    Some(PrCurve {
+      recall: (0..RECALL_POINTS)
+        .map(|r| r as f32 / (RECALL_POINTS - 1) as f32)
+        .collect(),
+      precision: (0..RECALL_POINTS)
+        .map(|r| self.precision[offset(r)])
+        .collect(),
+      score: (0..RECALL_POINTS).map(|r| self.scores[offset(r)]).collect(),
+    })
+  }
+
+  fn threshold_index(&self, threshold: f32) -> Option<usize> {
+    self
+      .iou_thresholds
+      .iter()
+      .position(|t| (t - threshold).abs() < 1e-6)
+  }
+
+  /// 对有效的插值精度求平均，t/k 为 None 时包含所有交并比阈值/类别
+  fn average(&self, t: Option<usize>, k: Option<usize>, area: AreaRange) -> f32 {
+    let k_dim = self.classes.len();
+    let a = area.index();
+    let mut sum = 0.0f64;
+    let mut count = 0usize;
+    for ti in 0..self.iou_thresholds.len() {
+      if t.is_some_and(|t| t != ti) {
+        continue;
+      }
+      for r in 0..RECALL_POINTS {
+        for ki in 0..k_dim {
+          if k.is_some_and(|k| k != ki) {
+            continue;
+          }
+          let v = self.precision[((ti * RECALL_POINTS + r) * k_dim + ki) * AREA_RANGES.len() + a];
+          if v > -1.0 {
+            sum += v as f64;
+            count += 1;
+          }
+        }
+      }
+    }
+    if count == 0 {
+      -1.0
+    } else {
+      (sum / count as f64) as f32
+    }
+  }
+}
+
+/// 检测框与标注框的交并比，标注为人群区域时使用交集与检测框面积之比
+fn overlap(detection: [f32; 4], gt: [f32; 4], crowd: bool) -> f32 {
+  if !crowd {
+    return box_iou(detection, gt);
+  }
+  let area = box_area(detection);
+  if area > 0.0 {
+    box_intersection(detection, gt) / area
+  } else {
+    0.0
+  }
+}
@@ -11,5 +11,14 @@
 //
 // Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
 
+mod compact;
+mod geometry;
 mod nn;
-pub use nn::sigmoid;
+mod sample;
+pub use compact::{
+  block_max, block_sum, compact_candidates, compact_top_k, gather, gather_channels, sort_by_score,
+  top_k,
+};
+pub use geometry::{iou, rotated_iou};
+pub use nn::{sigmoid, softmax};
+pub use sample::{crop_bilinear, resize_bilinear};
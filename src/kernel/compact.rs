@@ -0,0 +1,693 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/kernel/compact.rs - 基于前缀和的流压缩与 Top-K 选择 Kernel 实现
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+
+/// Cube 内求和，所有线程得到相同的结果
+#[cube]
+pub fn block_sum<N: Numeric>(value: N, shared: &mut SharedMemory<N>) -> N {
+  let unit = UNIT_POS as usize;
+
+  shared[unit] = value;
+  sync_cube();
+
+  let mut offset = CUBE_DIM as usize / 2;
+  while offset > 0 {
+    if unit < offset {
+      let other = shared[unit + offset];
+      shared[unit] += other;
+    }
+    sync_cube();
+    offset /= 2;
+  }
+
+  let total = shared[0];
+  sync_cube();
+  total
+}
+
+/// Cube 内求最大值，所有线程得到相同的结果
+#[cube]
+pub fn block_max<F: Float>(value: F, shared: &mut SharedMemory<F>) -> F {
+  let unit = UNIT_POS as usize;
+
+  shared[unit] = value;
+  sync_cube();
+
+  let mut offset = CUBE_DIM as usize / 2;
+  while offset > 0 {
+    if unit < offset {
+      shared[unit] = F::max(shared[unit], shared[unit + offset]);
+    }
+    sync_cube();
+    offset /= 2;
+  }
+
+  let result = shared[0];
+  sync_cube();
+  result
+}
+
+/// Cube 内的排他前缀和 (Hillis-Steele)，返回 (当前线程的起始偏移, 总和)
+#[cube]
+pub fn block_exclusive_scan(value: u32, shared: &mut SharedMemory<u32>) -> (u32, u32) {
+  let unit = UNIT_POS as usize;
+  let dim = CUBE_DIM as usize;
+
+  shared[unit] = value;
+  sync_cube();
+
+  let mut offset = 1;
+  while offset < dim {
+    let mut prev = 0;
+    if unit >= offset {
+      prev = shared[unit - offset];
+    }
+    sync_cube();
+    shared[unit] += prev;
+    sync_cube();
+    offset *= 2;
+  }
+
+  let inclusive = shared[unit];
+  let total = shared[dim - 1];
+  sync_cube();
+  (inclusive - value, total)
+}
+
+/// 统计 [base, base + m) 范围内得分位于 [low, high) 的元素数量，所有线程得到相同的结果
+#[cube]
+fn count_in_range<F: Float>(
+  score: &Tensor<F>,
+  base: usize,
+  m: usize,
+  low: F,
+  high: F,
+  shared: &mut SharedMemory<u32>,
+) -> u32 {
+  let mut local = 0;
+  let mut i = UNIT_POS as usize;
+  while i < m {
+    let s = score[base + i];
+    if s >= low && s < high {
+      local += 1;
+    }
+    i += CUBE_DIM as usize;
+  }
+  block_sum(local, shared)
+}
+
+/// [base, base + m) 范围内得分位于 [low, high) 的最低与最高得分，所有线程得到相同的结果
+///
+/// 范围内没有元素时返回 (F::max_value(), F::min_value())
+#[cube]
+fn score_extent<F: Float>(
+  score: &Tensor<F>,
+  base: usize,
+  m: usize,
+  low: F,
+  high: F,
+  shared: &mut SharedMemory<F>,
+) -> (F, F) {
+  let mut local_min = F::max_value();
+  let mut local_max = F::min_value();
+  let mut i = UNIT_POS as usize;
+  while i < m {
+    let s = score[base + i];
+    if s >= low && s < high {
+      local_min = F::min(local_min, s);
+      local_max = F::max(local_max, s);
+    }
+    i += CUBE_DIM as usize;
+  }
+  let min_score = -block_max::<F>(-local_min, shared);
+  let max_score = block_max::<F>(local_max, shared);
+  (min_score, max_score)
+}
+
+/// 二分查找 [base, base + m) 范围内第 K 大得分所在的区间，返回 (low, high)
+///
+/// 满足 count(s >= high) <= K，且 [low, high) 中按顺序补齐后恰好为得分不低于 low 的最高 K 个；
+/// 得分不低于 low 的元素不超过 K 个时 high 为最大值，即全部保留。
+/// 每轮取 [low, high) 中实际最低与最高得分的中点，因此阈值为 -inf 或极小时也能收敛，
+/// 区间内只剩一个得分值时提前结束
+#[cube]
+fn top_k_bounds<F: Float>(
+  score: &Tensor<F>,
+  base: usize,
+  m: usize,
+  capacity: u32,
+  score_threshold: F,
+  shared_count: &mut SharedMemory<u32>,
+  shared_score: &mut SharedMemory<F>,
+) -> (F, F) {
+  let infinity = F::max_value();
+  let (min_score, max_score) =
+    score_extent::<F>(score, base, m, score_threshold, infinity, shared_score);
+  let mut low = F::max(score_threshold, min_score);
+  let mut high = infinity;
+
+  // 不变式: count(s >= low) > K，count(s >= high) <= K
+  let total = count_in_range::<F>(score, base, m, low, infinity, shared_count);
+  let above_max = count_in_range::<F>(score, base, m, max_score, infinity, shared_count);
+  if total > capacity {
+    if above_max > capacity {
+      // 最高得分的并列元素已超过 K 个，直接在其中按顺序选取
+      low = max_score;
+    } else {
+      high = max_score;
+    }
+  }
+
+  // total 与 above_max 在 Cube 内一致，循环条件对所有线程相同
+  let two = F::new(comptime!(2.0));
+  let mut done = total <= capacity || above_max > capacity;
+  let mut round: u32 = 0;
+  while !done && round < 64 {
+    let (lo, hi) = score_extent::<F>(score, base, m, low, high, shared_score);
+    // mid 位于 (lo, hi]，无论向哪一侧收缩都至少排除一个得分值
+    let mut mid = lo / two + hi / two;
+    if mid <= lo {
+      mid = hi;
+    }
+    let c = count_in_range::<F>(score, base, m, mid, infinity, shared_count);
+    if lo >= hi {
+      done = true;
+    } else if c > capacity {
+      low = mid;
+    } else {
+      high = mid;
+    }
+    round += 1;
+  }
+
+  (low, high)
+}
+
+/// 当前线程在 [0, m) 中负责的连续区间 [begin, end)
+#[cube]
+fn thread_chunk(m: usize) -> (usize, usize) {
+  let chunk = m.div_ceil(CUBE_DIM as usize);
+  let mut begin = UNIT_POS as usize * chunk;
+  if begin > m {
+    begin = m;
+  }
+  let mut end = begin + chunk;
+  if end > m {
+    end = m;
+  }
+  (begin, end)
+}
+
+/// 将得分位于 [low, high) 的元素按原有顺序写入输出的 [start, capacity) 位置，返回写入的数量
+///
+/// 每个线程负责输入中一段连续的区间，先统计区间内满足条件的数量，
+/// 再通过前缀和得到各自的写入偏移，因此输出保持输入中的相对顺序。
+#[cube]
+#[allow(clippy::too_many_arguments)]
+fn compact_range<F: Float, I: Int>(
+  score: &Tensor<F>,
+  index: &Tensor<I>,
+  bbox: &Tensor<F>,
+  out_score: &mut Tensor<F>,
+  out_index: &mut Tensor<I>,
+  out_bbox: &mut Tensor<F>,
+  out_source: &mut Tensor<u32>,
+  n_idx: usize,
+  low: F,
+  high: F,
+  start: u32,
+  shared: &mut SharedMemory<u32>,
+) -> u32 {
+  let n_dim = score.shape(0);
+  let m = score.len() / n_dim;
+  let k_dim = out_score.len() / n_dim;
+
+  let base = n_idx * m;
+  let bbox_base = n_idx * 4 * m;
+  let out_base = n_idx * k_dim;
+  let out_bbox_base = n_idx * 4 * k_dim;
+
+  // 当前线程负责的连续区间
+  let (begin, end) = thread_chunk(m);
+
+  let mut local = 0;
+  for i in begin..end {
+    let s = score[base + i];
+    if s >= low && s < high {
+      local += 1;
+    }
+  }
+
+  let (offset, total) = block_exclusive_scan(local, shared);
+
+  let capacity = k_dim as u32;
+  let mut pos = start + offset;
+  for i in begin..end {
+    let s = score[base + i];
+    if s >= low && s < high && pos < capacity {
+      let o = pos as usize;
+      out_score[out_base + o] = s;
+      out_index[out_base + o] = index[base + i];
+      out_bbox[out_bbox_base + o] = bbox[bbox_base + i];
+      out_bbox[out_bbox_base + k_dim + o] = bbox[bbox_base + m + i];
+      out_bbox[out_bbox_base + 2 * k_dim + o] = bbox[bbox_base + 2 * m + i];
+      out_bbox[out_bbox_base + 3 * k_dim + o] = bbox[bbox_base + 3 * m + i];
+      out_source[out_base + o] = u32::cast_from(i);
+      pos += 1;
+    }
+  }
+
+  let mut written = 0;
+  if start < capacity {
+    written = capacity - start;
+    if total < written {
+      written = total;
+    }
+  }
+  written
+}
+
+/// 按得分阈值对稠密检测结果进行流压缩，可选地只保留得分最高的 K 个候选
+///
+/// 每个 Cube 处理一张图像，输出容量 K 由 out_score 的形状决定。
+/// top_k 为 true 时通过对得分值二分查找第 K 大的得分，
+/// 先写入严格高于该值的元素，再用与该值相同的元素补齐，保证恰好保留最高的 K 个。
+/// top_k 为 false 时按输入顺序保留前 K 个满足阈值的元素。
+///
+/// score/index: 输入 [N, M]，bbox: 输入 [N, 4, M]，均为紧凑布局
+/// out_*: 输出 [N, K] / [N, 4, K]，out_count: 输出 [N]
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+pub fn compact_candidates<F: Float + CubeScalar, I: Int>(
+  score: &Tensor<F>,
+  index: &Tensor<I>,
+  bbox: &Tensor<F>,
+  out_score: &mut Tensor<F>,
+  out_index: &mut Tensor<I>,
+  out_bbox: &mut Tensor<F>,
+  out_source: &mut Tensor<u32>,
+  out_count: &mut Tensor<u32>,
+  score_threshold: F,
+  #[comptime] top_k: bool,
+  #[comptime] block: usize,
+) {
+  let n_idx = CUBE_POS;
+  let unit = UNIT_POS as usize;
+
+  let n_dim = score.shape(0);
+  let m = score.len() / n_dim;
+  let k_dim = out_score.len() / n_dim;
+  let base = n_idx * m;
+
+  let mut shared_count = SharedMemory::<u32>::new(block);
+  let mut shared_score = SharedMemory::<F>::new(block);
+
+  let infinity = F::max_value();
+  let mut low = score_threshold;
+  let mut high = infinity;
+
+  if comptime!(top_k) {
+    let bounds = top_k_bounds::<F>(
+      score,
+      base,
+      m,
+      k_dim as u32,
+      score_threshold,
+      &mut shared_count,
+      &mut shared_score,
+    );
+    low = bounds.0;
+    high = bounds.1;
+  }
+
+  // 第一轮写入 [high, inf)，第二轮以 [low, high) 补齐
+  let first = compact_range::<F, I>(
+    score,
+    index,
+    bbox,
+    out_score,
+    out_index,
+    out_bbox,
+    out_source,
+    n_idx,
+    high,
+    infinity,
+    0,
+    &mut shared_count,
+  );
+  let second = compact_range::<F, I>(
+    score,
+    index,
+    bbox,
+    out_score,
+    out_index,
+    out_bbox,
+    out_source,
+    n_idx,
+    low,
+    high,
+    first,
+    &mut shared_count,
+  );
+  let kept = first + second;
+
+  // 填充剩余位置并写入有效数量
+  let zero = F::new(comptime!(0.0));
+  let out_base = n_idx * k_dim;
+  let out_bbox_base = n_idx * 4 * k_dim;
+  let mut j = kept as usize + unit;
+  while j < k_dim {
+    out_score[out_base + j] = zero;
+    out_index[out_base + j] = I::cast_from(0);
+    out_bbox[out_bbox_base + j] = zero;
+    out_bbox[out_bbox_base + k_dim + j] = zero;
+    out_bbox[out_bbox_base + 2 * k_dim + j] = zero;
+    out_bbox[out_bbox_base + 3 * k_dim + j] = zero;
+    out_source[out_base + j] = 0;
+    j += CUBE_DIM as usize;
+  }
+
+  if unit == 0 {
+    out_count[n_idx] = kept;
+  }
+}
+
+/// 与 compact_range 相同，但只写入得分与位置
+#[cube]
+#[allow(clippy::too_many_arguments)]
+fn compact_score_range<F: Float>(
+  score: &Tensor<F>,
+  out_score: &mut Tensor<F>,
+  out_source: &mut Tensor<u32>,
+  n_idx: usize,
+  low: F,
+  high: F,
+  start: u32,
+  shared: &mut SharedMemory<u32>,
+) -> u32 {
+  let n_dim = score.shape(0);
+  let m = score.len() / n_dim;
+  let k_dim = out_score.len() / n_dim;
+
+  let base = n_idx * m;
+  let out_base = n_idx * k_dim;
+  let (begin, end) = thread_chunk(m);
+
+  let mut local = 0;
+  for i in begin..end {
+    let s = score[base + i];
+    if s >= low && s < high {
+      local += 1;
+    }
+  }
+
+  let (offset, total) = block_exclusive_scan(local, shared);
+
+  let capacity = k_dim as u32;
+  let mut pos = start + offset;
+  for i in begin..end {
+    let s = score[base + i];
+    if s >= low && s < high && pos < capacity {
+      let o = pos as usize;
+      out_score[out_base + o] = s;
+      out_source[out_base + o] = u32::cast_from(i);
+      pos += 1;
+    }
+  }
+
+  let mut written = 0;
+  if start < capacity {
+    written = capacity - start;
+    if total < written {
+      written = total;
+    }
+  }
+  written
+}
+
+/// 只对得分做 Top-K 流压缩，保留得分不低于阈值的最高 K 个元素的得分与位置
+///
+/// 选取方式与 compact_candidates 的 Top-K 相同，输出保持输入中的相对顺序，
+/// 需要按得分排列时再由 sort_by_score 排序。每个 Cube 处理一张图像
+/// score: 输入 [N, M]，out_score/out_source: 输出 [N, K]，out_count: 输出 [N]
+#[cube(launch)]
+pub fn compact_top_k<F: Float + CubeScalar>(
+  score: &Tensor<F>,
+  out_score: &mut Tensor<F>,
+  out_source: &mut Tensor<u32>,
+  out_count: &mut Tensor<u32>,
+  score_threshold: F,
+  #[comptime] block: usize,
+) {
+  let n_idx = CUBE_POS;
+  let unit = UNIT_POS as usize;
+
+  let n_dim = score.shape(0);
+  let m = score.len() / n_dim;
+  let k_dim = out_score.len() / n_dim;
+  let base = n_idx * m;
+
+  let mut shared_count = SharedMemory::<u32>::new(block);
+  let mut shared_score = SharedMemory::<F>::new(block);
+
+  let infinity = F::max_value();
+  let (low, high) = top_k_bounds::<F>(
+    score,
+    base,
+    m,
+    k_dim as u32,
+    score_threshold,
+    &mut shared_count,
+    &mut shared_score,
+  );
+
+  let first = compact_score_range::<F>(
+    score,
+    out_score,
+    out_source,
+    n_idx,
+    high,
+    infinity,
+    0,
+    &mut shared_count,
+  );
+  let second = compact_score_range::<F>(
+    score,
+    out_score,
+    out_source,
+    n_idx,
+    low,
+    high,
+    first,
+    &mut shared_count,
+  );
+  let kept = first + second;
+
+  let zero = F::new(comptime!(0.0));
+  let out_base = n_idx * k_dim;
+  let mut j = kept as usize + unit;
+  while j < k_dim {
+    out_score[out_base + j] = zero;
+    out_source[out_base + j] = 0;
+    j += CUBE_DIM as usize;
+  }
+
+  if unit == 0 {
+    out_count[n_idx] = kept;
+  }
+}
+
+/// 将每张图像前 count[n] 个元素按得分降序排列，得分相同时位置靠前者在前，其余位置填充为 0
+///
+/// 每个线程处理一个元素，通过统计排在其前面的元素数量得到输出位置，适用于 K 较小的情况
+/// score/source: 输入 [N, K]，count: [N]，out_score/out_source: 输出 [N, K]
+#[cube(launch)]
+pub fn sort_by_score<F: Float>(
+  score: &Tensor<F>,
+  source: &Tensor<u32>,
+  count: &Tensor<u32>,
+  out_score: &mut Tensor<F>,
+  out_source: &mut Tensor<u32>,
+) {
+  let idx = ABSOLUTE_POS;
+  if idx < score.len() {
+    let n_dim = count.len();
+    let k_dim = score.len() / n_dim;
+    let n_idx = idx / k_dim;
+    let k_idx = idx % k_dim;
+    let base = n_idx * k_dim;
+    let valid = count[n_idx] as usize;
+
+    if k_idx < valid {
+      let s = score[idx];
+      let p = source[idx];
+      let mut rank = 0;
+      for j in 0..valid {
+        let other = score[base + j];
+        if other > s || (other == s && source[base + j] < p) {
+          rank += 1;
+        }
+      }
+      out_score[base + rank] = s;
+      out_source[base + rank] = p;
+    } else {
+      out_score[idx] = F::new(comptime!(0.0));
+      out_source[idx] = 0;
+    }
+  }
+}
+
+/// 按索引收集: output[n, k] = input[n, source[n, k]]，仅处理 k < count[n] 的位置
+///
+/// input: [N, M]，source/output: [N, K]，count: [N]
+#[cube(launch)]
+pub fn gather<T: CubePrimitive>(
+  input: &Tensor<T>,
+  source: &Tensor<u32>,
+  count: &Tensor<u32>,
+  output: &mut Tensor<T>,
+) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let n_dim = count.len();
+    let m = input.len() / n_dim;
+    let k_dim = output.len() / n_dim;
+
+    let n_idx = idx / k_dim;
+    let k_idx = idx % k_dim;
+
+    if (k_idx as u32) < count[n_idx] {
+      output[idx] = input[n_idx * m + source[idx] as usize];
+    } else {
+      output[idx] = T::cast_from(0u32);
+    }
+  }
+}
+
+/// 按索引收集多通道数据: output[n, c, k] = input[n, c, source[n, k]]，仅处理 k < count[n] 的位置
+///
+/// input: [N, C, M]，source: [N, K]，count: [N]，output: [N, C, K]，均为紧凑布局
+#[cube(launch)]
+pub fn gather_channels<T: CubePrimitive>(
+  input: &Tensor<T>,
+  source: &Tensor<u32>,
+  count: &Tensor<u32>,
+  output: &mut Tensor<T>,
+) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let n_dim = count.len();
+    let k_dim = source.len() / n_dim;
+    let c_dim = output.len() / (n_dim * k_dim);
+    let m = input.len() / (n_dim * c_dim);
+
+    let n_idx = idx / (c_dim * k_dim);
+    let c_idx = (idx / k_dim) % c_dim;
+    let k_idx = idx % k_dim;
+
+    if (k_idx as u32) < count[n_idx] {
+      output[idx] = input[(n_idx * c_dim + c_idx) * m + source[n_idx * k_dim + k_idx] as usize];
+    } else {
+      output[idx] = T::cast_from(0u32);
+    }
+  }
+}
+
+/// 每行选出最大的 K 个元素，按降序输出，值相同时取位置靠前者，每个 Cube 处理一行
+///
+/// 与 NMS 相同，每一轮在 Cube 内归约出剩余元素中的最大者并将其标记为已选，共 K 轮。
+/// 复杂度为 O(K * C)，只适合类别数较少的情况，K 或 C 较大时使用 compact_top_k 与 sort_by_score。
+/// 每个线程只读写自己负责的 taken 元素，因此只需同步共享内存。
+/// input: [N, C]，taken: 临时缓冲 [N, C]，out_value/out_index: 输出 [N, K]，要求 K <= C
+#[cube(launch)]
+pub fn top_k<F: Float, I: Int>(
+  input: &Tensor<F>,
+  taken: &mut Tensor<u32>,
+  out_value: &mut Tensor<F>,
+  out_index: &mut Tensor<I>,
+  #[comptime] block: usize,
+) {
+  let n_idx = CUBE_POS;
+  let unit = UNIT_POS as usize;
+  let dim = CUBE_DIM as usize;
+
+  let n_dim = out_value.shape(0);
+  let c_dim = input.len() / n_dim;
+  let k_dim = out_value.len() / n_dim;
+
+  let base = n_idx * c_dim;
+  let out_base = n_idx * k_dim;
+  let none = c_dim as u32;
+
+  let mut i = unit;
+  while i < c_dim {
+    taken[base + i] = 0;
+    i += dim;
+  }
+
+  let mut best_value = SharedMemory::<F>::new(block);
+  let mut best_pos = SharedMemory::<u32>::new(block);
+
+  for k in 0..k_dim {
+    // 线程内求局部最大值，按位置升序遍历，相同值保留靠前者
+    let mut local_value = F::min_value();
+    let mut local_pos = none;
+    let mut i = unit;
+    while i < c_dim {
+      let v = input[base + i];
+      if taken[base + i] == 0 && (local_pos == none || v > local_value) {
+        local_value = v;
+        local_pos = i as u32;
+      }
+      i += dim;
+    }
+
+    best_value[unit] = local_value;
+    best_pos[unit] = local_pos;
+    sync_cube();
+
+    // Cube 内树形归约，none 表示该线程没有剩余元素
+    let mut offset = dim / 2;
+    while offset > 0 {
+      if unit < offset {
+        let other_value = best_value[unit + offset];
+        let other_pos = best_pos[unit + offset];
+        let self_value = best_value[unit];
+        let self_pos = best_pos[unit];
+        if other_pos != none
+          && (self_pos == none
+            || other_value > self_value
+            || (other_value == self_value && other_pos < self_pos))
+        {
+          best_value[unit] = other_value;
+          best_pos[unit] = other_pos;
+        }
+      }
+      sync_cube();
+      offset /= 2;
+    }
+
+    let top_value = best_value[0];
+    let top_pos = best_pos[0] as usize;
+    sync_cube();
+
+    if unit == 0 {
+      out_value[out_base + k] = top_value;
+      out_index[out_base + k] = I::cast_from(top_pos);
+    }
+    if top_pos < c_dim && top_pos % dim == unit {
+      taken[base + top_pos] = 1;
+    }
+  }
+}
@@ -0,0 +1,170 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/kernel/geometry.rs - 几何计算相关的 Kernel 辅助函数
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+
+/// 计算两个 (xmin, ymin, xmax, ymax) 边界框的交并比
+#[cube]
+#[allow(clippy::too_many_arguments)]
+pub fn iou<F: Float>(
+  a_xmin: F,
+  a_ymin: F,
+  a_xmax: F,
+  a_ymax: F,
+  b_xmin: F,
+  b_ymin: F,
+  b_xmax: F,
+  b_ymax: F,
+) -> F {
+  let zero = F::new(comptime!(0.0));
+
+  let inter_w = (F::min(a_xmax, b_xmax) - F::max(a_xmin, b_xmin)).max(zero);
+  let inter_h = (F::min(a_ymax, b_ymax) - F::max(a_ymin, b_ymin)).max(zero);
+  let inter = inter_w * inter_h;
+
+  let area_a = (a_xmax - a_xmin).max(zero) * (a_ymax - a_ymin).max(zero);
+  let area_b = (b_xmax - b_xmin).max(zero) * (b_ymax - b_ymin).max(zero);
+  let union = area_a + area_b - inter;
+
+  let mut result = zero;
+  if union > zero {
+    result = inter / union;
+  }
+  result
+}
+
+/// 计算旋转矩形 (cx, cy, w, h, θ) 的四个顶点，按逆时针顺序写入 xs/ys
+#[cube]
+fn rect_corners<F: Float>(
+  cx: F,
+  cy: F,
+  w: F,
+  h: F,
+  theta: F,
+  xs: &mut Array<F>,
+  ys: &mut Array<F>,
+) {
+  let half = F::new(comptime!(0.5));
+  let cos = theta.cos();
+  let sin = theta.sin();
+  let hw = w * half;
+  let hh = h * half;
+
+  // 局部坐标依次为 (-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)
+  xs[0] = cx - hw * cos + hh * sin;
+  ys[0] = cy - hw * sin - hh * cos;
+  xs[1] = cx + hw * cos + hh * sin;
+  ys[1] = cy + hw * sin - hh * cos;
+  xs[2] = cx + hw * cos - hh * sin;
+  ys[2] = cy + hw * sin + hh * cos;
+  xs[3] = cx - hw * cos - hh * sin;
+  ys[3] = cy - hw * sin + hh * cos;
+}
+
+/// 计算两个旋转矩形 (cx, cy, w, h, θ) 的交并比，θ 为弧度
+///
+/// 用 b 的四条边依次裁剪 a (Sutherland-Hodgman)，凸多边形每次裁剪最多增加一个顶点，
+/// 因此 8 个顶点的缓冲足够，且裁剪结果保持顶点顺序，可直接用鞋带公式求面积
+#[cube]
+#[allow(clippy::too_many_arguments)]
+pub fn rotated_iou<F: Float>(
+  a_cx: F,
+  a_cy: F,
+  a_w: F,
+  a_h: F,
+  a_theta: F,
+  b_cx: F,
+  b_cy: F,
+  b_w: F,
+  b_h: F,
+  b_theta: F,
+) -> F {
+  let zero = F::new(comptime!(0.0));
+  let half = F::new(comptime!(0.5));
+
+  let mut px = Array::<F>::new(8usize);
+  let mut py = Array::<F>::new(8usize);
+  let mut qx = Array::<F>::new(8usize);
+  let mut qy = Array::<F>::new(8usize);
+  let mut bx = Array::<F>::new(4usize);
+  let mut by = Array::<F>::new(4usize);
+
+  rect_corners::<F>(a_cx, a_cy, a_w, a_h, a_theta, &mut px, &mut py);
+  rect_corners::<F>(b_cx, b_cy, b_w, b_h, b_theta, &mut bx, &mut by);
+
+  let mut len = 4u32;
+  for e in 0..4u32 {
+    let mut e1 = e + 1;
+    if e1 == 4 {
+      e1 = 0;
+    }
+    let ex = bx[e1 as usize] - bx[e as usize];
+    let ey = by[e1 as usize] - by[e as usize];
+
+    // 保留位于边左侧 (叉积非负) 的部分
+    let mut out_len = 0u32;
+    for i in 0..len {
+      let mut j = i + 1;
+      if j == len {
+        j = 0;
+      }
+      let cx = px[i as usize];
+      let cy = py[i as usize];
+      let nx = px[j as usize];
+      let ny = py[j as usize];
+      let sc = ex * (cy - by[e as usize]) - ey * (cx - bx[e as usize]);
+      let sn = ex * (ny - by[e as usize]) - ey * (nx - bx[e as usize]);
+
+      if sc >= zero {
+        qx[out_len as usize] = cx;
+        qy[out_len as usize] = cy;
+        out_len += 1;
+      }
+      if (sc >= zero) != (sn >= zero) {
+        let t = sc / (sc - sn);
+        qx[out_len as usize] = cx + t * (nx - cx);
+        qy[out_len as usize] = cy + t * (ny - cy);
+        out_len += 1;
+      }
+    }
+
+    for i in 0..out_len {
+      px[i as usize] = qx[i as usize];
+      py[i as usize] = qy[i as usize];
+    }
+    len = out_len;
+  }
+
+  // 鞋带公式求交集面积
+  let mut area = zero;
+  if len >= 3 {
+    for i in 0..len {
+      let mut j = i + 1;
+      if j == len {
+        j = 0;
+      }
+      area += px[i as usize] * py[j as usize] - px[j as usize] * py[i as usize];
+    }
+  }
+  let inter = (area * half).abs();
+
+  let area_a = a_w.max(zero) * a_h.max(zero);
+  let area_b = b_w.max(zero) * b_h.max(zero);
+  let union = area_a + area_b - inter;
+
+  let mut result = zero;
+  if union > zero {
+    result = inter / union;
+  }
+  result
+}
@@ -13,6 +13,8 @@
 
 use cubecl::prelude::*;
 
+use super::compact::{block_max, block_sum};
+
 #[cube(launch)]
 pub fn sigmoid<F: Float>(input: &Tensor<F>, output: &mut Tensor<F>) {
   let one = F::new(comptime!(1.0));
@@ -21,3 +23,43 @@ pub fn sigmoid<F: Float>(input: &Tensor<F>, output: &mut Tensor<F>) {
     output[ABSOLUTE_POS] = one / (one + (-input[ABSOLUTE_POS]).exp());
   }
 }
+
+/// 按行计算 softmax，每个 Cube 处理一行，先减去行内最大值保证数值稳定
+///
+/// input/output: [N, C]，紧凑布局
+#[cube(launch)]
+pub fn softmax<F: Float>(input: &Tensor<F>, output: &mut Tensor<F>, #[comptime] block: usize) {
+  let n_idx = CUBE_POS;
+  let unit = UNIT_POS as usize;
+  let dim = CUBE_DIM as usize;
+
+  let n_dim = input.shape(0);
+  let c_dim = input.len() / n_dim;
+  let base = n_idx * c_dim;
+
+  let mut shared = SharedMemory::<F>::new(block);
+
+  let mut local_max = F::min_value();
+  let mut i = unit;
+  while i < c_dim {
+    local_max = F::max(local_max, input[base + i]);
+    i += dim;
+  }
+  let row_max = block_max::<F>(local_max, &mut shared);
+
+  let mut local_sum = F::new(comptime!(0.0));
+  let mut i = unit;
+  while i < c_dim {
+    let e = (input[base + i] - row_max).exp();
+    output[base + i] = e;
+    local_sum += e;
+    i += dim;
+  }
+  let row_sum = block_sum::<F>(local_sum, &mut shared);
+
+  let mut i = unit;
+  while i < c_dim {
+    output[base + i] = output[base + i] / row_sum;
+    i += dim;
+  }
+}
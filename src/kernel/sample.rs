@@ -0,0 +1,130 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/kernel/sample.rs - 采样与缩放相关的计算 Kernel 实现
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+
+/// 双线性缩放，像素中心对齐 (align_corners = false)，边界处截断
+///
+/// input: [N, C, H, W]，支持任意 stride 布局
+/// output: [N, C, H', W']，紧凑布局
+#[cube(launch)]
+pub fn resize_bilinear<F: Float>(input: &Tensor<F>, output: &mut Tensor<F>) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let zero = F::new(comptime!(0.0));
+    let half = F::new(comptime!(0.5));
+
+    let c_dim = output.shape(1);
+    let oh_dim = output.shape(2);
+    let ow_dim = output.shape(3);
+    let ih_dim = input.shape(2);
+    let iw_dim = input.shape(3);
+
+    // 将 idx 映射回 (n, c, y, x)
+    let n_idx = idx / (c_dim * oh_dim * ow_dim);
+    let c_idx = (idx / (oh_dim * ow_dim)) % c_dim;
+    let y = (idx / ow_dim) % oh_dim;
+    let x = idx % ow_dim;
+
+    let scale_x = F::cast_from(iw_dim) / F::cast_from(ow_dim);
+    let scale_y = F::cast_from(ih_dim) / F::cast_from(oh_dim);
+    let sx = ((F::cast_from(x) + half) * scale_x - half).clamp(zero, F::cast_from(iw_dim - 1));
+    let sy = ((F::cast_from(y) + half) * scale_y - half).clamp(zero, F::cast_from(ih_dim - 1));
+
+    let x0 = u32::cast_from(sx.floor()) as usize;
+    let y0 = u32::cast_from(sy.floor()) as usize;
+    let mut x1 = x0 + 1;
+    if x1 >= iw_dim {
+      x1 = iw_dim - 1;
+    }
+    let mut y1 = y0 + 1;
+    if y1 >= ih_dim {
+      y1 = ih_dim - 1;
+    }
+    let fx = sx - F::cast_from(x0);
+    let fy = sy - F::cast_from(y0);
+
+    let base = n_idx * input.stride(0) + c_idx * input.stride(1);
+    let stride_h = input.stride(2);
+    let stride_w = input.stride(3);
+    let v00 = input[base + y0 * stride_h + x0 * stride_w];
+    let v01 = input[base + y0 * stride_h + x1 * stride_w];
+    let v10 = input[base + y1 * stride_h + x0 * stride_w];
+    let v11 = input[base + y1 * stride_h + x1 * stride_w];
+
+    let top = v00 + (v01 - v00) * fx;
+    let bottom = v10 + (v11 - v10) * fx;
+    output[idx] = top + (bottom - top) * fy;
+  }
+}
+
+/// 按区域裁剪并双线性缩放，像素中心对齐 (align_corners = false)，边界处截断在区域内
+///
+/// input: [N, C, H, W]，支持任意 stride 布局
+/// regions: 每个输出对应的 (图像索引, x, y, 宽, 高) [T, 5]，区域需位于图像内
+/// output: [T, C, H', W']，紧凑布局
+#[cube(launch)]
+pub fn crop_bilinear<F: Float>(input: &Tensor<F>, regions: &Tensor<u32>, output: &mut Tensor<F>) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let zero = F::new(comptime!(0.0));
+    let half = F::new(comptime!(0.5));
+
+    let c_dim = output.shape(1);
+    let oh_dim = output.shape(2);
+    let ow_dim = output.shape(3);
+
+    // 将 idx 映射回 (t, c, y, x)
+    let t_idx = idx / (c_dim * oh_dim * ow_dim);
+    let c_idx = (idx / (oh_dim * ow_dim)) % c_dim;
+    let y = (idx / ow_dim) % oh_dim;
+    let x = idx % ow_dim;
+
+    let r = t_idx * 5;
+    let n_idx = regions[r] as usize;
+    let rx = regions[r + 1] as usize;
+    let ry = regions[r + 2] as usize;
+    let rw = regions[r + 3] as usize;
+    let rh = regions[r + 4] as usize;
+
+    let scale_x = F::cast_from(rw) / F::cast_from(ow_dim);
+    let scale_y = F::cast_from(rh) / F::cast_from(oh_dim);
+    let sx = ((F::cast_from(x) + half) * scale_x - half).clamp(zero, F::cast_from(rw - 1));
+    let sy = ((F::cast_from(y) + half) * scale_y - half).clamp(zero, F::cast_from(rh - 1));
+
+    let x0 = u32::cast_from(sx.floor()) as usize;
+    let y0 = u32::cast_from(sy.floor()) as usize;
+    let mut x1 = x0 + 1;
+    if x1 >= rw {
+      x1 = rw - 1;
+    }
+    let mut y1 = y0 + 1;
+    if y1 >= rh {
+      y1 = rh - 1;
+    }
+    let fx = sx - F::cast_from(x0);
+    let fy = sy - F::cast_from(y0);
+
+    let base = n_idx * input.stride(0) + c_idx * input.stride(1);
+    let stride_h = input.stride(2);
+    let stride_w = input.stride(3);
+    let v00 = input[base + (ry + y0) * stride_h + (rx + x0) * stride_w];
+    let v01 = input[base + (ry + y0) * stride_h + (rx + x1) * stride_w];
+    let v10 = input[base + (ry + y1) * stride_h + (rx + x0) * stride_w];
+    let v11 = input[base + (ry + y1) * stride_h + (rx + x1) * stride_w];
+
+    let top = v00 + (v01 - v00) * fx;
+    let bottom = v10 + (v11 - v10) * fx;
+    output[idx] = top + (bottom - top) * fy;
+  }
+}
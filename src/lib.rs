@@ -13,8 +13,12 @@
 
 pub use cubecl;
 
+pub mod annotation;
 pub mod data;
+pub mod eval;
 pub mod image;
 pub mod postprocess;
+pub mod slicing;
+pub mod tracking;
 
 mod kernel;
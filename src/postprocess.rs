@@ -11,4 +11,16 @@
 //
 // Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
 
+pub mod candidate;
+pub mod classification;
+pub mod ctc;
+pub mod depth;
 pub mod detection;
+pub mod face;
+pub mod geometry;
+pub mod instance;
+pub mod letterbox;
+pub mod nms;
+pub mod segmentation;
+pub mod text;
+pub mod wbf;
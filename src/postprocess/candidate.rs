@@ -0,0 +1,162 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/candidate.rs - 候选框的阈值筛选与 Top-K 选择
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::{
+  data::DataBuffer,
+  kernel::compact_candidates,
+  postprocess::detection::{BoxEncoding, Candidates, candidate_shape, check_xyxy_planar},
+};
+
+#[derive(Debug, Error)]
+pub enum CandidateError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct CandidateConfig {
+  score_threshold: f32,
+  max_candidates: u32,
+  top_k: bool,
+  dim: u32,
+  encoding: BoxEncoding,
+}
+
+impl Default for CandidateConfig {
+  fn default() -> Self {
+    Self {
+      score_threshold: 0.25,
+      max_candidates: 1000,
+      top_k: true,
+      dim: 256,
+      encoding: BoxEncoding::default(),
+    }
+  }
+}
+
+impl CandidateConfig {
+  /// 低于该得分的位置直接丢弃
+  pub fn with_score_threshold(mut self, score_threshold: f32) -> Self {
+    self.score_threshold = score_threshold;
+    self
+  }
+
+  /// 每张图像最多保留的候选数量 K
+  pub fn with_max_candidates(mut self, max_candidates: u32) -> Self {
+    self.max_candidates = max_candidates;
+    self
+  }
+
+  /// 为 true 时保留得分最高的 K 个，否则按位置顺序保留前 K 个
+  pub fn with_top_k(mut self, top_k: bool) -> Self {
+    self.top_k = top_k;
+    self
+  }
+
+  /// 每张图像使用一个 Cube 处理，dim 为 Cube 内的线程数，必须为 2 的幂
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  /// 输入边界框的编码方式，应与 Yolo26::box_encoding 一致；
+  /// 仅支持 [N, 4, ...] 排列的 xyxy，其他格式或排列方式在 build 时报错
+  pub fn with_box_encoding(mut self, encoding: BoxEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  pub fn build(self) -> Result<CandidateFilter, CandidateError> {
+    if !self.dim.is_power_of_two() {
+      return Err(CandidateError::InvalidConfig(format!(
+        "dim 必须为 2 的幂，当前为 {}",
+        self.dim
+      )));
+    }
+    if self.max_candidates == 0 {
+      return Err(CandidateError::InvalidConfig(
+        "max_candidates 必须大于 0".to_string(),
+      ));
+    }
+    check_xyxy_planar(&self.encoding).map_err(CandidateError::InvalidConfig)?;
+    Ok(CandidateFilter {
+      score_threshold: self.score_threshold,
+      max_candidates: self.max_candidates,
+      top_k: self.top_k,
+      dim: self.dim,
+    })
+  }
+}
+
+pub struct CandidateFilter {
+  score_threshold: f32,
+  max_candidates: u32,
+  top_k: bool,
+  dim: u32,
+}
+
+impl CandidateFilter {
+  /// 将稠密的检测结果压缩为候选列表
+  /// score: 分类得分，形状为 [N, H, W]
+  /// index: 类别索引，形状为 [N, H, W]
+  /// bbox: 边界框坐标，形状为 [N, 4, H, W]
+  /// 返回的候选保持其在输入中的相对顺序，K 为 max_candidates
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    score: DataBuffer<R, F>,
+    index: DataBuffer<R, I>,
+    bbox: DataBuffer<R, F>,
+  ) -> Result<Candidates<R, F, I>, CandidateError> {
+    let (n, _) = candidate_shape(score.shape(), index.shape(), bbox.shape())
+      .map_err(CandidateError::InvalidInputShape)?;
+
+    let k = self.max_candidates as usize;
+    let out_score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let out_index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, k], client);
+    let out_bbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, k], client);
+    let out_source: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, k], client);
+    let out_count: DataBuffer<R, u32> = DataBuffer::with_shape(&[n], client);
+
+    compact_candidates::launch::<F, I, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      score.into_tensor_arg(1),
+      index.into_tensor_arg(1),
+      bbox.into_tensor_arg(1),
+      out_score.into_tensor_arg(1),
+      out_index.into_tensor_arg(1),
+      out_bbox.into_tensor_arg(1),
+      out_source.into_tensor_arg(1),
+      out_count.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.score_threshold)),
+      self.top_k,
+      self.dim as usize,
+    )?;
+
+    Ok(Candidates {
+      score: out_score,
+      index: out_index,
+      bbox: out_bbox,
+      source: out_source,
+      count: out_count,
+    })
+  }
+}
@@ -0,0 +1,166 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/classification.rs - 图像分类的 softmax 与 Top-K 后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use thiserror::Error;
+
+use crate::{
+  data::DataBuffer,
+  kernel::{softmax, top_k},
+};
+
+#[derive(Debug, Error)]
+pub enum ClassificationError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct ClassificationConfig {
+  top_k: u32,
+  softmax: bool,
+  dim: u32,
+}
+
+impl Default for ClassificationConfig {
+  fn default() -> Self {
+    Self {
+      top_k: 5,
+      softmax: true,
+      dim: 256,
+    }
+  }
+}
+
+impl ClassificationConfig {
+  /// 每张图像输出得分最高的 K 个类别
+  pub fn with_top_k(mut self, top_k: u32) -> Self {
+    self.top_k = top_k;
+    self
+  }
+
+  /// 为 true 时先对 logits 做 softmax，否则直接按 logits 排序
+  pub fn with_softmax(mut self, softmax: bool) -> Self {
+    self.softmax = softmax;
+    self
+  }
+
+  /// 每张图像使用一个 Cube 处理，dim 为 Cube 内的线程数，必须为 2 的幂
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Classification, ClassificationError> {
+    if !self.dim.is_power_of_two() {
+      return Err(ClassificationError::InvalidConfig(format!(
+        "dim 必须为 2 的幂，当前为 {}",
+        self.dim
+      )));
+    }
+    if self.top_k == 0 {
+      return Err(ClassificationError::InvalidConfig(
+        "top_k 必须大于 0".to_string(),
+      ));
+    }
+    Ok(Classification {
+      top_k: self.top_k,
+      softmax: self.softmax,
+      dim: self.dim,
+    })
+  }
+}
+
+/// Top-K 结果 (score, index)，形状均为 [N, K]
+pub type TopK<R, F, I> = (DataBuffer<R, F>, DataBuffer<R, I>);
+
+pub struct Classification {
+  top_k: u32,
+  softmax: bool,
+  dim: u32,
+}
+
+impl Classification {
+  /// 执行后处理操作
+  /// logits: 分类结果，形状为 [N, C]
+  /// 返回 (score, index)，形状均为 [N, K]，每行按得分降序排列，得分相同时类别索引小者在前
+  pub fn execute<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+  ) -> Result<TopK<R, F, I>, ClassificationError> {
+    let [n, c] = *logits.shape() else {
+      return Err(ClassificationError::InvalidInputShape(
+        "分类结果张量形状不正确，预期为 [N, C]".to_string(),
+      ));
+    };
+    let k = self.top_k as usize;
+    if k > c {
+      return Err(ClassificationError::InvalidInputShape(format!(
+        "top_k ({}) 不能大于类别数量 ({})",
+        k, c
+      )));
+    }
+
+    let score = if self.softmax {
+      self.softmax(client, logits)?
+    } else {
+      logits
+    };
+
+    let taken: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, c], client);
+    let out_score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let out_index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, k], client);
+
+    top_k::launch::<F, I, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      score.into_tensor_arg(1),
+      taken.into_tensor_arg(1),
+      out_score.into_tensor_arg(1),
+      out_index.into_tensor_arg(1),
+      self.dim as usize,
+    )?;
+
+    Ok((out_score, out_index))
+  }
+
+  /// 按行计算 softmax，返回形状为 [N, C] 的概率
+  pub fn softmax<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+  ) -> Result<DataBuffer<R, F>, ClassificationError> {
+    let [n, _] = *logits.shape() else {
+      return Err(ClassificationError::InvalidInputShape(
+        "分类结果张量形状不正确，预期为 [N, C]".to_string(),
+      ));
+    };
+
+    let output = logits.empty_like(client);
+    softmax::launch::<F, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      logits.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+      self.dim as usize,
+    )?;
+
+    Ok(output)
+  }
+}
@@ -0,0 +1,478 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/ctc.rs - 文本识别的 CTC 贪心解码与束搜索解码
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use std::collections::{HashMap, HashSet};
+
+use cubecl::prelude::*;
+use thiserror::Error;
+
+use crate::data::{DataBuffer, DataBufferError};
+
+#[derive(Debug, Error)]
+pub enum CtcError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 单条文本的识别结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recognition {
+  pub text: String,
+  /// 贪心解码时为保留字符概率的平均值，束搜索时为该序列的总概率，文本为空时为 0
+  pub score: f32,
+}
+
+pub struct CtcConfig {
+  dictionary: Vec<String>,
+  blank: u32,
+  softmax: bool,
+  beam_width: usize,
+  lexicon: Vec<String>,
+  dim: u32,
+}
+
+impl Default for CtcConfig {
+  fn default() -> Self {
+    Self {
+      dictionary: Vec::new(),
+      blank: 0,
+      softmax: true,
+      beam_width: 10,
+      lexicon: Vec::new(),
+      dim: 256,
+    }
+  }
+}
+
+impl CtcConfig {
+  /// 字符字典，第 i 项对应跳过 blank 之后的第 i 个类别，即类别数 C = 字典长度 + 1
+  pub fn with_dictionary(mut self, dictionary: Vec<String>) -> Self {
+    self.dictionary = dictionary;
+    self
+  }
+
+  /// blank 的类别索引
+  pub fn with_blank(mut self, blank: u32) -> Self {
+    self.blank = blank;
+    self
+  }
+
+  /// 为 true 时对每个时间步做 softmax，模型已输出概率时设为 false
+  pub fn with_softmax(mut self, softmax: bool) -> Self {
+    self.softmax = softmax;
+    self
+  }
+
+  /// 束搜索保留的前缀数量
+  pub fn with_beam_width(mut self, beam_width: usize) -> Self {
+    self.beam_width = beam_width;
+    self
+  }
+
+  /// 束搜索的词表，非空时只输出词表中的词
+  pub fn with_lexicon(mut self, lexicon: Vec<String>) -> Self {
+    self.lexicon = lexicon;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Ctc, CtcError> {
+    if self.dictionary.is_empty() {
+      return Err(CtcError::InvalidConfig("字符字典不能为空".to_string()));
+    }
+    if self.blank as usize > self.dictionary.len() {
+      return Err(CtcError::InvalidConfig(format!(
+        "blank ({}) 超出类别数量 ({})",
+        self.blank,
+        self.dictionary.len() + 1
+      )));
+    }
+    if self.beam_width == 0 {
+      return Err(CtcError::InvalidConfig("beam_width 必须大于 0".to_string()));
+    }
+
+    let mut ctc = Ctc {
+      dictionary: self.dictionary,
+      blank: self.blank,
+      softmax: self.softmax,
+      beam_width: self.beam_width,
+      words: HashSet::new(),
+      prefixes: HashSet::new(),
+      dim: self.dim,
+    };
+    for word in &self.lexicon {
+      let labels = ctc.tokenize(word).ok_or_else(|| {
+        CtcError::InvalidConfig(format!("词表中的词 \"{}\" 无法由字符字典组成", word))
+      })?;
+      for end in 1..=labels.len() {
+        ctc.prefixes.insert(labels[..end].to_vec());
+      }
+      ctc.words.insert(labels);
+    }
+    Ok(ctc)
+  }
+}
+
+/// 贪心解码的结果 (labels, probs, length)
+///
+/// labels/probs 形状为 [N, T]，每行前 length[n] 个位置为合并重复与去除 blank 后的类别及其概率
+pub type CtcLabels<R, F> = (DataBuffer<R, u32>, DataBuffer<R, F>, DataBuffer<R, u32>);
+
+/// CTC 解码
+pub struct Ctc {
+  dictionary: Vec<String>,
+  blank: u32,
+  softmax: bool,
+  beam_width: usize,
+  /// 词表中的词与其全部前缀，均以类别序列表示
+  words: HashSet<Vec<u32>>,
+  prefixes: HashSet<Vec<u32>>,
+  dim: u32,
+}
+
+impl Ctc {
+  /// 在设备上执行贪心解码
+  /// logits: 识别结果，形状为 [N, T, C]
+  pub fn greedy<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+  ) -> Result<CtcLabels<R, F>, CtcError> {
+    let (n, t) = self.logits_shape(logits.shape())?;
+
+    let best: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, t], client);
+    let best_prob: DataBuffer<R, F> = DataBuffer::with_shape(&[n, t], client);
+    argmax::launch::<F, R>(
+      client,
+      CubeCount::Static((n * t).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      logits.into_tensor_arg(1),
+      best.into_tensor_arg(1),
+      best_prob.into_tensor_arg(1),
+      self.softmax,
+    )?;
+
+    let labels: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, t], client);
+    let probs: DataBuffer<R, F> = DataBuffer::with_shape(&[n, t], client);
+    let length: DataBuffer<R, u32> = DataBuffer::with_shape(&[n], client);
+    collapse::launch::<F, R>(
+      client,
+      CubeCount::Static((n as u32).div_ceil(self.dim), 1, 1),
+      CubeDim::new_1d(self.dim),
+      best.into_tensor_arg(1),
+      best_prob.into_tensor_arg(1),
+      labels.into_tensor_arg(1),
+      probs.into_tensor_arg(1),
+      length.into_tensor_arg(1),
+      ScalarArg::new(self.blank),
+    )?;
+
+    Ok((labels, probs, length))
+  }
+
+  /// 贪心解码并按字符字典转换为文本
+  /// logits: 识别结果，形状为 [N, T, C]
+  pub fn decode_greedy<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+  ) -> Result<Vec<Recognition>, CtcError> {
+    let (n, t) = self.logits_shape(logits.shape())?;
+    let (labels, probs, length) = self.greedy(client, logits)?;
+
+    let labels = labels.into_vec(client)?;
+    let probs = to_f32(probs.into_vec(client)?)?;
+    let length = length.into_vec(client)?;
+
+    Ok(
+      (0..n)
+        .map(|b| {
+          let len = length[b] as usize;
+          let labels = &labels[b * t..b * t + len];
+          let probs = &probs[b * t..b * t + len];
+          let score = if len == 0 {
+            0.0
+          } else {
+            probs.iter().sum::<f32>() / len as f32
+          };
+          Recognition {
+            text: self.text(labels),
+            score,
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// 读回识别结果并在主机上执行束搜索解码
+  /// logits: 识别结果，形状为 [N, T, C]
+  pub fn beam_search<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+  ) -> Result<Vec<Recognition>, CtcError> {
+    let (n, t) = self.logits_shape(logits.shape())?;
+    let logits = to_f32(logits.into_vec(client)?)?;
+    let step = t * (self.dictionary.len() + 1);
+    Ok(
+      (0..n)
+        .map(|b| self.beam_search_sequence(&logits[b * step..(b + 1) * step]))
+        .collect(),
+    )
+  }
+
+  /// 对单条序列执行 CTC 前缀束搜索
+  /// logits: 形状为 [T, C] 的连续数据，C = 字典长度 + 1
+  ///
+  /// 配置了词表时只扩展词表中词的前缀，最终只保留完整的词，没有匹配的词时返回空文本
+  pub fn beam_search_sequence(&self, logits: &[f32]) -> Recognition {
+    let c = self.dictionary.len() + 1;
+    let constrained = !self.words.is_empty();
+
+    let mut beams: Vec<(Vec<u32>, f32, f32)> = vec![(Vec::new(), 0.0, f32::NEG_INFINITY)];
+    for step in logits.chunks_exact(c) {
+      let log_prob = self.log_prob(step);
+
+      // 前缀 -> (以 blank 结尾的概率, 以非 blank 结尾的概率)，均为对数
+      let mut next: HashMap<Vec<u32>, (f32, f32)> = HashMap::new();
+      for (prefix, blank_end, label_end) in &beams {
+        let total = log_add(*blank_end, *label_end);
+        let entry = next
+          .entry(prefix.clone())
+          .or_insert((f32::NEG_INFINITY, f32::NEG_INFINITY));
+        entry.0 = log_add(entry.0, total + log_prob[self.blank as usize]);
+
+        for (label, &lp) in log_prob.iter().enumerate() {
+          let label = label as u32;
+          if label == self.blank {
+            continue;
+          }
+          let last = prefix.last() == Some(&label);
+          if last {
+            // 重复字符之间没有 blank 时合并为同一个字符
+            let entry = next.get_mut(prefix).expect("前缀已插入");
+            entry.1 = log_add(entry.1, label_end + lp);
+          }
+
+          let mut extended = prefix.clone();
+          extended.push(label);
+          if constrained && !self.prefixes.contains(&extended) {
+            continue;
+          }
+          let from = if last { *blank_end } else { total };
+          let entry = next
+            .entry(extended)
+            .or_insert((f32::NEG_INFINITY, f32::NEG_INFINITY));
+          entry.1 = log_add(entry.1, from + lp);
+        }
+      }
+
+      beams = next
+        .into_iter()
+        .map(|(prefix, (b, l))| (prefix, b, l))
+        .collect();
+      sort_beams(&mut beams);
+      beams.truncate(self.beam_width);
+    }
+
+    beams
+      .into_iter()
+      .find(|(prefix, _, _)| !constrained || self.words.contains(prefix))
+      .filter(|(prefix, _, _)| !prefix.is_empty())
+      .map(|(prefix, b, l)| Recognition {
+        text: self.text(&prefix),
+        score: log_add(b, l).exp(),
+      })
+      .unwrap_or(Recognition {
+        text: String::new(),
+        score: 0.0,
+      })
+  }
+
+  /// 检查识别结果形状，返回 (N, T)
+  fn logits_shape(&self, shape: &[usize]) -> Result<(usize, usize), CtcError> {
+    let c = self.dictionary.len() + 1;
+    match *shape {
+      [n, t, sc] if sc == c => Ok((n, t)),
+      _ => Err(CtcError::InvalidInputShape(format!(
+        "识别结果张量形状不正确，预期为 [N, T, {}]",
+        c
+      ))),
+    }
+  }
+
+  /// 单个时间步的对数概率
+  fn log_prob(&self, step: &[f32]) -> Vec<f32> {
+    if self.softmax {
+      let max = step.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+      let sum = step.iter().map(|v| (v - max).exp()).sum::<f32>().ln();
+      step.iter().map(|v| v - max - sum).collect()
+    } else {
+      step.iter().map(|v| v.ln()).collect()
+    }
+  }
+
+  /// 类别序列转换为文本
+  fn text(&self, labels: &[u32]) -> String {
+    labels
+      .iter()
+      .filter_map(|&label| {
+        let index = match label.cmp(&self.blank) {
+          std::cmp::Ordering::Less => label as usize,
+          std::cmp::Ordering::Equal => return None,
+          std::cmp::Ordering::Greater => label as usize - 1,
+        };
+        self.dictionary.get(index).map(String::as_str)
+      })
+      .collect()
+  }
+
+  /// 按字符字典以最长匹配将文本切分为类别序列
+  fn tokenize(&self, word: &str) -> Option<Vec<u32>> {
+    let mut labels = Vec::new();
+    let mut rest = word;
+    while !rest.is_empty() {
+      let (index, entry) = self
+        .dictionary
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| !entry.is_empty() && rest.starts_with(entry.as_str()))
+        .max_by_key(|(_, entry)| entry.len())?;
+      let label = index as u32;
+      labels.push(if label >= self.blank {
+        label + 1
+      } else {
+        label
+      });
+      rest = &rest[entry.len()..];
+    }
+    Some(labels)
+  }
+}
+
+/// 按总概率降序排列，概率相同时按前缀排序以保证结果确定
+fn sort_beams(beams: &mut [(Vec<u32>, f32, f32)]) {
+  beams.sort_by(|a, b| {
+    log_add(b.1, b.2)
+      .total_cmp(&log_add(a.1, a.2))
+      .then_with(|| a.0.cmp(&b.0))
+  });
+}
+
+/// log(exp(a) + exp(b))
+fn log_add(a: f32, b: f32) -> f32 {
+  let max = a.max(b);
+  if max == f32::NEG_INFINITY {
+    return max;
+  }
+  max + ((a - max).exp() + (b - max).exp()).ln()
+}
+
+fn to_f32<F: Float + CubeElement>(values: Vec<F>) -> Result<Vec<f32>, DataBufferError> {
+  values
+    .iter()
+    .map(|v| {
+      v.to_f32()
+        .ok_or_else(|| DataBufferError::InvalidData("无法将识别结果转换为 f32".to_string()))
+    })
+    .collect()
+}
+
+/// 每个时间步取最大的类别及其概率
+/// logits: 输入 [N, T, C]，best/best_prob: 输出 [N, T]
+#[cube(launch)]
+fn argmax<F: Float>(
+  logits: &Tensor<F>,
+  best: &mut Tensor<u32>,
+  best_prob: &mut Tensor<F>,
+  #[comptime] softmax: bool,
+) {
+  let t_dim = logits.shape(1);
+  let c_dim = logits.shape(2);
+
+  let idx = ABSOLUTE_POS;
+  if idx < best.len() {
+    let n_idx = idx / t_dim;
+    let t_idx = idx % t_dim;
+    let base = n_idx * logits.stride(0) + t_idx * logits.stride(1);
+    let stride_c = logits.stride(2);
+
+    let mut best_c = 0;
+    let mut best_val = logits[base];
+    for c in 1..c_dim {
+      let v = logits[base + c * stride_c];
+      if v > best_val {
+        best_val = v;
+        best_c = c;
+      }
+    }
+
+    let prob = if comptime!(softmax) {
+      let mut sum = F::new(comptime!(0.0));
+      for c in 0..c_dim {
+        sum += (logits[base + c * stride_c] - best_val).exp();
+      }
+      F::new(comptime!(1.0)) / sum
+    } else {
+      best_val
+    };
+
+    best[idx] = best_c as u32;
+    best_prob[idx] = prob;
+  }
+}
+
+/// 合并相邻的重复类别并去除 blank，每个线程顺序处理一条序列
+/// best/best_prob: 输入 [N, T]，labels/probs: 输出 [N, T]，length: 输出 [N]
+#[cube(launch)]
+fn collapse<F: Float>(
+  best: &Tensor<u32>,
+  best_prob: &Tensor<F>,
+  labels: &mut Tensor<u32>,
+  probs: &mut Tensor<F>,
+  length: &mut Tensor<u32>,
+  blank: u32,
+) {
+  let n_idx = ABSOLUTE_POS;
+  if n_idx < length.len() {
+    let t_dim = best.shape(1);
+    let base = n_idx * t_dim;
+
+    let mut count = 0;
+    let mut previous = blank;
+    for t in 0..t_dim {
+      let label = best[base + t];
+      if label != blank && label != previous {
+        labels[base + count] = label;
+        probs[base + count] = best_prob[base + t];
+        count += 1;
+      }
+      previous = label;
+    }
+    for t in count..t_dim {
+      labels[base + t] = blank;
+      probs[base + t] = F::new(comptime!(0.0));
+    }
+    length[n_idx] = count as u32;
+  }
+}
@@ -0,0 +1,450 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/depth.rs - 单目深度估计的缩放、归一化与伪彩色
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use thiserror::Error;
+
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  kernel::{block_max, block_sum, resize_bilinear},
+};
+
+#[derive(Debug, Error)]
+pub enum DepthError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 深度归一化方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Normalization {
+  /// 按每张图像的最小值与最大值归一化
+  MinMax,
+  /// 按每张图像的分位数归一化，low/high 为 [0, 1] 内的分位，超出范围的值截断
+  Percentile { low: f32, high: f32 },
+}
+
+/// 伪彩色映射
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+  Gray,
+  Turbo,
+  Viridis,
+  Inferno,
+}
+
+impl Colormap {
+  /// 生成 256 级的调色板，可直接用于 Depth::colorize
+  ///
+  /// Turbo 使用 Google 给出的多项式近似，Viridis/Inferno 使用对 matplotlib 色表的六次多项式拟合
+  pub fn palette(&self) -> Vec<[u8; 3]> {
+    (0..256)
+      .map(|i| {
+        let x = i as f32 / 255.0;
+        let rgb = match self {
+          Colormap::Gray => [x, x, x],
+          Colormap::Turbo => turbo(x),
+          Colormap::Viridis => polynomial(x, &VIRIDIS),
+          Colormap::Inferno => polynomial(x, &INFERNO),
+        };
+        rgb.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8)
+      })
+      .collect()
+  }
+}
+
+const VIRIDIS: [[f32; 3]; 7] = [
+  [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+  [0.105_093_04, 1.404_613_5, 1.384_590_1],
+  [-0.330_861_83, 0.214_847_56, 0.095_095_16],
+  [-4.634_230_6, -5.799_101, -19.332_441],
+  [6.228_27, 14.179_933, 56.690_55],
+  [4.776_385, -13.745_145, -65.353_03],
+  [-5.435_456, 4.645_852_6, 26.312_435],
+];
+
+const INFERNO: [[f32; 3]; 7] = [
+  [0.000_218_940_37, 0.001_651_004_6, -0.019_480_898],
+  [0.106_513_42, 0.563_956_4, 3.932_712_3],
+  [11.602_493, -3.972_854, -15.942_394],
+  [-41.703_995, 17.436_398, 44.354_145],
+  [77.162_94, -33.402_36, -81.807_31],
+  [-71.319_43, 32.626_064, 73.209_52],
+  [25.131_126, -12.242_669, -23.070_325],
+];
+
+/// 按升幂系数计算多项式
+fn polynomial(x: f32, coefficients: &[[f32; 3]]) -> [f32; 3] {
+  let mut rgb = [0.0; 3];
+  for c in coefficients.iter().rev() {
+    for (v, k) in rgb.iter_mut().zip(c.iter()) {
+      *v = *v * x + k;
+    }
+  }
+  rgb
+}
+
+fn turbo(x: f32) -> [f32; 3] {
+  const COEFFICIENTS: [[f32; 3]; 6] = [
+    [0.135_721_38, 0.091_402_61, 0.106_673_3],
+    [4.615_392_6, 2.194_188_4, 12.641_946],
+    [-42.660_32, 4.842_966_6, -60.582_05],
+    [132.131_08, -14.185_033, 110.362_77],
+    [-152.942_4, 4.277_299, -89.903_11],
+    [59.286_38, 2.829_566, 27.348_25],
+  ];
+  polynomial(x, &COEFFICIENTS)
+}
+
+pub struct DepthConfig {
+  resize: Option<(u32, u32)>,
+  normalization: Normalization,
+  invert: bool,
+  dim: u32,
+}
+
+impl Default for DepthConfig {
+  fn default() -> Self {
+    Self {
+      resize: None,
+      normalization: Normalization::MinMax,
+      invert: false,
+      dim: 256,
+    }
+  }
+}
+
+impl DepthConfig {
+  /// 归一化前先将深度双线性缩放到指定尺寸 (通常为原始图像尺寸)
+  pub fn with_resize(mut self, width: u32, height: u32) -> Self {
+    self.resize = Some((width, height));
+    self
+  }
+
+  pub fn with_normalization(mut self, normalization: Normalization) -> Self {
+    self.normalization = normalization;
+    self
+  }
+
+  /// 为 true 时输出 1 - 归一化值，用于在视差与深度之间切换远近的表示
+  pub fn with_invert(mut self, invert: bool) -> Self {
+    self.invert = invert;
+    self
+  }
+
+  /// 求取范围时每张图像使用一个 Cube 处理，dim 为 Cube 内的线程数，必须为 2 的幂
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Depth, DepthError> {
+    if !self.dim.is_power_of_two() {
+      return Err(DepthError::InvalidConfig(format!(
+        "dim 必须为 2 的幂，当前为 {}",
+        self.dim
+      )));
+    }
+    if let Some((width, height)) = self.resize
+      && (width == 0 || height == 0)
+    {
+      return Err(DepthError::InvalidConfig("缩放尺寸必须大于 0".to_string()));
+    }
+    if let Normalization::Percentile { low, high } = self.normalization
+      && !(0.0 <= low && low < high && high <= 1.0)
+    {
+      return Err(DepthError::InvalidConfig(format!(
+        "分位数需满足 0 <= low < high <= 1，当前为 ({}, {})",
+        low, high
+      )));
+    }
+    Ok(Depth {
+      resize: self.resize,
+      normalization: self.normalization,
+      invert: self.invert,
+      dim: self.dim,
+    })
+  }
+}
+
+/// 单目深度估计后处理
+pub struct Depth {
+  resize: Option<(u32, u32)>,
+  normalization: Normalization,
+  invert: bool,
+  dim: u32,
+}
+
+impl Depth {
+  /// 执行后处理操作
+  /// depth: 相对深度，形状为 [N, 1, H, W]
+  /// 返回形状为 [N, 1, H', W'] 的归一化深度，取值范围为 [0, 1]，
+  /// 配置了缩放时 H'/W' 为缩放后的尺寸，否则与输入一致
+  pub fn execute<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    depth: DataBuffer<R, F>,
+  ) -> Result<DataBuffer<R, F>, DepthError> {
+    let [n, 1, h, w] = *depth.shape() else {
+      return Err(DepthError::InvalidInputShape(
+        "深度张量形状不正确，预期为 [N, 1, H, W]".to_string(),
+      ));
+    };
+    if n == 0 || h == 0 || w == 0 {
+      return Err(DepthError::InvalidInputShape(
+        "深度张量不能为空".to_string(),
+      ));
+    }
+
+    // 范围求取要求紧凑布局，未缩放时先复制一份
+    let (width, height) = self
+      .resize
+      .map_or((w, h), |(w, h)| (w as usize, h as usize));
+    let resized: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 1, height, width], client);
+    let count = (n * height * width).div_ceil(self.dim as usize);
+    resize_bilinear::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      depth.into_tensor_arg(1),
+      resized.into_tensor_arg(1),
+    )?;
+
+    let hw = height * width;
+    let (low, high, percentile) = match self.normalization {
+      Normalization::MinMax => (0, 0, false),
+      Normalization::Percentile { low, high } => (rank(low, hw), rank(high, hw), true),
+    };
+    let range: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 2], client);
+    depth_range::launch::<F, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      resized.clone().into_tensor_arg(1),
+      range.clone().into_tensor_arg(1),
+      ScalarArg::new(low),
+      ScalarArg::new(high),
+      percentile,
+      self.dim as usize,
+    )?;
+
+    let output: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 1, height, width], client);
+    normalize::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      resized.into_tensor_arg(1),
+      range.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+      self.invert,
+    )?;
+
+    Ok(output)
+  }
+
+  /// 按调色板将归一化深度转换为 RGB 图像
+  /// depth: 归一化深度，形状为 [N, 1, H, W]，取值范围为 [0, 1]
+  /// palette: 由低到高排列的颜色，如 Colormap::palette 的输出
+  /// 返回形状为 [N, 3, H, W] 的 RGB 图像，数值类型与调色板一致
+  pub fn colorize<R: Runtime, F: Float + CubeElement, T: Numeric + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    depth: DataBuffer<R, F>,
+    palette: &[[T; 3]],
+  ) -> Result<DataBuffer<R, T>, DepthError> {
+    let [n, 1, h, w] = *depth.shape() else {
+      return Err(DepthError::InvalidInputShape(
+        "深度张量形状不正确，预期为 [N, 1, H, W]".to_string(),
+      ));
+    };
+    if palette.is_empty() {
+      return Err(DepthError::InvalidConfig("调色板不能为空".to_string()));
+    }
+
+    let colors: Vec<T> = palette.iter().flatten().copied().collect();
+    let palette = DataBuffer::<R, T>::from_slice(&colors, &[colors.len() / 3, 3], client)?;
+
+    let output: DataBuffer<R, T> = DataBuffer::with_shape(&[n, 3, h, w], client);
+    let count = (n * h * w).div_ceil(self.dim as usize);
+    colorize::launch::<F, T, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      depth.into_tensor_arg(1),
+      palette.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+    )?;
+
+    Ok(output)
+  }
+}
+
+/// 分位 q 对应的元素个数，即最小的 v 满足 count(x <= v) >= rank
+fn rank(q: f32, hw: usize) -> u32 {
+  ((q * hw as f32).ceil() as u32).clamp(1, hw as u32)
+}
+
+/// 二分查找的迭代次数，区间每次减半，足以收敛到浮点精度
+const BISECTION_STEPS: u32 = 32;
+
+/// 求每张图像的归一化范围，每个 Cube 处理一张图像
+///
+/// input: [N, 1, H, W]，紧凑布局，range: 输出 [N, 2]，为 (low, high)
+/// percentile 为 true 时 low/high 为按 low_rank/high_rank 二分查找得到的分位数，否则为最小值与最大值
+#[cube(launch)]
+fn depth_range<F: Float>(
+  input: &Tensor<F>,
+  range: &mut Tensor<F>,
+  low_rank: u32,
+  high_rank: u32,
+  #[comptime] percentile: bool,
+  #[comptime] block: usize,
+) {
+  let n_idx = CUBE_POS;
+  let unit = UNIT_POS as usize;
+  let dim = CUBE_DIM as usize;
+
+  let hw = input.len() / range.shape(0);
+  let base = n_idx * hw;
+
+  let mut shared = SharedMemory::<F>::new(block);
+  let mut counts = SharedMemory::<u32>::new(block);
+
+  let mut local_max = F::min_value();
+  let mut local_neg_min = F::min_value();
+  let mut i = unit;
+  while i < hw {
+    let v = input[base + i];
+    local_max = F::max(local_max, v);
+    local_neg_min = F::max(local_neg_min, -v);
+    i += dim;
+  }
+  let max = block_max::<F>(local_max, &mut shared);
+  let min = -block_max::<F>(local_neg_min, &mut shared);
+
+  let (low, high) = if comptime!(percentile) {
+    (
+      quantile::<F>(input, base, hw, min, max, low_rank, &mut counts),
+      quantile::<F>(input, base, hw, min, max, high_rank, &mut counts),
+    )
+  } else {
+    (min, max)
+  };
+
+  if unit == 0 {
+    range[n_idx * 2] = low;
+    range[n_idx * 2 + 1] = high;
+  }
+}
+
+/// 在 [min, max] 内二分查找最小的 v 使得 count(x <= v) >= rank，所有线程得到相同的结果
+#[cube]
+fn quantile<F: Float>(
+  input: &Tensor<F>,
+  base: usize,
+  hw: usize,
+  min: F,
+  max: F,
+  rank: u32,
+  counts: &mut SharedMemory<u32>,
+) -> F {
+  let unit = UNIT_POS as usize;
+  let dim = CUBE_DIM as usize;
+  let half = F::new(comptime!(0.5));
+
+  let mut lo = min;
+  let mut hi = max;
+  for _ in 0..BISECTION_STEPS {
+    let mid = (lo + hi) * half;
+    let mut local = 0u32;
+    let mut i = unit;
+    while i < hw {
+      if input[base + i] <= mid {
+        local += 1;
+      }
+      i += dim;
+    }
+    let count = block_sum::<u32>(local, counts);
+    if count >= rank {
+      hi = mid;
+    } else {
+      lo = mid;
+    }
+  }
+  hi
+}
+
+/// 按范围归一化并截断到 [0, 1]，范围退化时输出 0
+/// input: [N, 1, H, W]，range: [N, 2]，output: [N, 1, H, W]
+#[cube(launch)]
+fn normalize<F: Float>(
+  input: &Tensor<F>,
+  range: &Tensor<F>,
+  output: &mut Tensor<F>,
+  #[comptime] invert: bool,
+) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+
+    let hw = output.len() / range.shape(0);
+    let n_idx = idx / hw;
+    let low = range[n_idx * 2];
+    let high = range[n_idx * 2 + 1];
+
+    let mut v = zero_value;
+    if high > low {
+      v = ((input[idx] - low) / (high - low)).clamp(zero_value, one_value);
+    }
+    if comptime!(invert) {
+      v = one_value - v;
+    }
+    output[idx] = v;
+  }
+}
+
+/// 将 [0, 1] 的值量化为调色板索引并查找颜色
+/// depth: 输入 [N, 1, H, W]，palette: [P, 3]，output: 输出 [N, 3, H, W]
+#[cube(launch)]
+fn colorize<F: Float, T: Numeric>(depth: &Tensor<F>, palette: &Tensor<T>, output: &mut Tensor<T>) {
+  let idx = ABSOLUTE_POS;
+  let n_dim = depth.shape(0);
+  let hw = depth.shape(2) * depth.shape(3);
+  if idx < n_dim * hw {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+
+    let n_idx = idx / hw;
+    let rem = idx % hw;
+    let h_idx = rem / depth.shape(3);
+    let w_idx = rem % depth.shape(3);
+
+    let v = depth[n_idx * depth.stride(0) + h_idx * depth.stride(2) + w_idx * depth.stride(3)];
+    let levels = palette.shape(0);
+    let scaled = v.clamp(zero_value, one_value) * F::cast_from(levels - 1) + half_value;
+    let color = u32::cast_from(scaled.floor()) as usize;
+
+    let out = n_idx * 3 * hw + rem;
+    output[out] = palette[color * 3];
+    output[out + hw] = palette[color * 3 + 1];
+    output[out + 2 * hw] = palette[color * 3 + 2];
+  }
+}
@@ -16,6 +16,23 @@ use thiserror::Error;
 
 use crate::{data::DataBuffer, kernel::sigmoid};
 
+mod centernet;
+mod detr;
+mod obb;
+mod pose;
+mod result;
+mod ssd;
+mod yolo5;
+mod yolo8;
+pub use centernet::{CenterNet, CenterNetConfig, CenterNetError};
+pub use detr::{Detr, DetrConfig, DetrError};
+pub use obb::{Obb, ObbConfig, ObbError, ObbLevel};
+pub use pose::{Pose, PoseConfig, PoseError, PoseLevel};
+pub use result::{BoxEncoding, BoxFormat, BoxLayout, BoxUnit, Detection, DetectionSet};
+pub use ssd::{PriorBox, PriorBoxConfig, PriorLevel, Ssd, SsdConfig, SsdError};
+pub use yolo5::{Yolo5, Yolo5Config, Yolo5Error, Yolo5Level};
+pub use yolo8::{Yolo8, Yolo8Config, Yolo8Error, Yolo8Level};
+
 #[derive(Debug, Error)]
 pub enum Yolo26Error {
   #[error("无效的输入形状: {0}")]
@@ -27,6 +44,7 @@ pub struct Yolo26Config {
   width: u32,
   height: u32,
   dim: u32,
+  encoding: BoxEncoding,
 }
 
 impl Default for Yolo26Config {
@@ -35,6 +53,7 @@ impl Default for Yolo26Config {
       width: 640,
       height: 640,
       dim: 1,
+      encoding: BoxEncoding::default(),
     }
   }
 }
@@ -51,6 +70,7 @@ impl Yolo26Config {
       width: self.width,
       height: self.height,
       dim: self.dim,
+      encoding: self.encoding,
     })
   }
 
@@ -58,21 +78,142 @@ impl Yolo26Config {
     self.dim = dim;
     self
   }
+
+  /// 输出边界框的格式，默认为 Xyxy
+  pub fn with_box_format(mut self, format: BoxFormat) -> Self {
+    self.encoding.format = format;
+    self
+  }
+
+  /// 输出边界框的坐标单位，默认为 Normalized
+  pub fn with_box_unit(mut self, unit: BoxUnit) -> Self {
+    self.encoding.unit = unit;
+    self
+  }
+
+  /// 是否将边界框裁剪到图像范围内，默认为 true
+  pub fn with_clamp(mut self, clamp: bool) -> Self {
+    self.encoding.clamp = clamp;
+    self
+  }
+
+  /// 输出边界框张量的排列方式，默认为 Planar
+  pub fn with_box_layout(mut self, layout: BoxLayout) -> Self {
+    self.encoding.layout = layout;
+    self
+  }
 }
 
 pub struct Yolo26 {
   width: u32,
   height: u32,
   dim: u32,
+  encoding: BoxEncoding,
 }
 
 pub type PPResult<R, F, I> = (DataBuffer<R, F>, DataBuffer<R, I>, DataBuffer<R, F>);
 
+/// 单个检测层级的输入 (cls, reg, stride)
+pub type Yolo26Level<R, F> = (DataBuffer<R, F>, DataBuffer<R, F>, F);
+
+/// 紧凑排列的候选框列表，每张图像最多保留 K 个候选
+///
+/// 仅每张图像前 `count[n]` 个位置有效，其余位置填充为 0
+#[derive(Debug)]
+pub struct Candidates<R: Runtime, F: CubeElement, I: CubeElement> {
+  /// 候选得分 [N, K]
+  pub score: DataBuffer<R, F>,
+  /// 候选类别索引 [N, K]
+  pub index: DataBuffer<R, I>,
+  /// 候选边界框 [N, 4, K]，为 xmin, ymin, xmax, ymax；旋转框 NMS 的输出为 [N, 5, K]，为 cx, cy, w, h, θ
+  pub bbox: DataBuffer<R, F>,
+  /// 候选在稠密输入中的位置 [N, K]，即 h * W + w，多层级时为拼接后的位置
+  pub source: DataBuffer<R, u32>,
+  /// 每张图像的有效候选数量 [N]
+  pub count: DataBuffer<R, u32>,
+}
+
+/// 检查稠密检测结果 score [N, ...]、index [N, ...]、bbox [N, 4, ...] 的形状是否一致
+/// 返回 (N, M)，M 为每张图像的位置数量
+pub(crate) fn candidate_shape(
+  score: &[usize],
+  index: &[usize],
+  bbox: &[usize],
+) -> Result<(usize, usize), String> {
+  dense_shape(score, index, bbox, BoxLayout::Planar)
+}
+
+/// 与 candidate_shape 相同，layout 为 Interleaved 时 bbox 应为 [N, ..., 4]
+pub(crate) fn dense_shape(
+  score: &[usize],
+  index: &[usize],
+  bbox: &[usize],
+  layout: BoxLayout,
+) -> Result<(usize, usize), String> {
+  let n = match score.first() {
+    Some(&n) if n > 0 => n,
+    _ => return Err("得分张量形状不正确，预期为 [N, H, W]".to_string()),
+  };
+  let m = score[1..].iter().product::<usize>();
+  if index != score {
+    return Err("类别索引张量形状应与得分张量一致".to_string());
+  }
+  if bbox.len() < 2 || bbox[0] != n {
+    return Err("边界框张量形状不正确，预期为 [N, 4, H, W] 或 [N, H, W, 4]".to_string());
+  }
+  let valid = match layout {
+    BoxLayout::Planar => bbox[1] == 4 && bbox[2..].iter().product::<usize>() == m,
+    BoxLayout::Interleaved => {
+      bbox[bbox.len() - 1] == 4 && bbox[1..bbox.len() - 1].iter().product::<usize>() == m
+    }
+  };
+  if !valid {
+    return Err(match layout {
+      BoxLayout::Planar => "边界框张量形状不正确，预期为 [N, 4, H, W]".to_string(),
+      BoxLayout::Interleaved => "边界框张量形状不正确，预期为 [N, H, W, 4]".to_string(),
+    });
+  }
+  Ok((n, m))
+}
+
+/// 候选压缩与 NMS 只支持 [N, 4, ...] 排列的 xyxy，坐标单位与是否裁剪不影响结果
+pub(crate) fn check_xyxy_planar(encoding: &BoxEncoding) -> Result<(), String> {
+  if encoding.format != BoxFormat::Xyxy || encoding.layout != BoxLayout::Planar {
+    return Err(format!(
+      "仅支持 [N, 4, ...] 排列的 xyxy 边界框，当前为 {:?} {:?}",
+      encoding.layout, encoding.format
+    ));
+  }
+  Ok(())
+}
+
+/// 按 layout 给出 [N, 4, ...] 或 [N, ..., 4] 的边界框张量形状，dims 为除 N 外的位置维度
+fn bbox_shape(n: usize, dims: &[usize], layout: BoxLayout) -> Vec<usize> {
+  let mut shape = vec![n];
+  match layout {
+    BoxLayout::Planar => {
+      shape.push(4);
+      shape.extend_from_slice(dims);
+    }
+    BoxLayout::Interleaved => {
+      shape.extend_from_slice(dims);
+      shape.push(4);
+    }
+  }
+  shape
+}
+
 impl Yolo26 {
+  /// 输出边界框的编码方式，可用于 DetectionSet::from_dense_encoded 等读取输出
+  pub fn box_encoding(&self) -> BoxEncoding {
+    self.encoding
+  }
+
   /// 执行后处理操作
   /// cls: 分类结果，形状为 [N, num_classes, H, W]
   /// reg: 回归结果，形状为 [N, 4, H, W]
-  /// 返回 (score, index, bbox) 三个张量，分别是分类得分、类别索引和边界框坐标
+  /// 返回 (score, index, bbox) 三个张量，分别是分类得分、类别索引和边界框坐标，
+  /// bbox 按 box_encoding 编码，排列为 [N, 4, H, W] 或 [N, H, W, 4]
   pub fn execute<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
     &self,
     client: &ComputeClient<R>,
@@ -80,11 +221,96 @@ impl Yolo26 {
     reg: DataBuffer<R, F>,
     stride: F,
   ) -> Result<PPResult<R, F, I>, Yolo26Error> {
+    let [n, _, h, w] = *cls.shape() else {
+      return Err(Yolo26Error::InvalidInputShape(
+        "分类结果张量形状不正确，预期为 [N, num_classes, H, W]".to_string(),
+      ));
+    };
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, h, w], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, h, w], client);
+    let bbox: DataBuffer<R, F> =
+      DataBuffer::with_shape(&bbox_shape(n, &[h, w], self.encoding.layout), client);
+
+    self.decode_level(client, cls, reg, stride, &score, &index, &bbox, 0)?;
+
+    Ok((score, index, bbox))
+  }
+
+  /// 对多个检测头 (如 P3/P4/P5) 执行后处理，并将结果按层级顺序拼接
+  /// levels: 每个层级的 (cls, reg, stride)，cls 形状为 [N, num_classes, H_i, W_i]，
+  ///         reg 形状为 [N, 4, H_i, W_i]，各层级的 N 与 num_classes 必须一致
+  /// 返回的 score/index 形状为 [N, M]，bbox 形状为 [N, 4, M] (Interleaved 时为 [N, M, 4])，其中 M = sum(H_i * W_i)，
+  /// 第 i 层的位置 h * W_i + w 对应拼接后的 sum(H_j * W_j, j < i) + h * W_i + w
+  pub fn execute_levels<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<Yolo26Level<R, F>>,
+  ) -> Result<PPResult<R, F, I>, Yolo26Error> {
+    let Some((first, _, _)) = levels.first() else {
+      return Err(Yolo26Error::InvalidInputShape(
+        "至少需要一个检测层级".to_string(),
+      ));
+    };
+    let [n, c, _, _] = *first.shape() else {
+      return Err(Yolo26Error::InvalidInputShape(
+        "分类结果张量形状不正确，预期为 [N, num_classes, H, W]".to_string(),
+      ));
+    };
+
+    let mut m = 0;
+    for (cls, _, _) in &levels {
+      let [ln, lc, h, w] = *cls.shape() else {
+        return Err(Yolo26Error::InvalidInputShape(
+          "分类结果张量形状不正确，预期为 [N, num_classes, H, W]".to_string(),
+        ));
+      };
+      if ln != n || lc != c {
+        return Err(Yolo26Error::InvalidInputShape(
+          "各层级分类结果的 N 与 num_classes 必须一致".to_string(),
+        ));
+      }
+      m += h * w;
+    }
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, m], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, m], client);
+    let bbox: DataBuffer<R, F> =
+      DataBuffer::with_shape(&bbox_shape(n, &[m], self.encoding.layout), client);
+
+    let mut offset = 0;
+    for (cls, reg, stride) in levels {
+      let hw = cls.shape()[2] * cls.shape()[3];
+      self.decode_level(client, cls, reg, stride, &score, &index, &bbox, offset)?;
+      offset += hw;
+    }
+
+    Ok((score, index, bbox))
+  }
+
+  /// 解码单个层级，并写入输出中每张图像的 [offset, offset + H * W) 位置
+  #[allow(clippy::too_many_arguments)]
+  fn decode_level<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    cls: DataBuffer<R, F>,
+    reg: DataBuffer<R, F>,
+    stride: F,
+    score: &DataBuffer<R, F>,
+    index: &DataBuffer<R, I>,
+    bbox: &DataBuffer<R, F>,
+    offset: usize,
+  ) -> Result<(), Yolo26Error> {
     let [n, c, h, w] = *cls.shape() else {
       return Err(Yolo26Error::InvalidInputShape(
         "分类结果张量形状不正确，预期为 [N, num_classes, H, W]".to_string(),
       ));
     };
+    if reg.shape() != [n, 4, h, w] {
+      return Err(Yolo26Error::InvalidInputShape(
+        "回归结果张量形状不正确，预期为 [N, 4, H, W]".to_string(),
+      ));
+    }
 
     let cls_sigmoid = cls.empty_like(client);
 
@@ -97,9 +323,6 @@ impl Yolo26 {
       cls_sigmoid.into_tensor_arg(1),
     )?;
 
-    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, h, w], client);
-    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, h, w], client);
-
     let count = (n * h * w).div_ceil(self.dim as usize);
     classify::launch::<F, I, R>(
       client,
@@ -108,10 +331,9 @@ impl Yolo26 {
       cls_sigmoid.into_tensor_arg(1),
       score.into_tensor_arg(1),
       index.into_tensor_arg(1),
+      ScalarArg::new(offset as u32),
     )?;
 
-    let bbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, h, w], client);
-
     bbox::launch::<F, R>(
       client,
       CubeCount::Static(count as u32, 1, 1),
@@ -121,26 +343,38 @@ impl Yolo26 {
       ScalarArg::new(F::new(self.width as f32)),
       ScalarArg::new(F::new(self.height as f32)),
       ScalarArg::new(stride),
+      ScalarArg::new(offset as u32),
+      self.encoding.format,
+      self.encoding.unit,
+      self.encoding.clamp,
+      self.encoding.layout,
     )?;
 
-    Ok((score, index, bbox))
+    Ok(())
   }
 }
 
 /// 将 Yolo 检测结果中的分类指标进行处理，输出每个位置的最大分类得分和对应的类别索引
 ///
 /// cls: 输入分类结果，形状为 [N, num_classes, H, W], 应该已经调用过 sigmoid 激活函数
-/// score: 输出分类结果得分 [N, H, W]
-/// index: 输出分类结果类型索引 [N, H, W]
+/// score: 输出分类结果得分 [N, M]
+/// index: 输出分类结果类型索引 [N, M]
+/// offset: 写入输出中每张图像的 [offset, offset + H * W) 位置，M >= offset + H * W
 #[cube(launch)]
-fn classify<F: Float, I: Int>(cls: Tensor<F>, score: &mut Tensor<F>, index: &mut Tensor<I>) {
-  // 输出张量总元素 = N * H * W
-  let nhw = score.len();
+pub(crate) fn classify<F: Float, I: Int>(
+  cls: Tensor<F>,
+  score: &mut Tensor<F>,
+  index: &mut Tensor<I>,
+  offset: u32,
+) {
+  // 需要处理的总元素 = N * H * W
+  let nhw = cls.shape(0) * cls.shape(2) * cls.shape(3);
 
   // 线程全局索引
   let idx = ABSOLUTE_POS;
   if idx < nhw {
     // 获取输入维度
+    let n_dim = cls.shape(0);
     let c_dim = cls.shape(1);
     let h_dim = cls.shape(2);
     let w_dim = cls.shape(3);
@@ -176,24 +410,35 @@ fn classify<F: Float, I: Int>(cls: Tensor<F>, score: &mut Tensor<F>, index: &mut
     }
 
     // 写入输出: 最大值 + 对应通道索引
-    score[idx] = best_val;
-    index[idx] = I::cast_from(best_c);
+    let m = score.len() / n_dim;
+    let out = n_idx * m + offset as usize + rem;
+    score[out] = best_val;
+    index[out] = I::cast_from(best_c);
   }
 }
 
 /// 将 Yolo 检测结果中的回归指标进行处理，输出每个位置的边界框坐标
 /// reg: 输入回归结果，形状为 [N, 4, H, W], 包含 (cx, cy, w, h) 四个通道
-/// bbox: 输出边界框坐标，形状为 [N, 4, H, W] 为 xmin, ymin, xmax, ymax
+/// bbox: 输出边界框坐标，layout 为 Planar 时形状为 [N, 4, M]，Interleaved 时为 [N, M, 4]，
+///       四个分量由 format 决定，unit 为 Normalized 时除以图像宽高
+/// offset: 写入输出中每张图像的 [offset, offset + H * W) 位置，M >= offset + H * W
+/// clamp: 是否先将角点裁剪到图像范围内
 #[cube(launch)]
+#[allow(clippy::too_many_arguments)]
 fn bbox<F: Float + CubeScalar + Zero>(
   reg: Tensor<F>,
   bbox: &mut Tensor<F>,
   image_width: F,
   image_height: F,
   stride: F,
+  offset: u32,
+  #[comptime] format: BoxFormat,
+  #[comptime] unit: BoxUnit,
+  #[comptime] clamp: bool,
+  #[comptime] layout: BoxLayout,
 ) {
-  // 输出张量总元素 = N * H * W
-  let nhw = bbox.len() / 4; // 每个位置有4个坐标
+  // 需要处理的总元素 = N * H * W
+  let nhw = reg.shape(0) * reg.shape(2) * reg.shape(3);
 
   // 线程全局索引
   let idx = ABSOLUTE_POS;
@@ -232,15 +477,58 @@ fn bbox<F: Float + CubeScalar + Zero>(
     let grid_x = F::cast_from(w_idx) + half_value;
     let grid_y = F::cast_from(h_idx) + half_value;
 
-    let xmin = (grid_x - cx) * stride;
-    let ymin = (grid_y - cy) * stride;
-    let xmax = (grid_x + cw) * stride;
-    let ymax = (grid_y + ch) * stride;
+    let mut xmin = (grid_x - cx) * stride;
+    let mut ymin = (grid_y - cy) * stride;
+    let mut xmax = (grid_x + cw) * stride;
+    let mut ymax = (grid_y + ch) * stride;
+
+    // 按单位缩放并裁剪角点 (xmin, ymin, xmax, ymax)
+    let (x_scale, y_scale) = if comptime!(unit == BoxUnit::Normalized) {
+      (image_width, image_height)
+    } else {
+      (one_value, one_value)
+    };
+    xmin /= x_scale;
+    ymin /= y_scale;
+    xmax /= x_scale;
+    ymax /= y_scale;
+    if comptime!(clamp) {
+      let (x_max, y_max) = if comptime!(unit == BoxUnit::Normalized) {
+        (one_value, one_value)
+      } else {
+        (image_width, image_height)
+      };
+      xmin = xmin.clamp(zero_value, x_max);
+      ymin = ymin.clamp(zero_value, y_max);
+      xmax = xmax.clamp(zero_value, x_max);
+      ymax = ymax.clamp(zero_value, y_max);
+    }
 
-    // 转换为边界框坐标 (xmin, ymin, xmax, ymax)
-    bbox[idx] = (xmin / image_width).clamp(zero_value, one_value); // xmin
-    bbox[idx + nhw] = (ymin / image_height).clamp(zero_value, one_value); // ymin
-    bbox[idx + 2 * nhw] = (xmax / image_width).clamp(zero_value, one_value); // xmax
-    bbox[idx + 3 * nhw] = (ymax / image_height).clamp(zero_value, one_value); // ymax
+    // 转换为输出格式的四个分量
+    let mut v0 = xmin;
+    let mut v1 = ymin;
+    let mut v2 = xmax;
+    let mut v3 = ymax;
+    if comptime!(format == BoxFormat::Xywh) {
+      v2 = xmax - xmin;
+      v3 = ymax - ymin;
+    } else if comptime!(format == BoxFormat::Cxcywh) {
+      v0 = (xmin + xmax) * half_value;
+      v1 = (ymin + ymax) * half_value;
+      v2 = xmax - xmin;
+      v3 = ymax - ymin;
+    }
+
+    // 按排列方式写入输出 [N, 4, M] 或 [N, M, 4]
+    let m = bbox.len() / (4 * reg.shape(0));
+    let (out, step) = if comptime!(layout == BoxLayout::Planar) {
+      (n_idx * 4 * m + offset as usize + rem, m)
+    } else {
+      ((n_idx * m + offset as usize + rem) * 4, 1)
+    };
+    bbox[out] = v0;
+    bbox[out + step] = v1;
+    bbox[out + 2 * step] = v2;
+    bbox[out + 3 * step] = v3;
   }
 }
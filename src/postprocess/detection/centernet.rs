@@ -0,0 +1,361 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/centernet.rs - CenterNet 热力图峰值提取与解码
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use super::Candidates;
+use crate::{
+  data::DataBuffer,
+  kernel::{compact_top_k, sigmoid, sort_by_score},
+};
+
+#[derive(Debug, Error)]
+pub enum CenterNetError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct CenterNetConfig {
+  width: u32,
+  height: u32,
+  top_k: u32,
+  score_threshold: f32,
+  sigmoid: bool,
+  dim: u32,
+}
+
+impl Default for CenterNetConfig {
+  fn default() -> Self {
+    Self {
+      width: 512,
+      height: 512,
+      top_k: 100,
+      score_threshold: 0.1,
+      sigmoid: true,
+      dim: 256,
+    }
+  }
+}
+
+impl CenterNetConfig {
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 每张图像保留得分最高的 K 个峰值
+  pub fn with_top_k(mut self, top_k: u32) -> Self {
+    self.top_k = top_k;
+    self
+  }
+
+  /// 低于该得分的峰值不计入有效数量
+  pub fn with_score_threshold(mut self, score_threshold: f32) -> Self {
+    self.score_threshold = score_threshold;
+    self
+  }
+
+  /// 为 true 时先对热力图做 sigmoid，模型已输出概率时设为 false
+  pub fn with_sigmoid(mut self, sigmoid: bool) -> Self {
+    self.sigmoid = sigmoid;
+    self
+  }
+
+  /// Top-K 时每张图像使用一个 Cube 处理，dim 为 Cube 内的线程数，必须为 2 的幂
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<CenterNet, CenterNetError> {
+    if !self.dim.is_power_of_two() {
+      return Err(CenterNetError::InvalidConfig(format!(
+        "dim 必须为 2 的幂，当前为 {}",
+        self.dim
+      )));
+    }
+    if self.top_k == 0 {
+      return Err(CenterNetError::InvalidConfig(
+        "top_k 必须大于 0".to_string(),
+      ));
+    }
+    Ok(CenterNet {
+      width: self.width,
+      height: self.height,
+      top_k: self.top_k,
+      score_threshold: self.score_threshold,
+      sigmoid: self.sigmoid,
+      dim: self.dim,
+    })
+  }
+}
+
+/// CenterNet 风格的无锚框检测后处理
+///
+/// 以 3x3 最大池化保留热力图中的局部峰值代替 NMS，再取全部类别中得分最高的 K 个峰值，
+/// 结合尺寸与偏移检测头解码为边界框
+pub struct CenterNet {
+  width: u32,
+  height: u32,
+  top_k: u32,
+  score_threshold: f32,
+  sigmoid: bool,
+  dim: u32,
+}
+
+impl CenterNet {
+  /// 执行后处理操作
+  /// heatmap: 类别热力图，形状为 [N, num_classes, H, W]
+  /// size: 尺寸结果，形状为 [N, 2, H, W]，为以特征图网格为单位的 (w, h)
+  /// offset: 中心偏移结果，形状为 [N, 2, H, W]，为以特征图网格为单位的 (dx, dy)
+  /// stride: 特征图相对网络输入的下采样倍数
+  /// 返回按得分降序排列的 K 个峰值，bbox 为归一化的 xmin, ymin, xmax, ymax，
+  /// source 为峰值在 H * W 中的位置，得分低于阈值的峰值不计入 count
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    heatmap: DataBuffer<R, F>,
+    size: DataBuffer<R, F>,
+    offset: DataBuffer<R, F>,
+    stride: F,
+  ) -> Result<Candidates<R, F, I>, CenterNetError> {
+    let [n, c, h, w] = *heatmap.shape() else {
+      return Err(CenterNetError::InvalidInputShape(
+        "热力图张量形状不正确，预期为 [N, num_classes, H, W]".to_string(),
+      ));
+    };
+    if size.shape() != [n, 2, h, w] {
+      return Err(CenterNetError::InvalidInputShape(
+        "尺寸结果张量形状不正确，预期为 [N, 2, H, W]".to_string(),
+      ));
+    }
+    if offset.shape() != [n, 2, h, w] {
+      return Err(CenterNetError::InvalidInputShape(
+        "偏移结果张量形状不正确，预期为 [N, 2, H, W]".to_string(),
+      ));
+    }
+    let k = self.top_k as usize;
+    if k > c * h * w {
+      return Err(CenterNetError::InvalidInputShape(format!(
+        "top_k ({}) 不能大于热力图元素数量 ({})",
+        k,
+        c * h * w
+      )));
+    }
+
+    let elements = n * c * h * w;
+    let heatmap = if self.sigmoid {
+      let output = heatmap.empty_like(client);
+      sigmoid::launch::<F, R>(
+        client,
+        CubeCount::Static(elements.div_ceil(self.dim as usize) as u32, 1, 1),
+        CubeDim::new_1d(self.dim),
+        heatmap.into_tensor_arg(1),
+        output.into_tensor_arg(1),
+      )?;
+      output
+    } else {
+      heatmap
+    };
+
+    // 峰值写入连续的 [N, C, H, W]，Top-K 将其视为 [N, C * H * W]
+    let peaks: DataBuffer<R, F> = DataBuffer::with_shape(&[n, c, h, w], client);
+    peak::launch::<F, R>(
+      client,
+      CubeCount::Static(elements.div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      heatmap.into_tensor_arg(1),
+      peaks.into_tensor_arg(1),
+    )?;
+
+    // 先按阈值选出最高的 K 个峰值，再按得分降序排列
+    let selected_value: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let selected_pos: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, k], client);
+    let count: DataBuffer<R, u32> = DataBuffer::with_shape(&[n], client);
+    compact_top_k::launch::<F, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      peaks.into_tensor_arg(1),
+      selected_value.into_tensor_arg(1),
+      selected_pos.into_tensor_arg(1),
+      count.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.score_threshold)),
+      self.dim as usize,
+    )?;
+
+    let top_value: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let top_pos: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, k], client);
+    sort_by_score::launch::<F, R>(
+      client,
+      CubeCount::Static((n * k).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      selected_value.into_tensor_arg(1),
+      selected_pos.into_tensor_arg(1),
+      count.into_tensor_arg(1),
+      top_value.into_tensor_arg(1),
+      top_pos.into_tensor_arg(1),
+    )?;
+
+    let candidates = Candidates {
+      score: DataBuffer::with_shape(&[n, k], client),
+      index: DataBuffer::with_shape(&[n, k], client),
+      bbox: DataBuffer::with_shape(&[n, 4, k], client),
+      source: DataBuffer::with_shape(&[n, k], client),
+      count,
+    };
+    decode::launch::<F, I, R>(
+      client,
+      CubeCount::Static((n * k).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      top_value.into_tensor_arg(1),
+      top_pos.into_tensor_arg(1),
+      size.into_tensor_arg(1),
+      offset.into_tensor_arg(1),
+      candidates.score.into_tensor_arg(1),
+      candidates.index.into_tensor_arg(1),
+      candidates.bbox.into_tensor_arg(1),
+      candidates.source.into_tensor_arg(1),
+      candidates.count.into_tensor_arg(1),
+      ScalarArg::new(stride),
+      ScalarArg::new(F::new(self.width as f32)),
+      ScalarArg::new(F::new(self.height as f32)),
+    )?;
+
+    Ok(candidates)
+  }
+}
+
+/// 3x3 最大池化峰值提取，非局部最大值的位置置为最小值
+///
+/// 与 CenterNet 的 `hmax == heat` 判定一致，邻域越界部分不参与比较
+/// heatmap: 输入 [N, C, H, W]，peaks: 输出连续的 [N, C, H, W]
+#[cube(launch)]
+fn peak<F: Float>(heatmap: &Tensor<F>, peaks: &mut Tensor<F>) {
+  let h_dim = heatmap.shape(2);
+  let w_dim = heatmap.shape(3);
+
+  let idx = ABSOLUTE_POS;
+  if idx < peaks.len() {
+    let nc_idx = idx / (h_dim * w_dim);
+    let rem = idx % (h_dim * w_dim);
+    let h_idx = rem / w_dim;
+    let w_idx = rem % w_dim;
+
+    let c_dim = heatmap.shape(1);
+    let base = (nc_idx / c_dim) * heatmap.stride(0) + (nc_idx % c_dim) * heatmap.stride(1);
+    let stride_h = heatmap.stride(2);
+    let stride_w = heatmap.stride(3);
+
+    let center = heatmap[base + h_idx * stride_h + w_idx * stride_w];
+    let mut neighbour_max = center;
+    for dy in 0..3usize {
+      for dx in 0..3usize {
+        let y = h_idx + dy;
+        let x = w_idx + dx;
+        // y/x 相对真实坐标偏移了 1，避免无符号下溢
+        if y >= 1 && y <= h_dim && x >= 1 && x <= w_dim {
+          let v = heatmap[base + (y - 1) * stride_h + (x - 1) * stride_w];
+          neighbour_max = F::max(neighbour_max, v);
+        }
+      }
+    }
+
+    if neighbour_max == center {
+      peaks[idx] = center;
+    } else {
+      peaks[idx] = F::min_value();
+    }
+  }
+}
+
+/// 将 Top-K 峰值解码为候选框
+///
+/// top_value/top_pos: 输入 [N, K]，按得分降序排列，top_pos 为峰值在 C * H * W 中的位置
+/// size/offset: 输入 [N, 2, H, W]
+/// count: 每张图像的有效峰值数量 [N]
+/// score/index/source: 输出 [N, K]，bbox: 输出 [N, 4, K]
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+fn decode<F: Float + CubeScalar, I: Int>(
+  top_value: &Tensor<F>,
+  top_pos: &Tensor<u32>,
+  size: &Tensor<F>,
+  offset: &Tensor<F>,
+  score: &mut Tensor<F>,
+  index: &mut Tensor<I>,
+  bbox: &mut Tensor<F>,
+  source: &mut Tensor<u32>,
+  count: &Tensor<u32>,
+  stride: F,
+  image_width: F,
+  image_height: F,
+) {
+  let n_dim = top_value.shape(0);
+  let k_dim = top_value.shape(1);
+  let h_dim = size.shape(2);
+  let w_dim = size.shape(3);
+  let hw = h_dim * w_dim;
+
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * k_dim {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+
+    let n_idx = idx / k_dim;
+    let k_idx = idx % k_dim;
+
+    // 有效峰值为前 count[n] 个
+    let value = top_value[idx];
+    let valid = (k_idx as u32) < count[n_idx];
+
+    let out = n_idx * 4 * k_dim + k_idx;
+    if valid {
+      let pos = top_pos[idx] as usize;
+      let rem = pos % hw;
+      let h_idx = rem / w_dim;
+      let w_idx = rem % w_dim;
+
+      let size_base = n_idx * size.stride(0) + h_idx * size.stride(2) + w_idx * size.stride(3);
+      let offset_base =
+        n_idx * offset.stride(0) + h_idx * offset.stride(2) + w_idx * offset.stride(3);
+      let cx = F::cast_from(w_idx) + offset[offset_base];
+      let cy = F::cast_from(h_idx) + offset[offset_base + offset.stride(1)];
+      let half_w = size[size_base] * half_value;
+      let half_h = size[size_base + size.stride(1)] * half_value;
+
+      score[idx] = value;
+      index[idx] = I::cast_from(pos / hw);
+      source[idx] = rem as u32;
+      bbox[out] = ((cx - half_w) * stride / image_width).clamp(zero_value, one_value);
+      bbox[out + k_dim] = ((cy - half_h) * stride / image_height).clamp(zero_value, one_value);
+      bbox[out + 2 * k_dim] = ((cx + half_w) * stride / image_width).clamp(zero_value, one_value);
+      bbox[out + 3 * k_dim] = ((cy + half_h) * stride / image_height).clamp(zero_value, one_value);
+    } else {
+      score[idx] = zero_value;
+      index[idx] = I::cast_from(0u32);
+      source[idx] = 0;
+      bbox[out] = zero_value;
+      bbox[out + k_dim] = zero_value;
+      bbox[out + 2 * k_dim] = zero_value;
+      bbox[out + 3 * k_dim] = zero_value;
+    }
+  }
+}
@@ -0,0 +1,344 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/detr.rs - DETR/RT-DETR 查询式检测后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use super::{BoxUnit, Candidates};
+use crate::{
+  data::DataBuffer,
+  kernel::{compact_top_k, sort_by_score},
+};
+
+#[derive(Debug, Error)]
+pub enum DetrError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct DetrConfig {
+  width: u32,
+  height: u32,
+  top_k: u32,
+  score_threshold: f32,
+  softmax: bool,
+  unit: BoxUnit,
+  dim: u32,
+}
+
+impl Default for DetrConfig {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      top_k: 300,
+      score_threshold: 0.25,
+      softmax: false,
+      unit: BoxUnit::Normalized,
+      dim: 256,
+    }
+  }
+}
+
+impl DetrConfig {
+  /// 输出为像素坐标时使用的图像尺寸
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 每张图像在 Q * C 个 (查询, 类别) 组合中保留得分最高的 K 个
+  pub fn with_top_k(mut self, top_k: u32) -> Self {
+    self.top_k = top_k;
+    self
+  }
+
+  /// 低于该得分的结果不计入有效数量
+  pub fn with_score_threshold(mut self, score_threshold: f32) -> Self {
+    self.score_threshold = score_threshold;
+    self
+  }
+
+  /// 为 true 时按 DETR 对类别做 softmax，最后一个类别为“无目标”，不参与排序；
+  /// 否则按 RT-DETR 对每个类别做 sigmoid
+  pub fn with_softmax(mut self, softmax: bool) -> Self {
+    self.softmax = softmax;
+    self
+  }
+
+  /// 输出边界框的单位，为 BoxUnit::Pixel 时按 with_shape 的尺寸缩放
+  pub fn with_unit(mut self, unit: BoxUnit) -> Self {
+    self.unit = unit;
+    self
+  }
+
+  /// 每张图像使用一个 Cube 处理，dim 为 Cube 内的线程数，必须为 2 的幂
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Detr, DetrError> {
+    if !self.dim.is_power_of_two() {
+      return Err(DetrError::InvalidConfig(format!(
+        "dim 必须为 2 的幂，当前为 {}",
+        self.dim
+      )));
+    }
+    if self.top_k == 0 {
+      return Err(DetrError::InvalidConfig("top_k 必须大于 0".to_string()));
+    }
+    Ok(Detr {
+      width: self.width,
+      height: self.height,
+      top_k: self.top_k,
+      score_threshold: self.score_threshold,
+      softmax: self.softmax,
+      unit: self.unit,
+      dim: self.dim,
+    })
+  }
+}
+
+/// DETR/RT-DETR 后处理，查询之间没有网格关系，也不需要 NMS
+pub struct Detr {
+  width: u32,
+  height: u32,
+  top_k: u32,
+  score_threshold: f32,
+  softmax: bool,
+  unit: BoxUnit,
+  dim: u32,
+}
+
+impl Detr {
+  /// 执行后处理操作
+  /// logits: 分类结果，形状为 [N, Q, C]
+  /// boxes: 边界框，形状为 [N, Q, 4]，为归一化的 cx, cy, w, h
+  /// 返回按得分降序排列的 K 个结果，格式与 Nms::execute 一致，bbox 为 xmin, ymin, xmax, ymax，
+  /// source 为查询序号，得分低于阈值的结果不计入 count
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+    boxes: DataBuffer<R, F>,
+  ) -> Result<Candidates<R, F, I>, DetrError> {
+    let [n, q, c] = *logits.shape() else {
+      return Err(DetrError::InvalidInputShape(
+        "分类结果张量形状不正确，预期为 [N, Q, C]".to_string(),
+      ));
+    };
+    if boxes.shape() != [n, q, 4] {
+      return Err(DetrError::InvalidInputShape(
+        "边界框张量形状不正确，预期为 [N, Q, 4]".to_string(),
+      ));
+    }
+    let classes = if self.softmax { c.saturating_sub(1) } else { c };
+    if classes == 0 {
+      return Err(DetrError::InvalidInputShape(
+        "分类结果中没有目标类别".to_string(),
+      ));
+    }
+    let k = self.top_k as usize;
+    if k > q * classes {
+      return Err(DetrError::InvalidInputShape(format!(
+        "top_k ({}) 不能大于查询与类别的组合数量 ({})",
+        k,
+        q * classes
+      )));
+    }
+
+    let prob: DataBuffer<R, F> = DataBuffer::with_shape(&[n, q * classes], client);
+    probability::launch::<F, R>(
+      client,
+      CubeCount::Static((n * q).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      logits.into_tensor_arg(1),
+      prob.into_tensor_arg(1),
+      self.softmax,
+    )?;
+
+    // 先按阈值选出最高的 K 个组合，再按得分降序排列
+    let selected_value: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let selected_pos: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, k], client);
+    let count: DataBuffer<R, u32> = DataBuffer::with_shape(&[n], client);
+    compact_top_k::launch::<F, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      prob.into_tensor_arg(1),
+      selected_value.into_tensor_arg(1),
+      selected_pos.into_tensor_arg(1),
+      count.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.score_threshold)),
+      self.dim as usize,
+    )?;
+
+    let top_value: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let top_pos: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, k], client);
+    sort_by_score::launch::<F, R>(
+      client,
+      CubeCount::Static((n * k).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      selected_value.into_tensor_arg(1),
+      selected_pos.into_tensor_arg(1),
+      count.into_tensor_arg(1),
+      top_value.into_tensor_arg(1),
+      top_pos.into_tensor_arg(1),
+    )?;
+
+    let (scale_x, scale_y) = match self.unit {
+      BoxUnit::Normalized => (1.0, 1.0),
+      BoxUnit::Pixel => (self.width as f32, self.height as f32),
+    };
+    let candidates = Candidates {
+      score: DataBuffer::with_shape(&[n, k], client),
+      index: DataBuffer::with_shape(&[n, k], client),
+      bbox: DataBuffer::with_shape(&[n, 4, k], client),
+      source: DataBuffer::with_shape(&[n, k], client),
+      count,
+    };
+    decode::launch::<F, I, R>(
+      client,
+      CubeCount::Static((n * k).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      top_value.into_tensor_arg(1),
+      top_pos.into_tensor_arg(1),
+      boxes.into_tensor_arg(1),
+      candidates.score.into_tensor_arg(1),
+      candidates.index.into_tensor_arg(1),
+      candidates.bbox.into_tensor_arg(1),
+      candidates.source.into_tensor_arg(1),
+      candidates.count.into_tensor_arg(1),
+      ScalarArg::new(classes as u32),
+      ScalarArg::new(F::new(scale_x)),
+      ScalarArg::new(F::new(scale_y)),
+    )?;
+
+    Ok(candidates)
+  }
+}
+
+/// 计算每个查询的类别概率，每个线程处理一个查询
+///
+/// logits: 输入 [N, Q, C]，prob: 输出连续的 [N, Q * C']，
+/// softmax 时 C' = C - 1 (去掉最后的“无目标”类别)，否则 C' = C
+#[cube(launch)]
+fn probability<F: Float>(logits: &Tensor<F>, prob: &mut Tensor<F>, #[comptime] softmax: bool) {
+  let n_dim = logits.shape(0);
+  let q_dim = logits.shape(1);
+  let c_dim = logits.shape(2);
+
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * q_dim {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+
+    let n_idx = idx / q_dim;
+    let q_idx = idx % q_dim;
+    let base = n_idx * logits.stride(0) + q_idx * logits.stride(1);
+    let stride_c = logits.stride(2);
+
+    if comptime!(softmax) {
+      let classes = c_dim - 1;
+      let mut max_val = logits[base];
+      for c in 1..c_dim {
+        max_val = F::max(max_val, logits[base + c * stride_c]);
+      }
+      let mut sum = zero_value;
+      for c in 0..c_dim {
+        sum += (logits[base + c * stride_c] - max_val).exp();
+      }
+      for c in 0..classes {
+        prob[idx * classes + c] = (logits[base + c * stride_c] - max_val).exp() / sum;
+      }
+    } else {
+      for c in 0..c_dim {
+        prob[idx * c_dim + c] = one_value / (one_value + (-logits[base + c * stride_c]).exp());
+      }
+    }
+  }
+}
+
+/// 将 Top-K 结果解码为候选框
+///
+/// top_value/top_pos: 输入 [N, K]，按得分降序排列，top_pos 为在 Q * C' 中的位置
+/// boxes: 输入 [N, Q, 4]，为归一化的 cx, cy, w, h
+/// count: 每张图像的有效结果数量 [N]
+/// score/index/source: 输出 [N, K]，bbox: 输出 [N, 4, K]
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+fn decode<F: Float + CubeScalar, I: Int>(
+  top_value: &Tensor<F>,
+  top_pos: &Tensor<u32>,
+  boxes: &Tensor<F>,
+  score: &mut Tensor<F>,
+  index: &mut Tensor<I>,
+  bbox: &mut Tensor<F>,
+  source: &mut Tensor<u32>,
+  count: &Tensor<u32>,
+  classes: u32,
+  scale_x: F,
+  scale_y: F,
+) {
+  let n_dim = top_value.shape(0);
+  let k_dim = top_value.shape(1);
+
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * k_dim {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+
+    let n_idx = idx / k_dim;
+    let k_idx = idx % k_dim;
+
+    // 有效结果为前 count[n] 个
+    let value = top_value[idx];
+    let valid = (k_idx as u32) < count[n_idx];
+
+    let out = n_idx * 4 * k_dim + k_idx;
+    if valid {
+      let pos = top_pos[idx];
+      let q_idx = (pos / classes) as usize;
+
+      let base = n_idx * boxes.stride(0) + q_idx * boxes.stride(1);
+      let stride_c = boxes.stride(2);
+      let cx = boxes[base];
+      let cy = boxes[base + stride_c];
+      let half_w = boxes[base + 2 * stride_c] * half_value;
+      let half_h = boxes[base + 3 * stride_c] * half_value;
+
+      score[idx] = value;
+      index[idx] = I::cast_from(pos % classes);
+      source[idx] = q_idx as u32;
+      bbox[out] = (cx - half_w).clamp(zero_value, one_value) * scale_x;
+      bbox[out + k_dim] = (cy - half_h).clamp(zero_value, one_value) * scale_y;
+      bbox[out + 2 * k_dim] = (cx + half_w).clamp(zero_value, one_value) * scale_x;
+      bbox[out + 3 * k_dim] = (cy + half_h).clamp(zero_value, one_value) * scale_y;
+    } else {
+      score[idx] = zero_value;
+      index[idx] = I::cast_from(0u32);
+      source[idx] = 0;
+      bbox[out] = zero_value;
+      bbox[out + k_dim] = zero_value;
+      bbox[out + 2 * k_dim] = zero_value;
+      bbox[out + 3 * k_dim] = zero_value;
+    }
+  }
+}
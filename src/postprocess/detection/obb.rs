@@ -0,0 +1,262 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/obb.rs - 旋转框 (OBB) 检测后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use super::{PPResult, classify};
+use crate::{data::DataBuffer, kernel::sigmoid};
+
+#[derive(Debug, Error)]
+pub enum ObbError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct ObbConfig {
+  dim: u32,
+}
+
+impl Default for ObbConfig {
+  fn default() -> Self {
+    Self { dim: 1 }
+  }
+}
+
+impl ObbConfig {
+  pub fn build(self) -> Result<Obb, ObbError> {
+    Ok(Obb { dim: self.dim })
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+}
+
+/// 旋转框检测后处理 (如 YOLOv8-OBB)，输出 (cx, cy, w, h, θ) 形式的旋转框
+///
+/// 旋转框以网络输入像素为单位，不做归一化，以免宽高缩放比例不同时改变角度
+pub struct Obb {
+  dim: u32,
+}
+
+/// 单个检测层级的输入 (cls, reg, angle, stride)
+pub type ObbLevel<R, F> = (DataBuffer<R, F>, DataBuffer<R, F>, DataBuffer<R, F>, F);
+
+impl Obb {
+  /// 执行后处理操作
+  /// cls: 分类结果，形状为 [N, num_classes, H, W]
+  /// reg: 回归结果，形状为 [N, 4, H, W]，为旋转坐标系下到四条边的距离 (left, top, right, bottom)
+  /// angle: 角度 logits，形状为 [N, 1, H, W]，解码为 (sigmoid(angle) - 0.25) * π
+  /// 返回 (score, index, rbox)，rbox 形状为 [N, 5, H, W]，为 cx, cy, w, h, θ
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    cls: DataBuffer<R, F>,
+    reg: DataBuffer<R, F>,
+    angle: DataBuffer<R, F>,
+    stride: F,
+  ) -> Result<PPResult<R, F, I>, ObbError> {
+    let (n, _, h, w) = level_shape(&cls, &reg, &angle)?;
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, h, w], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, h, w], client);
+    let rbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 5, h, w], client);
+
+    self.decode_level(client, cls, reg, angle, stride, &score, &index, &rbox, 0)?;
+
+    Ok((score, index, rbox))
+  }
+
+  /// 对多个检测头执行后处理，并将结果按层级顺序拼接，
+  /// 返回的 score/index 形状为 [N, M]，rbox 形状为 [N, 5, M]，位置顺序与 Yolo26::execute_levels 一致
+  pub fn execute_levels<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<ObbLevel<R, F>>,
+  ) -> Result<PPResult<R, F, I>, ObbError> {
+    let Some((cls, reg, angle, _)) = levels.first() else {
+      return Err(ObbError::InvalidInputShape(
+        "至少需要一个检测层级".to_string(),
+      ));
+    };
+    let (n, c, _, _) = level_shape(cls, reg, angle)?;
+
+    let mut m = 0;
+    for (cls, reg, angle, _) in &levels {
+      let (ln, lc, h, w) = level_shape(cls, reg, angle)?;
+      if ln != n || lc != c {
+        return Err(ObbError::InvalidInputShape(
+          "各层级分类结果的 N 与 num_classes 必须一致".to_string(),
+        ));
+      }
+      m += h * w;
+    }
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, m], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, m], client);
+    let rbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 5, m], client);
+
+    let mut offset = 0;
+    for (cls, reg, angle, stride) in levels {
+      let hw = cls.shape()[2] * cls.shape()[3];
+      self.decode_level(
+        client, cls, reg, angle, stride, &score, &index, &rbox, offset,
+      )?;
+      offset += hw;
+    }
+
+    Ok((score, index, rbox))
+  }
+
+  /// 解码单个层级，并写入输出中每张图像的 [offset, offset + H * W) 位置
+  #[allow(clippy::too_many_arguments)]
+  fn decode_level<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    cls: DataBuffer<R, F>,
+    reg: DataBuffer<R, F>,
+    angle: DataBuffer<R, F>,
+    stride: F,
+    score: &DataBuffer<R, F>,
+    index: &DataBuffer<R, I>,
+    rbox: &DataBuffer<R, F>,
+    offset: usize,
+  ) -> Result<(), ObbError> {
+    let (n, c, h, w) = level_shape(&cls, &reg, &angle)?;
+
+    let cls_sigmoid = cls.empty_like(client);
+
+    let count = (n * c * h * w).div_ceil(self.dim as usize);
+    sigmoid::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      cls.into_tensor_arg(1),
+      cls_sigmoid.into_tensor_arg(1),
+    )?;
+
+    let count = (n * h * w).div_ceil(self.dim as usize);
+    classify::launch::<F, I, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      cls_sigmoid.into_tensor_arg(1),
+      score.into_tensor_arg(1),
+      index.into_tensor_arg(1),
+      ScalarArg::new(offset as u32),
+    )?;
+
+    rotated_bbox::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      reg.into_tensor_arg(1),
+      angle.into_tensor_arg(1),
+      rbox.into_tensor_arg(1),
+      ScalarArg::new(stride),
+      ScalarArg::new(offset as u32),
+    )?;
+
+    Ok(())
+  }
+}
+
+/// 检查单个层级的输入形状，返回 (N, num_classes, H, W)
+fn level_shape<R: Runtime, F: Float + CubeElement>(
+  cls: &DataBuffer<R, F>,
+  reg: &DataBuffer<R, F>,
+  angle: &DataBuffer<R, F>,
+) -> Result<(usize, usize, usize, usize), ObbError> {
+  let [n, c, h, w] = *cls.shape() else {
+    return Err(ObbError::InvalidInputShape(
+      "分类结果张量形状不正确，预期为 [N, num_classes, H, W]".to_string(),
+    ));
+  };
+  if reg.shape() != [n, 4, h, w] {
+    return Err(ObbError::InvalidInputShape(
+      "回归结果张量形状不正确，预期为 [N, 4, H, W]".to_string(),
+    ));
+  }
+  if angle.shape() != [n, 1, h, w] {
+    return Err(ObbError::InvalidInputShape(
+      "角度张量形状不正确，预期为 [N, 1, H, W]".to_string(),
+    ));
+  }
+  Ok((n, c, h, w))
+}
+
+/// 将回归距离与角度解码为旋转框，网格与 stride 的约定与 bbox kernel 一致
+///
+/// reg: 输入 [N, 4, H, W]，为旋转坐标系下到四条边的距离
+/// angle: 输入 [N, 1, H, W]，角度 logits
+/// rbox: 输出 [N, 5, M]，为 cx, cy, w, h, θ，单位为网络输入像素与弧度
+/// offset: 写入输出中每张图像的 [offset, offset + H * W) 位置
+#[cube(launch)]
+fn rotated_bbox<F: Float + CubeScalar>(
+  reg: &Tensor<F>,
+  angle: &Tensor<F>,
+  rbox: &mut Tensor<F>,
+  stride: F,
+  offset: u32,
+) {
+  let n_dim = reg.shape(0);
+  let h_dim = reg.shape(2);
+  let w_dim = reg.shape(3);
+  let hw = h_dim * w_dim;
+
+  // 需要处理的总元素 = N * H * W
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * hw {
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+    let quarter_value = F::new(comptime!(0.25));
+    let pi_value = F::new(comptime!(std::f32::consts::PI));
+
+    // 将 idx 映射回 (n, h, w)
+    let n_idx = idx / hw;
+    let rem = idx % hw;
+    let h_idx = rem / w_dim;
+    let w_idx = rem % w_dim;
+
+    let stride_c = reg.stride(1);
+    let base = n_idx * reg.stride(0) + h_idx * reg.stride(2) + w_idx * reg.stride(3);
+    let left = reg[base];
+    let top = reg[base + stride_c];
+    let right = reg[base + 2 * stride_c];
+    let bottom = reg[base + 3 * stride_c];
+
+    let logit = angle[n_idx * angle.stride(0) + h_idx * angle.stride(2) + w_idx * angle.stride(3)];
+    let theta = (one_value / (one_value + (-logit).exp()) - quarter_value) * pi_value;
+
+    // 旋转坐标系下中心相对网格点的偏移，旋转回图像坐标系
+    let cos = theta.cos();
+    let sin = theta.sin();
+    let dx = (right - left) * half_value;
+    let dy = (bottom - top) * half_value;
+
+    let grid_x = F::cast_from(w_idx) + half_value;
+    let grid_y = F::cast_from(h_idx) + half_value;
+
+    let m = rbox.len() / (5 * n_dim);
+    let out = n_idx * 5 * m + offset as usize + rem;
+    rbox[out] = (grid_x + dx * cos - dy * sin) * stride;
+    rbox[out + m] = (grid_y + dx * sin + dy * cos) * stride;
+    rbox[out + 2 * m] = (left + right) * stride;
+    rbox[out + 3 * m] = (top + bottom) * stride;
+    rbox[out + 4 * m] = theta;
+  }
+}
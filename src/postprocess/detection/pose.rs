@@ -0,0 +1,282 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/pose.rs - YOLO-pose 关键点解码
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use super::{Candidates, candidate_shape};
+use crate::{data::DataBuffer, kernel::gather_channels};
+
+#[derive(Debug, Error)]
+pub enum PoseError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct PoseConfig {
+  width: u32,
+  height: u32,
+  num_keypoints: u32,
+  dim: u32,
+}
+
+impl Default for PoseConfig {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      // COCO 人体关键点数量
+      num_keypoints: 17,
+      dim: 1,
+    }
+  }
+}
+
+impl PoseConfig {
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 每个目标的关键点数量
+  pub fn with_num_keypoints(mut self, num_keypoints: u32) -> Self {
+    self.num_keypoints = num_keypoints;
+    self
+  }
+
+  pub fn build(self) -> Result<Pose, PoseError> {
+    if self.num_keypoints == 0 {
+      return Err(PoseError::InvalidConfig(
+        "num_keypoints 必须大于 0".to_string(),
+      ));
+    }
+    Ok(Pose {
+      width: self.width,
+      height: self.height,
+      num_keypoints: self.num_keypoints,
+      dim: self.dim,
+    })
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+}
+
+/// YOLO-pose 关键点解码，与 Yolo26 的检测结果按位置一一对应
+pub struct Pose {
+  width: u32,
+  height: u32,
+  num_keypoints: u32,
+  dim: u32,
+}
+
+/// 单个检测层级的关键点输入 (kpt, stride)
+pub type PoseLevel<R, F> = (DataBuffer<R, F>, F);
+
+impl Pose {
+  /// 解码关键点
+  /// kpt: 关键点结果，形状为 [N, K * 3, H, W]，每个关键点依次为 (x, y, visibility)
+  /// 返回形状为 [N, K, 3, H, W] 的关键点，x/y 为归一化坐标，visibility 已经过 sigmoid
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    kpt: DataBuffer<R, F>,
+    stride: F,
+  ) -> Result<DataBuffer<R, F>, PoseError> {
+    let (n, h, w) = self.level_shape(&kpt)?;
+    let k = self.num_keypoints as usize;
+
+    let keypoints: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k, 3, h, w], client);
+    self.decode_level(client, kpt, stride, &keypoints, 0)?;
+
+    Ok(keypoints)
+  }
+
+  /// 对多个检测层级解码关键点，并按层级顺序拼接为 [N, K, 3, M]，
+  /// 位置顺序与 Yolo26::execute_levels 一致
+  pub fn execute_levels<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<PoseLevel<R, F>>,
+  ) -> Result<DataBuffer<R, F>, PoseError> {
+    let Some((first, _)) = levels.first() else {
+      return Err(PoseError::InvalidInputShape(
+        "至少需要一个检测层级".to_string(),
+      ));
+    };
+    let (n, _, _) = self.level_shape(first)?;
+
+    let mut m = 0;
+    for (kpt, _) in &levels {
+      let (ln, h, w) = self.level_shape(kpt)?;
+      if ln != n {
+        return Err(PoseError::InvalidInputShape(
+          "各层级关键点结果的 N 必须一致".to_string(),
+        ));
+      }
+      m += h * w;
+    }
+
+    let k = self.num_keypoints as usize;
+    let keypoints: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k, 3, m], client);
+
+    let mut offset = 0;
+    for (kpt, stride) in levels {
+      let hw = kpt.shape()[2] * kpt.shape()[3];
+      self.decode_level(client, kpt, stride, &keypoints, offset)?;
+      offset += hw;
+    }
+
+    Ok(keypoints)
+  }
+
+  /// 按候选列表 (如 NMS 的输出) 的 source 收集对应检测结果的关键点
+  /// keypoints: execute 或 execute_levels 的输出 [N, K, 3, ...]
+  /// 返回形状为 [N, K, 3, D] 的关键点，D 为候选列表长度，count[n] 之后的位置为 0
+  pub fn gather<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    keypoints: DataBuffer<R, F>,
+    candidates: &Candidates<R, F, I>,
+  ) -> Result<DataBuffer<R, F>, PoseError> {
+    let (n, d) = candidate_shape(
+      candidates.score.shape(),
+      candidates.index.shape(),
+      candidates.bbox.shape(),
+    )
+    .map_err(PoseError::InvalidInputShape)?;
+    let k = self.num_keypoints as usize;
+    let shape = keypoints.shape();
+    if shape.len() < 3 || shape[0] != n || shape[1] != k || shape[2] != 3 {
+      return Err(PoseError::InvalidInputShape(
+        "关键点张量形状不正确，预期为 [N, K, 3, ...]".to_string(),
+      ));
+    }
+
+    let output: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k, 3, d], client);
+    let total = n * k * 3 * d;
+    gather_channels::launch::<F, R>(
+      client,
+      CubeCount::Static(total.div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      keypoints.into_tensor_arg(1),
+      candidates.source.into_tensor_arg(1),
+      candidates.count.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+    )?;
+
+    Ok(output)
+  }
+
+  /// 检查关键点输入形状，返回 (N, H, W)
+  fn level_shape<R: Runtime, F: Float + CubeElement>(
+    &self,
+    kpt: &DataBuffer<R, F>,
+  ) -> Result<(usize, usize, usize), PoseError> {
+    match *kpt.shape() {
+      [n, c, h, w] if c == 3 * self.num_keypoints as usize => Ok((n, h, w)),
+      _ => Err(PoseError::InvalidInputShape(format!(
+        "关键点结果张量形状不正确，预期为 [N, {} * 3, H, W]",
+        self.num_keypoints
+      ))),
+    }
+  }
+
+  /// 解码单个层级，并写入输出中每张图像的 [offset, offset + H * W) 位置
+  fn decode_level<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    kpt: DataBuffer<R, F>,
+    stride: F,
+    keypoints: &DataBuffer<R, F>,
+    offset: usize,
+  ) -> Result<(), PoseError> {
+    let (n, h, w) = self.level_shape(&kpt)?;
+
+    let count = (n * self.num_keypoints as usize * h * w).div_ceil(self.dim as usize);
+    keypoint::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      kpt.into_tensor_arg(1),
+      keypoints.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.width as f32)),
+      ScalarArg::new(F::new(self.height as f32)),
+      ScalarArg::new(stride),
+      ScalarArg::new(offset as u32),
+    )?;
+
+    Ok(())
+  }
+}
+
+/// 解码关键点，网格与 stride 的约定与 bbox kernel 一致 (网格中心为 w + 0.5)
+///
+/// kpt: 输入关键点结果 [N, K * 3, H, W]
+/// keypoints: 输出 [N, K, 3, M]，为归一化的 x, y 与 sigmoid 后的 visibility
+/// offset: 写入输出中每张图像的 [offset, offset + H * W) 位置
+#[cube(launch)]
+fn keypoint<F: Float + CubeScalar>(
+  kpt: &Tensor<F>,
+  keypoints: &mut Tensor<F>,
+  image_width: F,
+  image_height: F,
+  stride: F,
+  offset: u32,
+) {
+  let n_dim = kpt.shape(0);
+  let k_dim = kpt.shape(1) / 3;
+  let h_dim = kpt.shape(2);
+  let w_dim = kpt.shape(3);
+  let hw = h_dim * w_dim;
+
+  // 需要处理的总元素 = N * K * H * W
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * k_dim * hw {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+    let two_value = F::new(comptime!(2.0));
+
+    // 将 idx 映射回 (n, k, h, w)
+    let n_idx = idx / (k_dim * hw);
+    let k_idx = (idx / hw) % k_dim;
+    let rem = idx % hw;
+    let h_idx = rem / w_dim;
+    let w_idx = rem % w_dim;
+
+    let stride_c = kpt.stride(1);
+    let base =
+      n_idx * kpt.stride(0) + k_idx * 3 * stride_c + h_idx * kpt.stride(2) + w_idx * kpt.stride(3);
+
+    let grid_x = F::cast_from(w_idx) + half_value;
+    let grid_y = F::cast_from(h_idx) + half_value;
+
+    let x = (kpt[base] * two_value + grid_x - half_value) * stride;
+    let y = (kpt[base + stride_c] * two_value + grid_y - half_value) * stride;
+    let visibility = one_value / (one_value + (-kpt[base + 2 * stride_c]).exp());
+
+    let m = keypoints.len() / (n_dim * k_dim * 3);
+    let out = (n_idx * k_dim + k_idx) * 3 * m + offset as usize + rem;
+    keypoints[out] = (x / image_width).clamp(zero_value, one_value);
+    keypoints[out + m] = (y / image_height).clamp(zero_value, one_value);
+    keypoints[out + 2 * m] = visibility;
+  }
+}
@@ -0,0 +1,328 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/result.rs - 目标检测结果的类型化表示
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{num_traits::ToPrimitive, prelude::*};
+
+use crate::{
+  data::DataBufferError,
+  postprocess::detection::{Candidates, PPResult, candidate_shape, dense_shape},
+};
+
+/// 边界框坐标的单位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxUnit {
+  /// 相对于图像宽高归一化到 [0, 1]
+  Normalized,
+  /// 图像像素坐标
+  Pixel,
+}
+
+/// 边界框四个分量的含义
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxFormat {
+  /// xmin, ymin, xmax, ymax
+  Xyxy,
+  /// xmin, ymin, w, h
+  Xywh,
+  /// cx, cy, w, h
+  Cxcywh,
+}
+
+/// 稠密边界框张量中坐标分量的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoxLayout {
+  /// 分量为第二维，形状为 [N, 4, ...]
+  Planar,
+  /// 分量为最后一维，形状为 [N, ..., 4]
+  Interleaved,
+}
+
+/// 稠密边界框张量的编码方式，默认为裁剪到图像内的归一化 xyxy，按 [N, 4, ...] 排列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoxEncoding {
+  pub format: BoxFormat,
+  pub unit: BoxUnit,
+  /// 是否已裁剪到图像范围内
+  pub clamp: bool,
+  pub layout: BoxLayout,
+}
+
+impl Default for BoxEncoding {
+  fn default() -> Self {
+    Self {
+      format: BoxFormat::Xyxy,
+      unit: BoxUnit::Normalized,
+      clamp: true,
+      layout: BoxLayout::Planar,
+    }
+  }
+}
+
+impl BoxEncoding {
+  /// 将一个边界框从该编码的格式转换为 xyxy
+  pub fn to_xyxy(&self, bbox: [f32; 4]) -> [f32; 4] {
+    let [a, b, c, d] = bbox;
+    match self.format {
+      BoxFormat::Xyxy => bbox,
+      BoxFormat::Xywh => [a, b, a + c, b + d],
+      BoxFormat::Cxcywh => [a - c / 2.0, b - d / 2.0, a + c / 2.0, b + d / 2.0],
+    }
+  }
+}
+
+/// 单个检测结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+  /// 边界框 (xmin, ymin, xmax, ymax)
+  pub bbox: [f32; 4],
+  /// 分类得分
+  pub score: f32,
+  /// 类别索引
+  pub class_id: u32,
+  /// 所属图像在批次中的索引
+  pub batch_index: usize,
+}
+
+/// 一个批次的检测结果，按图像分组存放
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionSet {
+  detections: Vec<Detection>,
+  batch_size: usize,
+  unit: BoxUnit,
+}
+
+impl DetectionSet {
+  /// 由检测结果列表构造，detections 会按 batch_index 稳定排序以便分组
+  pub fn new(mut detections: Vec<Detection>, batch_size: usize, unit: BoxUnit) -> Self {
+    detections.sort_by_key(|d| d.batch_index);
+    let batch_size = detections
+      .iter()
+      .map(|d| d.batch_index + 1)
+      .max()
+      .unwrap_or(0)
+      .max(batch_size);
+    Self {
+      detections,
+      batch_size,
+      unit,
+    }
+  }
+
+  /// 从候选列表 (如 NMS 的输出) 读取检测结果，只保留每张图像前 count[n] 个
+  pub fn from_candidates<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    client: &ComputeClient<R>,
+    candidates: Candidates<R, F, I>,
+    unit: BoxUnit,
+  ) -> Result<Self, DataBufferError> {
+    let (n, k) = candidate_shape(
+      candidates.score.shape(),
+      candidates.index.shape(),
+      candidates.bbox.shape(),
+    )
+    .map_err(DataBufferError::InvalidShape)?;
+    if candidates.count.shape() != [n] {
+      return Err(DataBufferError::InvalidShape(
+        "有效数量张量形状不正确，预期为 [N]".to_string(),
+      ));
+    }
+
+    let count = candidates.count.into_vec(client)?;
+    let score = candidates.score.into_vec(client)?;
+    let index = candidates.index.into_vec(client)?;
+    let bbox = candidates.bbox.into_vec(client)?;
+
+    let mut detections = Vec::new();
+    for (b, &valid) in count.iter().enumerate() {
+      for i in 0..(valid as usize).min(k) {
+        detections.push(read_detection(
+          &score,
+          &index,
+          &bbox,
+          b,
+          i,
+          k,
+          BoxLayout::Planar,
+        )?);
+      }
+    }
+
+    Ok(Self::new(detections, n, unit))
+  }
+
+  /// 从稠密的检测结果 (如 Yolo26::execute 的输出) 读取得分不低于 score_threshold 的位置
+  ///
+  /// bbox 应为默认编码，即 [N, 4, ...] 排列的 xyxy，unit 为其坐标单位
+  pub fn from_dense<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    client: &ComputeClient<R>,
+    result: PPResult<R, F, I>,
+    score_threshold: f32,
+    unit: BoxUnit,
+  ) -> Result<Self, DataBufferError> {
+    let encoding = BoxEncoding {
+      unit,
+      ..BoxEncoding::default()
+    };
+    Self::from_dense_encoded(client, result, score_threshold, encoding)
+  }
+
+  /// 与 from_dense 相同，但 bbox 按 encoding (如 Yolo26::box_encoding) 解读，读取后统一转换为 xyxy
+  pub fn from_dense_encoded<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    client: &ComputeClient<R>,
+    result: PPResult<R, F, I>,
+    score_threshold: f32,
+    encoding: BoxEncoding,
+  ) -> Result<Self, DataBufferError> {
+    let (score, index, bbox) = result;
+    let (n, m) = dense_shape(score.shape(), index.shape(), bbox.shape(), encoding.layout)
+      .map_err(DataBufferError::InvalidShape)?;
+
+    let score = score.into_vec(client)?;
+    let index = index.into_vec(client)?;
+    let bbox = bbox.into_vec(client)?;
+
+    let mut detections = Vec::new();
+    for b in 0..n {
+      for i in 0..m {
+        let mut detection = read_detection(&score, &index, &bbox, b, i, m, encoding.layout)?;
+        if detection.score >= score_threshold {
+          detection.bbox = encoding.to_xyxy(detection.bbox);
+          detections.push(detection);
+        }
+      }
+    }
+
+    Ok(Self::new(detections, n, encoding.unit))
+  }
+
+  pub fn detections(&self) -> &[Detection] {
+    &self.detections
+  }
+
+  pub fn into_detections(self) -> Vec<Detection> {
+    self.detections
+  }
+
+  pub fn batch_size(&self) -> usize {
+    self.batch_size
+  }
+
+  pub fn unit(&self) -> BoxUnit {
+    self.unit
+  }
+
+  pub fn len(&self) -> usize {
+    self.detections.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.detections.is_empty()
+  }
+
+  /// 获取第 batch_index 张图像的检测结果
+  pub fn batch(&self, batch_index: usize) -> &[Detection] {
+    let begin = self
+      .detections
+      .partition_point(|d| d.batch_index < batch_index);
+    let end = self
+      .detections
+      .partition_point(|d| d.batch_index <= batch_index);
+    &self.detections[begin..end]
+  }
+
+  /// 按图像分组，返回长度为 batch_size 的列表
+  pub fn group_by_batch(&self) -> Vec<&[Detection]> {
+    (0..self.batch_size).map(|b| self.batch(b)).collect()
+  }
+
+  /// 每张图像内按得分降序排序，图像之间的顺序不变
+  pub fn sort_by_score(&mut self) {
+    self.detections.sort_by(|a, b| {
+      a.batch_index
+        .cmp(&b.batch_index)
+        .then(b.score.total_cmp(&a.score))
+    });
+  }
+
+  /// 只保留类别在 classes 中的检测结果
+  pub fn filter_classes(mut self, classes: &[u32]) -> Self {
+    self.detections.retain(|d| classes.contains(&d.class_id));
+    self
+  }
+
+  /// 转换为像素坐标，width/height 为归一化时所参照的图像尺寸
+  pub fn to_pixel(mut self, width: u32, height: u32) -> Self {
+    if self.unit == BoxUnit::Normalized {
+      self.scale(width as f32, height as f32);
+      self.unit = BoxUnit::Pixel;
+    }
+    self
+  }
+
+  /// 转换为归一化坐标，width/height 为像素坐标所在的图像尺寸，需要转换时不能为 0
+  pub fn to_normalized(mut self, width: u32, height: u32) -> Result<Self, DataBufferError> {
+    if self.unit == BoxUnit::Pixel {
+      if width == 0 || height == 0 {
+        return Err(DataBufferError::InvalidData(format!(
+          "图像尺寸为 {}x{}，无法转换归一化坐标",
+          width, height
+        )));
+      }
+      self.scale(1.0 / width as f32, 1.0 / height as f32);
+      self.unit = BoxUnit::Normalized;
+    }
+    Ok(self)
+  }
+
+  fn scale(&mut self, sx: f32, sy: f32) {
+    for d in &mut self.detections {
+      d.bbox[0] *= sx;
+      d.bbox[1] *= sy;
+      d.bbox[2] *= sx;
+      d.bbox[3] *= sy;
+    }
+  }
+}
+
+/// 读取布局为 score/index [N, M]、bbox [N, 4, M] (或 layout 为 Interleaved 时 [N, M, 4])
+/// 中第 b 张图像第 i 个位置的检测结果
+fn read_detection<F: ToPrimitive, I: ToPrimitive>(
+  score: &[F],
+  index: &[I],
+  bbox: &[F],
+  b: usize,
+  i: usize,
+  m: usize,
+  layout: BoxLayout,
+) -> Result<Detection, DataBufferError> {
+  let to_f32 = |v: &F| {
+    v.to_f32()
+      .ok_or_else(|| DataBufferError::InvalidData("无法将得分转换为 f32".to_string()))
+  };
+  let (base, step) = match layout {
+    BoxLayout::Planar => (b * 4 * m + i, m),
+    BoxLayout::Interleaved => ((b * m + i) * 4, 1),
+  };
+  Ok(Detection {
+    bbox: [
+      to_f32(&bbox[base])?,
+      to_f32(&bbox[base + step])?,
+      to_f32(&bbox[base + 2 * step])?,
+      to_f32(&bbox[base + 3 * step])?,
+    ],
+    score: to_f32(&score[b * m + i])?,
+    class_id: index[b * m + i]
+      .to_u32()
+      .ok_or_else(|| DataBufferError::InvalidData("无法将类别索引转换为 u32".to_string()))?,
+    batch_index: b,
+  })
+}
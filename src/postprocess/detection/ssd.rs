@@ -0,0 +1,402 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/ssd.rs - SSD/RetinaNet 先验框生成与解码
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use super::PPResult;
+use crate::data::{DataBuffer, DataBufferError};
+
+#[derive(Debug, Error)]
+pub enum SsdError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 单个特征层级的先验框参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorLevel {
+  /// 特征图宽度
+  pub grid_width: u32,
+  /// 特征图高度
+  pub grid_height: u32,
+  /// 先验框边长，单位为网络输入像素
+  pub sizes: Vec<f32>,
+  /// 宽高比 w / h
+  pub aspect_ratios: Vec<f32>,
+}
+
+pub struct PriorBoxConfig {
+  width: u32,
+  height: u32,
+  levels: Vec<PriorLevel>,
+  clip: bool,
+}
+
+impl Default for PriorBoxConfig {
+  fn default() -> Self {
+    Self {
+      width: 300,
+      height: 300,
+      levels: Vec::new(),
+      clip: false,
+    }
+  }
+}
+
+impl PriorBoxConfig {
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 追加一个特征层级，层级顺序需与模型输出中位置的拼接顺序一致
+  pub fn with_level(
+    mut self,
+    grid_width: u32,
+    grid_height: u32,
+    sizes: Vec<f32>,
+    aspect_ratios: Vec<f32>,
+  ) -> Self {
+    self.levels.push(PriorLevel {
+      grid_width,
+      grid_height,
+      sizes,
+      aspect_ratios,
+    });
+    self
+  }
+
+  /// 为 true 时将先验框裁剪到图像范围内
+  pub fn with_clip(mut self, clip: bool) -> Self {
+    self.clip = clip;
+    self
+  }
+
+  pub fn build(self) -> Result<PriorBox, SsdError> {
+    if self.levels.is_empty() {
+      return Err(SsdError::InvalidConfig("至少需要一个特征层级".to_string()));
+    }
+    for level in &self.levels {
+      if level.grid_width == 0 || level.grid_height == 0 {
+        return Err(SsdError::InvalidConfig("特征图尺寸必须大于 0".to_string()));
+      }
+      if level.sizes.is_empty() || level.aspect_ratios.is_empty() {
+        return Err(SsdError::InvalidConfig(
+          "每个层级至少需要一个尺寸与一个宽高比".to_string(),
+        ));
+      }
+      if level.aspect_ratios.iter().any(|&r| r <= 0.0) {
+        return Err(SsdError::InvalidConfig("宽高比必须大于 0".to_string()));
+      }
+    }
+    Ok(PriorBox {
+      width: self.width,
+      height: self.height,
+      levels: self.levels,
+      clip: self.clip,
+    })
+  }
+}
+
+/// 先验框 (锚框) 生成器
+pub struct PriorBox {
+  width: u32,
+  height: u32,
+  levels: Vec<PriorLevel>,
+  clip: bool,
+}
+
+impl PriorBox {
+  /// 先验框总数 M = sum(H_i * W_i * sizes_i * aspect_ratios_i)
+  pub fn len(&self) -> usize {
+    self
+      .levels
+      .iter()
+      .map(|l| (l.grid_width * l.grid_height) as usize * l.sizes.len() * l.aspect_ratios.len())
+      .sum()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// 生成归一化的先验框 (cx, cy, w, h)
+  ///
+  /// 按层级、行、列、尺寸、宽高比的顺序排列，即每个位置的全部先验框相邻，
+  /// 与 SSD/RetinaNet 导出模型中 [N, M, 4] 的位置顺序一致
+  pub fn priors(&self) -> Vec<[f32; 4]> {
+    let (width, height) = (self.width as f32, self.height as f32);
+    let mut priors = Vec::with_capacity(self.len());
+    for level in &self.levels {
+      let step_x = width / level.grid_width as f32;
+      let step_y = height / level.grid_height as f32;
+      for y in 0..level.grid_height {
+        for x in 0..level.grid_width {
+          let cx = (x as f32 + 0.5) * step_x / width;
+          let cy = (y as f32 + 0.5) * step_y / height;
+          for &size in &level.sizes {
+            for &ratio in &level.aspect_ratios {
+              let w = size * ratio.sqrt() / width;
+              let h = size / ratio.sqrt() / height;
+              let prior = [cx, cy, w, h];
+              priors.push(if self.clip {
+                prior.map(|v| v.clamp(0.0, 1.0))
+              } else {
+                prior
+              });
+            }
+          }
+        }
+      }
+    }
+    priors
+  }
+
+  /// 生成先验框并上传到设备，形状为 [4, M]，为 cx, cy, w, h
+  pub fn generate<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+  ) -> Result<DataBuffer<R, F>, SsdError> {
+    let priors = self.priors();
+    let m = priors.len();
+    let mut planar = vec![F::new(0.0); 4 * m];
+    for (i, prior) in priors.iter().enumerate() {
+      for (c, &v) in prior.iter().enumerate() {
+        planar[c * m + i] = F::new(v);
+      }
+    }
+    Ok(DataBuffer::from_slice(&planar, &[4, m], client)?)
+  }
+}
+
+pub struct SsdConfig {
+  variances: [f32; 4],
+  softmax: bool,
+  background: bool,
+  dim: u32,
+}
+
+impl Default for SsdConfig {
+  fn default() -> Self {
+    Self {
+      variances: [0.1, 0.1, 0.2, 0.2],
+      softmax: true,
+      background: true,
+      dim: 1,
+    }
+  }
+}
+
+impl SsdConfig {
+  /// 编码偏移量时使用的方差 (dx, dy, dw, dh)
+  pub fn with_variances(mut self, variances: [f32; 4]) -> Self {
+    self.variances = variances;
+    self
+  }
+
+  /// 为 true 时对类别做 softmax (SSD)，否则对每个类别做 sigmoid (RetinaNet)
+  pub fn with_softmax(mut self, softmax: bool) -> Self {
+    self.softmax = softmax;
+    self
+  }
+
+  /// 为 true 时类别 0 为背景，不参与取最大值，输出的类别索引减 1
+  pub fn with_background(mut self, background: bool) -> Self {
+    self.background = background;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Ssd, SsdError> {
+    Ok(Ssd {
+      variances: self.variances,
+      softmax: self.softmax,
+      background: self.background,
+      dim: self.dim,
+    })
+  }
+}
+
+/// SSD/RetinaNet 后处理，按先验框解码方差编码的偏移量
+pub struct Ssd {
+  variances: [f32; 4],
+  softmax: bool,
+  background: bool,
+  dim: u32,
+}
+
+impl Ssd {
+  /// 执行后处理操作
+  /// loc: 回归结果，形状为 [N, M, 4]，为 (dx, dy, dw, dh)
+  /// conf: 分类结果，形状为 [N, M, C]
+  /// priors: 先验框，形状为 [4, M]，即 PriorBox::generate 的输出
+  /// 返回 (score, index, bbox)，score/index 形状为 [N, M]，bbox 形状为 [N, 4, M]，
+  /// 为归一化的 xmin, ymin, xmax, ymax，与 Yolo26::execute 的布局一致
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    loc: DataBuffer<R, F>,
+    conf: DataBuffer<R, F>,
+    priors: &DataBuffer<R, F>,
+  ) -> Result<PPResult<R, F, I>, SsdError> {
+    let [n, m, 4] = *loc.shape() else {
+      return Err(SsdError::InvalidInputShape(
+        "回归结果张量形状不正确，预期为 [N, M, 4]".to_string(),
+      ));
+    };
+    let c = match *conf.shape() {
+      [cn, cm, c] if cn == n && cm == m => c,
+      _ => {
+        return Err(SsdError::InvalidInputShape(
+          "分类结果张量形状不正确，预期为 [N, M, C]".to_string(),
+        ));
+      }
+    };
+    if c <= self.background as usize {
+      return Err(SsdError::InvalidInputShape(
+        "分类结果中没有前景类别".to_string(),
+      ));
+    }
+    if priors.shape() != [4, m] {
+      return Err(SsdError::InvalidInputShape(format!(
+        "先验框张量形状不正确，预期为 [4, {}]",
+        m
+      )));
+    }
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, m], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, m], client);
+    let bbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, m], client);
+
+    let [v0, v1, v2, v3] = self.variances.map(F::new);
+    let count = (n * m).div_ceil(self.dim as usize);
+    decode::launch::<F, I, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      loc.into_tensor_arg(1),
+      conf.into_tensor_arg(1),
+      priors.into_tensor_arg(1),
+      score.into_tensor_arg(1),
+      index.into_tensor_arg(1),
+      bbox.into_tensor_arg(1),
+      ScalarArg::new(v0),
+      ScalarArg::new(v1),
+      ScalarArg::new(v2),
+      ScalarArg::new(v3),
+      self.softmax,
+      self.background,
+    )?;
+
+    Ok((score, index, bbox))
+  }
+}
+
+/// 按先验框解码回归结果，并计算每个位置的最大类别得分
+///
+/// loc: 输入 [N, M, 4]，conf: 输入 [N, M, C]，priors: 输入 [4, M]
+/// score/index: 输出 [N, M]，bbox: 输出 [N, 4, M]
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+fn decode<F: Float + CubeScalar, I: Int>(
+  loc: &Tensor<F>,
+  conf: &Tensor<F>,
+  priors: &Tensor<F>,
+  score: &mut Tensor<F>,
+  index: &mut Tensor<I>,
+  bbox: &mut Tensor<F>,
+  variance_x: F,
+  variance_y: F,
+  variance_w: F,
+  variance_h: F,
+  #[comptime] softmax: bool,
+  #[comptime] background: bool,
+) {
+  let m = loc.shape(1);
+  let c_dim = conf.shape(2);
+
+  let idx = ABSOLUTE_POS;
+  if idx < loc.shape(0) * m {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+
+    let n_idx = idx / m;
+    let m_idx = idx % m;
+
+    // 类别得分，背景类别不参与取最大值
+    let first = comptime!(if background { 1usize } else { 0usize });
+    let conf_base = n_idx * conf.stride(0) + m_idx * conf.stride(1);
+    let stride_c = conf.stride(2);
+
+    let mut best_c = first;
+    let mut best_val = conf[conf_base + first * stride_c];
+    for c in first + 1..c_dim {
+      let v = conf[conf_base + c * stride_c];
+      if v > best_val {
+        best_val = v;
+        best_c = c;
+      }
+    }
+
+    let best_score = if comptime!(softmax) {
+      // softmax 分母包含背景类别，减去全体最大值保证数值稳定
+      let mut max_val = conf[conf_base];
+      for c in 1..c_dim {
+        max_val = F::max(max_val, conf[conf_base + c * stride_c]);
+      }
+      let mut sum = zero_value;
+      for c in 0..c_dim {
+        sum += (conf[conf_base + c * stride_c] - max_val).exp();
+      }
+      (best_val - max_val).exp() / sum
+    } else {
+      one_value / (one_value + (-best_val).exp())
+    };
+
+    let out = n_idx * m + m_idx;
+    score[out] = best_score;
+    index[out] = I::cast_from(best_c - first);
+
+    // 方差编码: cx = p_cx + dx * v_x * p_w，w = p_w * exp(dw * v_w)
+    let loc_base = n_idx * loc.stride(0) + m_idx * loc.stride(1);
+    let stride_l = loc.stride(2);
+    let p_cx = priors[m_idx];
+    let p_cy = priors[m + m_idx];
+    let p_w = priors[2 * m + m_idx];
+    let p_h = priors[3 * m + m_idx];
+
+    let cx = p_cx + loc[loc_base] * variance_x * p_w;
+    let cy = p_cy + loc[loc_base + stride_l] * variance_y * p_h;
+    let w = p_w * (loc[loc_base + 2 * stride_l] * variance_w).exp();
+    let h = p_h * (loc[loc_base + 3 * stride_l] * variance_h).exp();
+
+    let out = n_idx * 4 * m + m_idx;
+    bbox[out] = (cx - w * half_value).clamp(zero_value, one_value);
+    bbox[out + m] = (cy - h * half_value).clamp(zero_value, one_value);
+    bbox[out + 2 * m] = (cx + w * half_value).clamp(zero_value, one_value);
+    bbox[out + 3 * m] = (cy + h * half_value).clamp(zero_value, one_value);
+  }
+}
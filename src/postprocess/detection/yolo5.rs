@@ -0,0 +1,355 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/yolo5.rs - 基于锚框的 YOLOv5/v7 后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use super::PPResult;
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  kernel::sigmoid,
+};
+
+#[derive(Debug, Error)]
+pub enum Yolo5Error {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct Yolo5Config {
+  width: u32,
+  height: u32,
+  anchors: Vec<Vec<[f32; 2]>>,
+  dim: u32,
+}
+
+impl Default for Yolo5Config {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      // YOLOv5/v7 在 COCO 上的默认锚框，依次对应 P3/P4/P5 (stride 8/16/32)
+      anchors: vec![
+        vec![[10.0, 13.0], [16.0, 30.0], [33.0, 23.0]],
+        vec![[30.0, 61.0], [62.0, 45.0], [59.0, 119.0]],
+        vec![[116.0, 90.0], [156.0, 198.0], [373.0, 326.0]],
+      ],
+      dim: 1,
+    }
+  }
+}
+
+impl Yolo5Config {
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 每个检测层级的锚框 (width, height)，单位为网络输入像素，顺序与层级顺序一致
+  pub fn with_anchors(mut self, anchors: Vec<Vec<[f32; 2]>>) -> Self {
+    self.anchors = anchors;
+    self
+  }
+
+  pub fn build(self) -> Result<Yolo5, Yolo5Error> {
+    if self.anchors.is_empty() || self.anchors.iter().any(Vec::is_empty) {
+      return Err(Yolo5Error::InvalidConfig(
+        "每个检测层级至少需要一个锚框".to_string(),
+      ));
+    }
+    Ok(Yolo5 {
+      width: self.width,
+      height: self.height,
+      anchors: self.anchors,
+      dim: self.dim,
+    })
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+}
+
+/// YOLOv5/v7 后处理，按锚框解码边界框，并将目标置信度乘入分类得分
+pub struct Yolo5 {
+  width: u32,
+  height: u32,
+  anchors: Vec<Vec<[f32; 2]>>,
+  dim: u32,
+}
+
+/// 单个检测层级的输入 (pred, stride)，pred 形状为 [N, A * (5 + num_classes), H, W]
+pub type Yolo5Level<R, F> = (DataBuffer<R, F>, F);
+
+impl Yolo5 {
+  /// 对第 level 个检测层级执行后处理
+  /// pred: 检测结果，形状为 [N, A * (5 + num_classes), H, W]，
+  ///       每个锚框依次为 (x, y, w, h, objectness, cls...)，A 为该层级的锚框数量
+  /// 返回的 score/index 形状为 [N, A, H, W]，bbox 形状为 [N, 4, A, H, W]
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    pred: DataBuffer<R, F>,
+    level: usize,
+    stride: F,
+  ) -> Result<PPResult<R, F, I>, Yolo5Error> {
+    let (n, a, h, w) = self.level_shape(&pred, level)?;
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, a, h, w], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, a, h, w], client);
+    let bbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, a, h, w], client);
+
+    self.decode_level(client, pred, level, stride, &score, &index, &bbox, 0)?;
+
+    Ok((score, index, bbox))
+  }
+
+  /// 对多个检测层级执行后处理，并将结果按层级顺序拼接
+  /// levels: 每个层级的 (pred, stride)，第 i 个层级使用第 i 组锚框
+  /// 返回的 score/index 形状为 [N, M]，bbox 形状为 [N, 4, M]，其中 M = sum(A_i * H_i * W_i)，
+  /// 第 i 层的位置 a * H_i * W_i + h * W_i + w 对应拼接后的 sum(A_j * H_j * W_j, j < i) 加上该位置
+  pub fn execute_levels<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<Yolo5Level<R, F>>,
+  ) -> Result<PPResult<R, F, I>, Yolo5Error> {
+    if levels.is_empty() || levels.len() > self.anchors.len() {
+      return Err(Yolo5Error::InvalidInputShape(format!(
+        "检测层级数量应在 1 到 {} 之间，实际为 {}",
+        self.anchors.len(),
+        levels.len()
+      )));
+    }
+
+    let mut shape = None;
+    let mut m = 0;
+    for (level, (pred, _)) in levels.iter().enumerate() {
+      let (n, a, h, w) = self.level_shape(pred, level)?;
+      let c = pred.shape()[1] / a;
+      match shape {
+        None => shape = Some((n, c)),
+        Some(s) if s != (n, c) => {
+          return Err(Yolo5Error::InvalidInputShape(
+            "各层级检测结果的 N 与 num_classes 必须一致".to_string(),
+          ));
+        }
+        Some(_) => {}
+      }
+      m += a * h * w;
+    }
+    let Some((n, _)) = shape else {
+      unreachable!("levels 非空");
+    };
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, m], client);
+    let index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, m], client);
+    let bbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, m], client);
+
+    let mut offset = 0;
+    for (level, (pred, stride)) in levels.into_iter().enumerate() {
+      let size = self.anchors[level].len() * pred.shape()[2] * pred.shape()[3];
+      self.decode_level(client, pred, level, stride, &score, &index, &bbox, offset)?;
+      offset += size;
+    }
+
+    Ok((score, index, bbox))
+  }
+
+  /// 检查第 level 个层级的输入形状，返回 (N, A, H, W)
+  fn level_shape<R: Runtime, F: Float + CubeElement>(
+    &self,
+    pred: &DataBuffer<R, F>,
+    level: usize,
+  ) -> Result<(usize, usize, usize, usize), Yolo5Error> {
+    let Some(anchors) = self.anchors.get(level) else {
+      return Err(Yolo5Error::InvalidInputShape(format!(
+        "检测层级 {} 没有对应的锚框",
+        level
+      )));
+    };
+    let [n, c, h, w] = *pred.shape() else {
+      return Err(Yolo5Error::InvalidInputShape(
+        "检测结果张量形状不正确，预期为 [N, A * (5 + num_classes), H, W]".to_string(),
+      ));
+    };
+    let a = anchors.len();
+    if c % a != 0 || c / a <= 5 {
+      return Err(Yolo5Error::InvalidInputShape(format!(
+        "检测结果通道数 {} 与锚框数量 {} 不匹配，预期为 A * (5 + num_classes)",
+        c, a
+      )));
+    }
+    Ok((n, a, h, w))
+  }
+
+  /// 解码单个层级，并写入输出中每张图像的 [offset, offset + A * H * W) 位置
+  #[allow(clippy::too_many_arguments)]
+  fn decode_level<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    pred: DataBuffer<R, F>,
+    level: usize,
+    stride: F,
+    score: &DataBuffer<R, F>,
+    index: &DataBuffer<R, I>,
+    bbox: &DataBuffer<R, F>,
+    offset: usize,
+  ) -> Result<(), Yolo5Error> {
+    let (n, a, h, w) = self.level_shape(&pred, level)?;
+
+    let anchors: Vec<F> = self.anchors[level]
+      .iter()
+      .flatten()
+      .map(|&v| F::new(v))
+      .collect();
+    let anchors = DataBuffer::<R, F>::from_slice(&anchors, &[a, 2], client)?;
+
+    let pred_sigmoid = pred.empty_like(client);
+
+    let count = pred
+      .shape()
+      .iter()
+      .product::<usize>()
+      .div_ceil(self.dim as usize);
+    sigmoid::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      pred.into_tensor_arg(1),
+      pred_sigmoid.into_tensor_arg(1),
+    )?;
+
+    let count = (n * a * h * w).div_ceil(self.dim as usize);
+    decode::launch::<F, I, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      pred_sigmoid.into_tensor_arg(1),
+      anchors.into_tensor_arg(1),
+      score.into_tensor_arg(1),
+      index.into_tensor_arg(1),
+      bbox.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.width as f32)),
+      ScalarArg::new(F::new(self.height as f32)),
+      ScalarArg::new(stride),
+      ScalarArg::new(offset as u32),
+    )?;
+
+    Ok(())
+  }
+}
+
+/// 解码基于锚框的检测结果
+///
+/// pred: 输入检测结果，形状为 [N, A * (5 + num_classes), H, W]，应该已经调用过 sigmoid 激活函数
+/// anchors: 该层级的锚框 [A, 2]，为 (width, height)，单位为网络输入像素
+/// score: 输出得分 [N, M]，为 objectness * max(cls)
+/// index: 输出类别索引 [N, M]
+/// bbox: 输出边界框坐标 [N, 4, M]，为 xmin, ymin, xmax, ymax
+/// offset: 写入输出中每张图像的 [offset, offset + A * H * W) 位置
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+fn decode<F: Float + CubeScalar, I: Int>(
+  pred: &Tensor<F>,
+  anchors: &Tensor<F>,
+  score: &mut Tensor<F>,
+  index: &mut Tensor<I>,
+  bbox: &mut Tensor<F>,
+  image_width: F,
+  image_height: F,
+  stride: F,
+  offset: u32,
+) {
+  let n_dim = pred.shape(0);
+  let a_dim = anchors.shape(0);
+  let h_dim = pred.shape(2);
+  let w_dim = pred.shape(3);
+
+  // 需要处理的总元素 = N * A * H * W
+  let hw = h_dim * w_dim;
+  let ahw = a_dim * hw;
+
+  // 线程全局索引
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * ahw {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+    let half_value = F::new(comptime!(0.5));
+    let two_value = F::new(comptime!(2.0));
+
+    // 将 idx 映射回 (n, a, h, w)
+    let n_idx = idx / ahw;
+    let rem = idx % ahw;
+    let a_idx = rem / hw;
+    let h_idx = (rem % hw) / w_dim;
+    let w_idx = rem % w_dim;
+
+    // 每个锚框占用 5 + num_classes 个通道
+    let per_anchor = pred.shape(1) / a_dim;
+    let c_dim = per_anchor - 5;
+
+    // 输入 strides (支持任意 stride 布局)
+    let stride_c = pred.stride(1);
+    let base = n_idx * pred.stride(0)
+      + a_idx * per_anchor * stride_c
+      + h_idx * pred.stride(2)
+      + w_idx * pred.stride(3);
+
+    let tx = pred[base];
+    let ty = pred[base + stride_c];
+    let tw = pred[base + 2 * stride_c];
+    let th = pred[base + 3 * stride_c];
+    let objectness = pred[base + 4 * stride_c];
+
+    let cls_base = base + 5 * stride_c;
+    let mut best_c = 0;
+    let mut best_val = pred[cls_base];
+    for c in 1..c_dim {
+      let v = pred[cls_base + c * stride_c];
+      if v > best_val {
+        best_val = v;
+        best_c = c;
+      }
+    }
+
+    // YOLOv5/v7: 中心 (2 * sig - 0.5 + grid) * stride，宽高 (2 * sig)^2 * anchor
+    let cx = (two_value * tx - half_value + F::cast_from(w_idx)) * stride;
+    let cy = (two_value * ty - half_value + F::cast_from(h_idx)) * stride;
+    let bw = (two_value * tw) * (two_value * tw) * anchors[a_idx * 2];
+    let bh = (two_value * th) * (two_value * th) * anchors[a_idx * 2 + 1];
+
+    let xmin = cx - bw * half_value;
+    let ymin = cy - bh * half_value;
+    let xmax = cx + bw * half_value;
+    let ymax = cy + bh * half_value;
+
+    let m = score.len() / n_dim;
+    let out = n_idx * m + offset as usize + rem;
+    score[out] = objectness * best_val;
+    index[out] = I::cast_from(best_c);
+
+    let out = n_idx * 4 * m + offset as usize + rem;
+    bbox[out] = (xmin / image_width).clamp(zero_value, one_value);
+    bbox[out + m] = (ymin / image_height).clamp(zero_value, one_value);
+    bbox[out + 2 * m] = (xmax / image_width).clamp(zero_value, one_value);
+    bbox[out + 3 * m] = (ymax / image_height).clamp(zero_value, one_value);
+  }
+}
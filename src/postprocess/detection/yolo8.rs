@@ -0,0 +1,203 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/detection/yolo8.rs - YOLOv8/v11 DFL 边界框解码
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use thiserror::Error;
+
+use super::{BoxEncoding, PPResult, Yolo26, Yolo26Error};
+use crate::data::DataBuffer;
+
+#[derive(Debug, Error)]
+pub enum Yolo8Error {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("解码错误: {0}")]
+  DecodeError(#[from] Yolo26Error),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct Yolo8Config {
+  width: u32,
+  height: u32,
+  reg_max: u32,
+  dim: u32,
+}
+
+impl Default for Yolo8Config {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      reg_max: 16,
+      dim: 1,
+    }
+  }
+}
+
+impl Yolo8Config {
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 每条边的分布区间数量，YOLOv8/v11 默认为 16
+  pub fn with_reg_max(mut self, reg_max: u32) -> Self {
+    self.reg_max = reg_max;
+    self
+  }
+
+  pub fn build(self) -> Result<Yolo8, Yolo8Error> {
+    if self.reg_max == 0 {
+      return Err(Yolo8Error::InvalidConfig("reg_max 必须大于 0".to_string()));
+    }
+    Ok(Yolo8 {
+      decoder: Yolo26 {
+        width: self.width,
+        height: self.height,
+        dim: self.dim,
+        encoding: BoxEncoding::default(),
+      },
+      reg_max: self.reg_max,
+    })
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+}
+
+/// YOLOv8/v11 后处理，先通过 DFL 将分布解码为到四条边的距离，再按 Yolo26 的方式解码
+pub struct Yolo8 {
+  decoder: Yolo26,
+  reg_max: u32,
+}
+
+/// 单个检测层级的输入 (cls, reg, stride)，reg 形状为 [N, 4 * reg_max, H, W]
+pub type Yolo8Level<R, F> = (DataBuffer<R, F>, DataBuffer<R, F>, F);
+
+impl Yolo8 {
+  /// 执行后处理操作
+  /// cls: 分类结果，形状为 [N, num_classes, H, W]
+  /// reg: 回归分布，形状为 [N, 4 * reg_max, H, W]，按 (left, top, right, bottom) 分组
+  /// 返回 (score, index, bbox) 三个张量，与 Yolo26::execute 一致
+  pub fn execute<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    cls: DataBuffer<R, F>,
+    reg: DataBuffer<R, F>,
+    stride: F,
+  ) -> Result<PPResult<R, F, I>, Yolo8Error> {
+    let dist = self.distribution_focal(client, reg)?;
+    Ok(self.decoder.execute(client, cls, dist, stride)?)
+  }
+
+  /// 对多个检测头执行后处理，并将结果按层级顺序拼接，与 Yolo26::execute_levels 一致
+  pub fn execute_levels<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<Yolo8Level<R, F>>,
+  ) -> Result<PPResult<R, F, I>, Yolo8Error> {
+    let levels = levels
+      .into_iter()
+      .map(|(cls, reg, stride)| Ok((cls, self.distribution_focal(client, reg)?, stride)))
+      .collect::<Result<Vec<_>, Yolo8Error>>()?;
+    Ok(self.decoder.execute_levels(client, levels)?)
+  }
+
+  /// 将 [N, 4 * reg_max, H, W] 的分布解码为 [N, 4, H, W] 的距离 (以 stride 为单位)
+  fn distribution_focal<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    reg: DataBuffer<R, F>,
+  ) -> Result<DataBuffer<R, F>, Yolo8Error> {
+    let [n, c, h, w] = *reg.shape() else {
+      return Err(Yolo8Error::InvalidInputShape(
+        "回归结果张量形状不正确，预期为 [N, 4 * reg_max, H, W]".to_string(),
+      ));
+    };
+    if c != 4 * self.reg_max as usize {
+      return Err(Yolo8Error::InvalidInputShape(format!(
+        "回归结果通道数应为 4 * reg_max = {}，实际为 {}",
+        4 * self.reg_max,
+        c
+      )));
+    }
+
+    let dist: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, h, w], client);
+
+    let count = (n * 4 * h * w).div_ceil(self.decoder.dim as usize);
+    dfl::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.decoder.dim),
+      reg.into_tensor_arg(1),
+      dist.into_tensor_arg(1),
+    )?;
+
+    Ok(dist)
+  }
+}
+
+/// 对每条边的分布做 softmax 并求期望，得到到该边的距离
+///
+/// reg: 输入回归分布，形状为 [N, 4 * reg_max, H, W]
+/// dist: 输出距离，形状为 [N, 4, H, W]，依次为 left, top, right, bottom
+#[cube(launch)]
+fn dfl<F: Float>(reg: Tensor<F>, dist: &mut Tensor<F>) {
+  // 输出张量总元素 = N * 4 * H * W
+  let total = dist.len();
+
+  // 线程全局索引
+  let idx = ABSOLUTE_POS;
+  if idx < total {
+    let reg_max = reg.shape(1) / 4;
+    let h_dim = reg.shape(2);
+    let w_dim = reg.shape(3);
+
+    // 将 idx 映射回 (n, side, h, w)
+    let hw = h_dim * w_dim;
+    let n_idx = idx / (4 * hw);
+    let side = (idx / hw) % 4;
+    let rem = idx % hw;
+    let h_idx = rem / w_dim;
+    let w_idx = rem % w_dim;
+
+    // 输入 strides (支持任意 stride 布局)
+    let stride_c = reg.stride(1);
+    let base = n_idx * reg.stride(0)
+      + side * reg_max * stride_c
+      + h_idx * reg.stride(2)
+      + w_idx * reg.stride(3);
+
+    // 减去最大值保证数值稳定
+    let mut max_val = reg[base];
+    for i in 1..reg_max {
+      max_val = F::max(max_val, reg[base + i * stride_c]);
+    }
+
+    let mut sum = F::new(comptime!(0.0));
+    let mut expectation = F::new(comptime!(0.0));
+    for i in 0..reg_max {
+      let e = (reg[base + i * stride_c] - max_val).exp();
+      sum += e;
+      expectation += e * F::cast_from(i);
+    }
+
+    dist[idx] = expectation / sum;
+  }
+}
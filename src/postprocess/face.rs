@@ -0,0 +1,472 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/face.rs - SCRFD/RetinaFace 人脸检测与关键点后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+//! SCRFD/RetinaFace 风格的人脸检测后处理
+//!
+//! 官方导出的 SCRFD 模型在每个层级输出三个张量，得分为 [N, H * W * A, 1]，
+//! 边界框为 [N, H * W * A, 4]，关键点为 [N, H * W * A, L * 2]，不带批次维的导出为 [H * W * A, C]。
+//! 行按 (h, w, a) 排列，锚点变化最快，H 与 W 为输入尺寸除以 stride 向下取整，
+//! 这是默认的 [`ScrfdLayout::Native`]，无需重排即可直接传入。
+//! 若检测头保留了特征图形状，使用 [`ScrfdLayout::Nchw`]，
+//! 此时得分为 [N, A, H, W]，边界框为 [N, A * 4, H, W]，关键点为 [N, A * L * 2, H, W]。
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::{
+  data::DataBuffer,
+  kernel::gather_channels,
+  postprocess::{
+    detection::Candidates,
+    nms::{Nms, NmsError},
+  },
+};
+
+#[derive(Debug, Error)]
+pub enum ScrfdError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("NMS 错误: {0}")]
+  NmsError(#[from] NmsError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// SCRFD 检测头输出的排列方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrfdLayout {
+  /// 官方导出的形状 [N, H * W * A, C] 或 [H * W * A, C]，行按 (h, w, a) 排列
+  Native,
+  /// 特征图形状 [N, A * C, H, W]，通道按锚点分组
+  Nchw,
+}
+
+pub struct ScrfdConfig {
+  width: u32,
+  height: u32,
+  layout: ScrfdLayout,
+  num_anchors: u32,
+  num_landmarks: u32,
+  sigmoid: bool,
+  dim: u32,
+}
+
+impl Default for ScrfdConfig {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      layout: ScrfdLayout::Native,
+      num_anchors: 2,
+      num_landmarks: 5,
+      sigmoid: false,
+      dim: 256,
+    }
+  }
+}
+
+impl ScrfdConfig {
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 检测头输出的排列方式，默认为官方导出的 [`ScrfdLayout::Native`]
+  pub fn with_layout(mut self, layout: ScrfdLayout) -> Self {
+    self.layout = layout;
+    self
+  }
+
+  /// 每个位置的锚点数量
+  pub fn with_num_anchors(mut self, num_anchors: u32) -> Self {
+    self.num_anchors = num_anchors;
+    self
+  }
+
+  /// 每张人脸的关键点数量
+  pub fn with_num_landmarks(mut self, num_landmarks: u32) -> Self {
+    self.num_landmarks = num_landmarks;
+    self
+  }
+
+  /// 为 true 时对得分做 sigmoid，官方导出的 SCRFD 模型已包含 sigmoid
+  pub fn with_sigmoid(mut self, sigmoid: bool) -> Self {
+    self.sigmoid = sigmoid;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Scrfd, ScrfdError> {
+    if self.num_anchors == 0 {
+      return Err(ScrfdError::InvalidConfig(
+        "num_anchors 必须大于 0".to_string(),
+      ));
+    }
+    if self.num_landmarks == 0 {
+      return Err(ScrfdError::InvalidConfig(
+        "num_landmarks 必须大于 0".to_string(),
+      ));
+    }
+    Ok(Scrfd {
+      width: self.width,
+      height: self.height,
+      layout: self.layout,
+      num_anchors: self.num_anchors,
+      num_landmarks: self.num_landmarks,
+      sigmoid: self.sigmoid,
+      dim: self.dim,
+    })
+  }
+}
+
+/// 单个检测层级的输入 (score, bbox, kps, stride)
+pub type ScrfdLevel<R, F> = (DataBuffer<R, F>, DataBuffer<R, F>, DataBuffer<R, F>, F);
+
+/// 稠密的人脸检测结果 (score, index, bbox, landmarks)
+///
+/// score/index 形状为 [N, M]，bbox 形状为 [N, 4, M]，landmarks 形状为 [N, L, 2, M]
+pub type ScrfdDense<R, F, I> = (
+  DataBuffer<R, F>,
+  DataBuffer<R, I>,
+  DataBuffer<R, F>,
+  DataBuffer<R, F>,
+);
+
+/// NMS 之后的人脸检测结果 (faces, landmarks)，landmarks 形状为 [N, L, 2, K]
+pub type ScrfdResult<R, F, I> = (Candidates<R, F, I>, DataBuffer<R, F>);
+
+/// SCRFD/RetinaFace 风格的人脸检测后处理
+///
+/// 边界框沿用 bbox kernel 的距离编码 (到四条边的距离乘以 stride)，
+/// 但与 SCRFD 一致，锚点中心为 (w, h) * stride，不加 0.5
+pub struct Scrfd {
+  width: u32,
+  height: u32,
+  layout: ScrfdLayout,
+  num_anchors: u32,
+  num_landmarks: u32,
+  sigmoid: bool,
+  dim: u32,
+}
+
+impl Scrfd {
+  /// 解码多个检测层级，并按层级顺序拼接
+  /// levels: 每个层级的 (score, bbox, kps, stride)，形状由 [`ScrfdLayout`] 决定，
+  /// 每个锚点依次为 (left, top, right, bottom) 与 L 个 (x, y)
+  /// 返回的位置顺序为层级、锚点、行、列，与输入排列方式无关，坐标为归一化坐标，index 全为 0
+  pub fn decode_levels<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<ScrfdLevel<R, F>>,
+  ) -> Result<ScrfdDense<R, F, I>, ScrfdError> {
+    let shapes = levels
+      .iter()
+      .map(|(score, bbox, kps, stride)| self.level_shape(score, bbox, kps, *stride))
+      .collect::<Result<Vec<_>, _>>()?;
+    let Some(&(n, _, _)) = shapes.first() else {
+      return Err(ScrfdError::InvalidInputShape(
+        "至少需要一个检测层级".to_string(),
+      ));
+    };
+    if shapes.iter().any(|&(ln, _, _)| ln != n) {
+      return Err(ScrfdError::InvalidInputShape(
+        "各层级的 N 必须一致".to_string(),
+      ));
+    }
+    let m: usize = shapes
+      .iter()
+      .map(|&(_, h, w)| self.num_anchors as usize * h * w)
+      .sum();
+
+    let l = self.num_landmarks as usize;
+    let score_out: DataBuffer<R, F> = DataBuffer::with_shape(&[n, m], client);
+    let index_out: DataBuffer<R, I> = DataBuffer::with_shape(&[n, m], client);
+    let bbox_out: DataBuffer<R, F> = DataBuffer::with_shape(&[n, 4, m], client);
+    let landmarks_out: DataBuffer<R, F> = DataBuffer::with_shape(&[n, l, 2, m], client);
+
+    let mut offset = 0;
+    for ((score, bbox, kps, stride), (_, h, w)) in levels.into_iter().zip(shapes) {
+      let count = (n * self.num_anchors as usize * h * w).div_ceil(self.dim as usize);
+      decode::launch::<F, I, R>(
+        client,
+        CubeCount::Static(count as u32, 1, 1),
+        CubeDim::new_1d(self.dim),
+        score.into_tensor_arg(1),
+        bbox.into_tensor_arg(1),
+        kps.into_tensor_arg(1),
+        score_out.into_tensor_arg(1),
+        index_out.into_tensor_arg(1),
+        bbox_out.into_tensor_arg(1),
+        landmarks_out.into_tensor_arg(1),
+        ScalarArg::new(F::new(self.width as f32)),
+        ScalarArg::new(F::new(self.height as f32)),
+        ScalarArg::new(stride),
+        ScalarArg::new(self.num_anchors),
+        ScalarArg::new(h as u32),
+        ScalarArg::new(w as u32),
+        ScalarArg::new(offset as u32),
+        self.layout,
+        self.sigmoid,
+      )?;
+      offset += self.num_anchors as usize * h * w;
+    }
+
+    Ok((score_out, index_out, bbox_out, landmarks_out))
+  }
+
+  /// 解码多个检测层级并执行 NMS
+  /// 返回 NMS 的保留结果与对应的关键点，关键点形状为 [N, L, 2, K]，K 为 NMS 的 max_detections
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    levels: Vec<ScrfdLevel<R, F>>,
+    nms: &Nms,
+  ) -> Result<ScrfdResult<R, F, I>, ScrfdError> {
+    let (score, index, bbox, landmarks) = self.decode_levels::<R, F, I>(client, levels)?;
+    let n = score.shape()[0];
+
+    let faces = nms.execute(client, score, index, bbox)?;
+    let k = faces.score.shape()[1];
+
+    let l = self.num_landmarks as usize;
+    let output: DataBuffer<R, F> = DataBuffer::with_shape(&[n, l, 2, k], client);
+    gather_channels::launch::<F, R>(
+      client,
+      CubeCount::Static((n * l * 2 * k).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      landmarks.into_tensor_arg(1),
+      faces.source.into_tensor_arg(1),
+      faces.count.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+    )?;
+
+    Ok((faces, output))
+  }
+
+  /// 检查单个层级的输入形状，返回 (N, H, W)
+  fn level_shape<R: Runtime, F: Float + CubeElement>(
+    &self,
+    score: &DataBuffer<R, F>,
+    bbox: &DataBuffer<R, F>,
+    kps: &DataBuffer<R, F>,
+    stride: F,
+  ) -> Result<(usize, usize, usize), ScrfdError> {
+    match self.layout {
+      ScrfdLayout::Native => self.native_shape(score, bbox, kps, stride),
+      ScrfdLayout::Nchw => self.nchw_shape(score, bbox, kps),
+    }
+  }
+
+  /// 检查 [N, H * W * A, C] 或 [H * W * A, C] 的输入，H 与 W 由输入尺寸与 stride 得到
+  fn native_shape<R: Runtime, F: Float + CubeElement>(
+    &self,
+    score: &DataBuffer<R, F>,
+    bbox: &DataBuffer<R, F>,
+    kps: &DataBuffer<R, F>,
+    stride: F,
+  ) -> Result<(usize, usize, usize), ScrfdError> {
+    let stride = stride
+      .to_f32()
+      .filter(|s| s.is_finite() && *s > 0.0)
+      .ok_or_else(|| ScrfdError::InvalidInputShape("stride 必须为正数".to_string()))?;
+    let h = (self.height as f32 / stride).floor() as usize;
+    let w = (self.width as f32 / stride).floor() as usize;
+    let rows = h * w * self.num_anchors as usize;
+
+    let (n, prefix) = match *score.shape() {
+      [n, r, 1] if r == rows => (n, vec![n, rows]),
+      [r, 1] if r == rows => (1, vec![rows]),
+      _ => {
+        return Err(ScrfdError::InvalidInputShape(format!(
+          "得分张量形状不正确，预期为 [N, {}, 1] 或 [{}, 1]",
+          rows, rows
+        )));
+      }
+    };
+    let expected = |c: usize| [prefix.as_slice(), &[c]].concat();
+    if bbox.shape() != expected(4) {
+      return Err(ScrfdError::InvalidInputShape(format!(
+        "边界框张量形状不正确，预期为 {:?}",
+        expected(4)
+      )));
+    }
+    let l = self.num_landmarks as usize * 2;
+    if kps.shape() != expected(l) {
+      return Err(ScrfdError::InvalidInputShape(format!(
+        "关键点张量形状不正确，预期为 {:?}",
+        expected(l)
+      )));
+    }
+    Ok((n, h, w))
+  }
+
+  /// 检查 [N, A * C, H, W] 的输入
+  fn nchw_shape<R: Runtime, F: Float + CubeElement>(
+    &self,
+    score: &DataBuffer<R, F>,
+    bbox: &DataBuffer<R, F>,
+    kps: &DataBuffer<R, F>,
+  ) -> Result<(usize, usize, usize), ScrfdError> {
+    let a = self.num_anchors as usize;
+    let [n, sa, h, w] = *score.shape() else {
+      return Err(ScrfdError::InvalidInputShape(
+        "得分张量形状不正确，预期为 [N, A, H, W]".to_string(),
+      ));
+    };
+    if sa != a {
+      return Err(ScrfdError::InvalidInputShape(format!(
+        "得分张量形状不正确，预期为 [N, {}, H, W]",
+        a
+      )));
+    }
+    if bbox.shape() != [n, a * 4, h, w] {
+      return Err(ScrfdError::InvalidInputShape(format!(
+        "边界框张量形状不正确，预期为 [N, {} * 4, H, W]",
+        a
+      )));
+    }
+    if kps.shape() != [n, a * self.num_landmarks as usize * 2, h, w] {
+      return Err(ScrfdError::InvalidInputShape(format!(
+        "关键点张量形状不正确，预期为 [N, {} * {} * 2, H, W]",
+        a, self.num_landmarks
+      )));
+    }
+    Ok((n, h, w))
+  }
+}
+
+/// 解码人脸边界框与关键点
+///
+/// score/bbox/kps: 输入，形状由 layout 决定，通道数分别为 1、4、L * 2
+/// score_out/index_out: 输出 [N, M]，bbox_out: 输出 [N, 4, M]，landmarks_out: 输出 [N, L, 2, M]
+/// a_dim/h_dim/w_dim: 该层级的锚点数与特征图尺寸
+/// offset: 写入输出中每张图像的 [offset, offset + A * H * W) 位置
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+fn decode<F: Float + CubeScalar, I: Int>(
+  score: &Tensor<F>,
+  bbox: &Tensor<F>,
+  kps: &Tensor<F>,
+  score_out: &mut Tensor<F>,
+  index_out: &mut Tensor<I>,
+  bbox_out: &mut Tensor<F>,
+  landmarks_out: &mut Tensor<F>,
+  image_width: F,
+  image_height: F,
+  stride: F,
+  a_dim: u32,
+  h_dim: u32,
+  w_dim: u32,
+  offset: u32,
+  #[comptime] layout: ScrfdLayout,
+  #[comptime] sigmoid: bool,
+) {
+  let n_dim = score_out.shape(0);
+  let a_dim = a_dim as usize;
+  let w_dim = w_dim as usize;
+  let hw = h_dim as usize * w_dim;
+
+  // 需要处理的总元素 = N * A * H * W
+  let idx = ABSOLUTE_POS;
+  if idx < n_dim * a_dim * hw {
+    let zero_value = F::new(comptime!(0.0));
+    let one_value = F::new(comptime!(1.0));
+
+    // 将 idx 映射回 (n, a, h, w)
+    let n_idx = idx / (a_dim * hw);
+    let rem = idx % (a_dim * hw);
+    let a_idx = rem / hw;
+    let h_idx = (rem % hw) / w_dim;
+    let w_idx = rem % w_dim;
+    let cell = (n_idx, a_idx, h_idx, w_idx);
+
+    let (base, _) = head_offset::<F>(score, cell, a_dim, w_dim, 1, layout);
+    let raw = score[base];
+    let value = if comptime!(sigmoid) {
+      one_value / (one_value + (-raw).exp())
+    } else {
+      raw
+    };
+
+    let center_x = F::cast_from(w_idx) * stride;
+    let center_y = F::cast_from(h_idx) * stride;
+
+    let m = score_out.len() / n_dim;
+    let out = n_idx * m + offset as usize + rem;
+    score_out[out] = value;
+    index_out[out] = I::cast_from(0u32);
+
+    let (base, step) = head_offset::<F>(bbox, cell, a_dim, w_dim, 4, layout);
+    let xmin = center_x - bbox[base] * stride;
+    let ymin = center_y - bbox[base + step] * stride;
+    let xmax = center_x + bbox[base + 2 * step] * stride;
+    let ymax = center_y + bbox[base + 3 * step] * stride;
+
+    let out_box = n_idx * 4 * m + offset as usize + rem;
+    bbox_out[out_box] = (xmin / image_width).clamp(zero_value, one_value);
+    bbox_out[out_box + m] = (ymin / image_height).clamp(zero_value, one_value);
+    bbox_out[out_box + 2 * m] = (xmax / image_width).clamp(zero_value, one_value);
+    bbox_out[out_box + 3 * m] = (ymax / image_height).clamp(zero_value, one_value);
+
+    let l_dim = landmarks_out.shape(1);
+    let (base, step) = head_offset::<F>(kps, cell, a_dim, w_dim, l_dim * 2, layout);
+    let out_kps = n_idx * l_dim * 2 * m + offset as usize + rem;
+    for l in 0..l_dim {
+      let x = center_x + kps[base + 2 * l * step] * stride;
+      let y = center_y + kps[base + (2 * l + 1) * step] * stride;
+      landmarks_out[out_kps + 2 * l * m] = (x / image_width).clamp(zero_value, one_value);
+      landmarks_out[out_kps + (2 * l + 1) * m] = (y / image_height).clamp(zero_value, one_value);
+    }
+  }
+}
+
+/// 计算 (n, a, h, w) 处第一个通道在检测头输出中的位置，返回 (位置, 通道步长)
+/// channels: 每个锚点的通道数
+#[cube]
+fn head_offset<F: Float>(
+  head: &Tensor<F>,
+  cell: (usize, usize, usize, usize),
+  a_dim: usize,
+  w_dim: usize,
+  channels: usize,
+  #[comptime] layout: ScrfdLayout,
+) -> (usize, usize) {
+  let (n_idx, a_idx, h_idx, w_idx) = cell;
+  let step = if comptime!(layout == ScrfdLayout::Native) {
+    head.stride(head.rank() - 1)
+  } else {
+    head.stride(1)
+  };
+  let mut base = 0;
+  if comptime!(layout == ScrfdLayout::Native) {
+    // 行按 (h, w, a) 排列，不带批次维时 N 为 1
+    let rank = head.rank();
+    if rank == 3 {
+      base = n_idx * head.stride(0);
+    }
+    base += ((h_idx * w_dim + w_idx) * a_dim + a_idx) * head.stride(rank - 2);
+  } else {
+    base = n_idx * head.stride(0)
+      + a_idx * channels * step
+      + h_idx * head.stride(2)
+      + w_idx * head.stride(3);
+  }
+  (base, step)
+}
@@ -0,0 +1,34 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/geometry.rs - 主机端的边界框几何计算
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+//! 与 Kernel 中的 iou 对应的 CPU 实现，供在主机端处理检测结果的模块使用，
+//! 边界框均为 (xmin, ymin, xmax, ymax)
+
+/// 边界框面积，坐标颠倒时为 0
+pub fn box_area(bbox: [f32; 4]) -> f32 {
+  (bbox[2] - bbox[0]).max(0.0) * (bbox[3] - bbox[1]).max(0.0)
+}
+
+/// 两个边界框的交集面积
+pub fn box_intersection(a: [f32; 4], b: [f32; 4]) -> f32 {
+  let inter_w = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+  let inter_h = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+  inter_w * inter_h
+}
+
+/// 两个边界框的交并比，并集为 0 时返回 0
+pub fn box_iou(a: [f32; 4], b: [f32; 4]) -> f32 {
+  let inter = box_intersection(a, b);
+  let union = box_area(a) + box_area(b) - inter;
+  if union > 0.0 { inter / union } else { 0.0 }
+}
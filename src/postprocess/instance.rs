@@ -0,0 +1,358 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/instance.rs - 实例分割 (YOLO-seg) 的掩码组装
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::{
+  data::DataBuffer,
+  postprocess::detection::{BoxUnit, Candidates, candidate_shape},
+};
+
+#[derive(Debug, Error)]
+pub enum MaskAssemblyError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct MaskAssemblyConfig {
+  width: u32,
+  height: u32,
+  binary: bool,
+  threshold: f32,
+  unit: BoxUnit,
+  dim: u32,
+}
+
+impl Default for MaskAssemblyConfig {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      binary: true,
+      threshold: 0.5,
+      unit: BoxUnit::Normalized,
+      dim: 256,
+    }
+  }
+}
+
+impl MaskAssemblyConfig {
+  /// 输出掩码的尺寸，通常为网络输入尺寸
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  /// 为 true 时输出 0/1 二值掩码，否则输出 [0, 1] 的软掩码
+  pub fn with_binary(mut self, binary: bool) -> Self {
+    self.binary = binary;
+    self
+  }
+
+  /// 二值化阈值，仅在 binary 为 true 时使用
+  pub fn with_threshold(mut self, threshold: f32) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  /// 候选边界框的坐标单位，应与 Yolo26Config::with_box_unit 一致，默认为 Normalized；
+  /// 为 Pixel 时坐标相对于网络输入，此时 with_shape 应为网络输入尺寸
+  pub fn with_box_unit(mut self, unit: BoxUnit) -> Self {
+    self.unit = unit;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<MaskAssembly, MaskAssemblyError> {
+    Ok(MaskAssembly {
+      width: self.width,
+      height: self.height,
+      binary: self.binary,
+      threshold: self.threshold,
+      unit: self.unit,
+      dim: self.dim,
+    })
+  }
+}
+
+/// 将每个检测结果的掩码系数与原型掩码组合为实例掩码
+pub struct MaskAssembly {
+  width: u32,
+  height: u32,
+  binary: bool,
+  threshold: f32,
+  unit: BoxUnit,
+  dim: u32,
+}
+
+impl MaskAssembly {
+  /// 组装实例掩码
+  /// coef: 掩码系数，形状为 [N, P, H, W]，与检测头的位置一一对应
+  /// proto: 原型掩码，形状为 [N, P, Hp, Wp]
+  /// candidates: 检测结果 (如 NMS 的输出)，bbox 的单位由 with_box_unit 决定，source 指向 coef 中的位置
+  /// 返回形状为 [N, K, height, width] 的掩码，count[n] 之后的位置为 0
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    coef: DataBuffer<R, F>,
+    proto: DataBuffer<R, F>,
+    candidates: &Candidates<R, F, I>,
+  ) -> Result<DataBuffer<R, F>, MaskAssemblyError> {
+    self.execute_levels(client, vec![coef], proto, candidates)
+  }
+
+  /// 对多层级的掩码系数组装实例掩码
+  /// coefs: 每个层级的掩码系数 [N, P, H_i, W_i]，顺序与 Yolo26::execute_levels 的层级顺序一致，
+  ///        即 source 中的位置按层级顺序拼接
+  pub fn execute_levels<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    coefs: Vec<DataBuffer<R, F>>,
+    proto: DataBuffer<R, F>,
+    candidates: &Candidates<R, F, I>,
+  ) -> Result<DataBuffer<R, F>, MaskAssemblyError> {
+    let (n, k) = candidate_shape(
+      candidates.score.shape(),
+      candidates.index.shape(),
+      candidates.bbox.shape(),
+    )
+    .map_err(MaskAssemblyError::InvalidInputShape)?;
+    if candidates.source.shape() != [n, k] || candidates.count.shape() != [n] {
+      return Err(MaskAssemblyError::InvalidInputShape(
+        "候选列表的 source 或 count 形状不正确".to_string(),
+      ));
+    }
+    let [pn, p, _, _] = *proto.shape() else {
+      return Err(MaskAssemblyError::InvalidInputShape(
+        "原型掩码张量形状不正确，预期为 [N, P, Hp, Wp]".to_string(),
+      ));
+    };
+    if pn != n {
+      return Err(MaskAssemblyError::InvalidInputShape(
+        "原型掩码的 N 应与检测结果一致".to_string(),
+      ));
+    }
+    if coefs.is_empty() {
+      return Err(MaskAssemblyError::InvalidInputShape(
+        "至少需要一个层级的掩码系数".to_string(),
+      ));
+    }
+
+    // 先按 source 收集每个检测结果的系数 [N, P, K]
+    let gathered: DataBuffer<R, F> = DataBuffer::with_shape(&[n, p, k], client);
+    let total = n * p * k;
+    let mut offset = 0;
+    for coef in coefs {
+      let [cn, cp, h, w] = *coef.shape() else {
+        return Err(MaskAssemblyError::InvalidInputShape(
+          "掩码系数张量形状不正确，预期为 [N, P, H, W]".to_string(),
+        ));
+      };
+      if cn != n || cp != p {
+        return Err(MaskAssemblyError::InvalidInputShape(
+          "掩码系数的 N 与 P 应与原型掩码一致".to_string(),
+        ));
+      }
+      gather_coefficients::launch::<F, R>(
+        client,
+        CubeCount::Static(total.div_ceil(self.dim as usize) as u32, 1, 1),
+        CubeDim::new_1d(self.dim),
+        coef.into_tensor_arg(1),
+        candidates.source.into_tensor_arg(1),
+        candidates.count.into_tensor_arg(1),
+        gathered.into_tensor_arg(1),
+        ScalarArg::new(offset as u32),
+      )?;
+      offset += h * w;
+    }
+
+    let (width, height) = (self.width as usize, self.height as usize);
+    let masks: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k, height, width], client);
+    let total = n * k * height * width;
+    assemble::launch::<F, R>(
+      client,
+      CubeCount::Static(total.div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      gathered.into_tensor_arg(1),
+      proto.into_tensor_arg(1),
+      candidates.bbox.into_tensor_arg(1),
+      candidates.count.into_tensor_arg(1),
+      masks.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.threshold)),
+      self.binary,
+      self.unit,
+    )?;
+
+    Ok(masks)
+  }
+}
+
+/// 收集单个层级的掩码系数: output[n, p, k] = coef[n, p, source[n, k] - offset]
+///
+/// 仅处理 k < count[n] 且 source[n, k] 落在该层级 [offset, offset + H * W) 范围内的位置，
+/// 第一个层级 (offset 为 0) 同时将其余位置清零，因此 source 超出所有层级的有效位置系数为 0
+/// coef: [N, P, H, W]，source: [N, K]，count: [N]，output: [N, P, K]
+#[cube(launch)]
+fn gather_coefficients<F: Float>(
+  coef: &Tensor<F>,
+  source: &Tensor<u32>,
+  count: &Tensor<u32>,
+  output: &mut Tensor<F>,
+  offset: u32,
+) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let p_dim = output.shape(1);
+    let k_dim = output.shape(2);
+    let hw = coef.shape(2) * coef.shape(3);
+
+    let n_idx = idx / (p_dim * k_dim);
+    let p_idx = (idx / k_dim) % p_dim;
+    let k_idx = idx % k_dim;
+
+    let src = source[n_idx * k_dim + k_idx];
+    let valid = (k_idx as u32) < count[n_idx];
+    if valid && src >= offset && ((src - offset) as usize) < hw {
+      let pos = (src - offset) as usize;
+      let h_idx = pos / coef.shape(3);
+      let w_idx = pos % coef.shape(3);
+      output[idx] = coef[n_idx * coef.stride(0)
+        + p_idx * coef.stride(1)
+        + h_idx * coef.stride(2)
+        + w_idx * coef.stride(3)];
+    } else if offset == 0 {
+      output[idx] = F::new(comptime!(0.0));
+    }
+  }
+}
+
+/// 计算原型掩码第 (y, x) 个位置的掩码 logit: sum_p coef[n, p, k] * proto[n, p, y, x]
+#[cube]
+fn proto_logit<F: Float>(
+  coef: &Tensor<F>,
+  proto: &Tensor<F>,
+  n_idx: usize,
+  k_idx: usize,
+  y: usize,
+  x: usize,
+) -> F {
+  let p_dim = coef.shape(1);
+  let k_dim = coef.shape(2);
+  let mut acc = F::new(comptime!(0.0));
+  for p in 0..p_dim {
+    acc += coef[(n_idx * p_dim + p) * k_dim + k_idx]
+      * proto
+        [n_idx * proto.stride(0) + p * proto.stride(1) + y * proto.stride(2) + x * proto.stride(3)];
+  }
+  acc
+}
+
+/// 组装实例掩码
+///
+/// 对原型掩码的线性组合做 sigmoid 后双线性上采样 (像素中心对齐) 到输出尺寸，
+/// 并将边界框以外的位置置为 0
+/// coef: [N, P, K]，proto: [N, P, Hp, Wp]，count: [N]
+/// bbox: [N, 4, K]，unit 为 Pixel 时为相对于输出尺寸的像素坐标，否则为归一化坐标
+/// masks: 输出 [N, K, H, W]
+#[cube(launch)]
+fn assemble<F: Float + CubeScalar>(
+  coef: &Tensor<F>,
+  proto: &Tensor<F>,
+  bbox: &Tensor<F>,
+  count: &Tensor<u32>,
+  masks: &mut Tensor<F>,
+  threshold: F,
+  #[comptime] binary: bool,
+  #[comptime] unit: BoxUnit,
+) {
+  let idx = ABSOLUTE_POS;
+  if idx < masks.len() {
+    let zero = F::new(comptime!(0.0));
+    let one = F::new(comptime!(1.0));
+    let half = F::new(comptime!(0.5));
+
+    let k_dim = masks.shape(1);
+    let h_dim = masks.shape(2);
+    let w_dim = masks.shape(3);
+    let ph_dim = proto.shape(2);
+    let pw_dim = proto.shape(3);
+
+    let n_idx = idx / (k_dim * h_dim * w_dim);
+    let k_idx = (idx / (h_dim * w_dim)) % k_dim;
+    let y = (idx / w_dim) % h_dim;
+    let x = idx % w_dim;
+
+    // 输出像素中心的归一化坐标
+    let u = (F::cast_from(x) + half) / F::cast_from(w_dim);
+    let v = (F::cast_from(y) + half) / F::cast_from(h_dim);
+
+    // 像素坐标的边界框换算为归一化坐标
+    let (x_scale, y_scale) = if comptime!(unit == BoxUnit::Pixel) {
+      (F::cast_from(w_dim), F::cast_from(h_dim))
+    } else {
+      (one, one)
+    };
+
+    let base = n_idx * 4 * k_dim + k_idx;
+    let inside = (k_idx as u32) < count[n_idx]
+      && u >= bbox[base] / x_scale
+      && v >= bbox[base + k_dim] / y_scale
+      && u < bbox[base + 2 * k_dim] / x_scale
+      && v < bbox[base + 3 * k_dim] / y_scale;
+
+    let mut value = zero;
+    if inside {
+      // 在原型掩码中的采样位置，边界处截断
+      let max_x = F::cast_from(pw_dim - 1);
+      let max_y = F::cast_from(ph_dim - 1);
+      let sx = (u * F::cast_from(pw_dim) - half).clamp(zero, max_x);
+      let sy = (v * F::cast_from(ph_dim) - half).clamp(zero, max_y);
+
+      let x0 = u32::cast_from(sx.floor()) as usize;
+      let y0 = u32::cast_from(sy.floor()) as usize;
+      let mut x1 = x0 + 1;
+      if x1 >= pw_dim {
+        x1 = pw_dim - 1;
+      }
+      let mut y1 = y0 + 1;
+      if y1 >= ph_dim {
+        y1 = ph_dim - 1;
+      }
+      let fx = sx - F::cast_from(x0);
+      let fy = sy - F::cast_from(y0);
+
+      let m00 = one / (one + (-proto_logit(coef, proto, n_idx, k_idx, y0, x0)).exp());
+      let m01 = one / (one + (-proto_logit(coef, proto, n_idx, k_idx, y0, x1)).exp());
+      let m10 = one / (one + (-proto_logit(coef, proto, n_idx, k_idx, y1, x0)).exp());
+      let m11 = one / (one + (-proto_logit(coef, proto, n_idx, k_idx, y1, x1)).exp());
+
+      let top = m00 + (m01 - m00) * fx;
+      let bottom = m10 + (m11 - m10) * fx;
+      value = top + (bottom - top) * fy;
+
+      if comptime!(binary) {
+        value = if value > threshold { one } else { zero };
+      }
+    }
+
+    masks[idx] = value;
+  }
+}
@@ -0,0 +1,348 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/letterbox.rs - 将检测框从网络输入坐标映射回原始图像坐标
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  postprocess::detection::{BoxEncoding, BoxFormat, BoxLayout, BoxUnit, Candidates},
+};
+
+#[derive(Debug, Error)]
+pub enum LetterboxError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的输入: {0}")]
+  InvalidInput(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 原始图像到网络输入的预处理参数
+///
+/// 预处理时原始图像先缩放 (scale_x, scale_y)，再在左上方填充 (pad_x, pad_y) 像素，
+/// 因此网络输入坐标 x' = x * scale_x + pad_x
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Letterbox {
+  pub source_width: u32,
+  pub source_height: u32,
+  pub scale_x: f32,
+  pub scale_y: f32,
+  pub pad_x: f32,
+  pub pad_y: f32,
+}
+
+impl Letterbox {
+  /// 等比例缩放并居中填充 (letterbox)
+  pub fn fit(source_width: u32, source_height: u32, input_width: u32, input_height: u32) -> Self {
+    let scale =
+      (input_width as f32 / source_width as f32).min(input_height as f32 / source_height as f32);
+    Self {
+      source_width,
+      source_height,
+      scale_x: scale,
+      scale_y: scale,
+      pad_x: (input_width as f32 - source_width as f32 * scale) / 2.0,
+      pad_y: (input_height as f32 - source_height as f32 * scale) / 2.0,
+    }
+  }
+
+  /// 直接拉伸到网络输入尺寸，不填充
+  pub fn stretch(
+    source_width: u32,
+    source_height: u32,
+    input_width: u32,
+    input_height: u32,
+  ) -> Self {
+    Self {
+      source_width,
+      source_height,
+      scale_x: input_width as f32 / source_width as f32,
+      scale_y: input_height as f32 / source_height as f32,
+      pad_x: 0.0,
+      pad_y: 0.0,
+    }
+  }
+
+  /// 原始图像尺寸不为 0 且缩放与填充为有限值时才能反投影，
+  /// 原始或网络输入尺寸为 0 时 fit/stretch 会得到无效的参数
+  fn is_valid(&self) -> bool {
+    self.source_width > 0
+      && self.source_height > 0
+      && self.scale_x.is_finite()
+      && self.scale_y.is_finite()
+      && self.scale_x > 0.0
+      && self.scale_y > 0.0
+      && self.pad_x.is_finite()
+      && self.pad_y.is_finite()
+  }
+
+  fn to_params(self) -> [f32; 6] {
+    [
+      self.scale_x,
+      self.scale_y,
+      self.pad_x,
+      self.pad_y,
+      self.source_width as f32,
+      self.source_height as f32,
+    ]
+  }
+}
+
+pub struct BackProjectConfig {
+  width: u32,
+  height: u32,
+  dim: u32,
+  encoding: BoxEncoding,
+}
+
+impl Default for BackProjectConfig {
+  fn default() -> Self {
+    Self {
+      width: 640,
+      height: 640,
+      dim: 256,
+      encoding: BoxEncoding::default(),
+    }
+  }
+}
+
+impl BackProjectConfig {
+  /// 网络输入尺寸，应与 Yolo26Config::with_shape 一致
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.width = width;
+    self.height = height;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  /// 输入边界框的编码方式，应与 Yolo26::box_encoding 一致，默认为归一化的 [N, 4, ...] xyxy
+  ///
+  /// 输出保持相同的格式与排列方式，单位总是原始图像的像素坐标
+  pub fn with_box_encoding(mut self, encoding: BoxEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  pub fn build(self) -> Result<BackProject, LetterboxError> {
+    Ok(BackProject {
+      width: self.width,
+      height: self.height,
+      dim: self.dim,
+      encoding: self.encoding,
+    })
+  }
+}
+
+pub struct BackProject {
+  width: u32,
+  height: u32,
+  dim: u32,
+  encoding: BoxEncoding,
+}
+
+impl BackProject {
+  /// 将网络输入上的边界框映射为原始图像的像素坐标，并裁剪到原始图像范围内
+  /// bbox: 边界框坐标，按 with_box_encoding 给出的编码解读，默认形状为 [N, 4, ...]，
+  ///       为归一化的 xmin, ymin, xmax, ymax
+  /// letterbox: 每张图像的预处理参数，长度为 1 (所有图像共用) 或 N
+  /// 返回与 bbox 形状、格式相同的像素坐标
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    bbox: DataBuffer<R, F>,
+    letterbox: &[Letterbox],
+  ) -> Result<DataBuffer<R, F>, LetterboxError> {
+    self.project(client, bbox, letterbox, self.encoding)
+  }
+
+  /// 对候选列表中的边界框执行映射，其余字段保持不变
+  ///
+  /// 候选列表总是 [N, 4, K] 排列的 xyxy，因此只使用 with_box_encoding 中的单位
+  pub fn execute_candidates<R: Runtime, F: Float + CubeElement + CubeScalar, I: CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    mut candidates: Candidates<R, F, I>,
+    letterbox: &[Letterbox],
+  ) -> Result<Candidates<R, F, I>, LetterboxError> {
+    let encoding = BoxEncoding {
+      format: BoxFormat::Xyxy,
+      layout: BoxLayout::Planar,
+      ..self.encoding
+    };
+    candidates.bbox = self.project(client, candidates.bbox, letterbox, encoding)?;
+    Ok(candidates)
+  }
+
+  fn project<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    bbox: DataBuffer<R, F>,
+    letterbox: &[Letterbox],
+    encoding: BoxEncoding,
+  ) -> Result<DataBuffer<R, F>, LetterboxError> {
+    let shape = bbox.shape();
+    match encoding.layout {
+      BoxLayout::Planar if shape.len() < 2 || shape[1] != 4 => {
+        return Err(LetterboxError::InvalidInputShape(
+          "边界框张量形状不正确，预期为 [N, 4, ...]".to_string(),
+        ));
+      }
+      BoxLayout::Interleaved if shape.len() < 2 || shape[shape.len() - 1] != 4 => {
+        return Err(LetterboxError::InvalidInputShape(
+          "边界框张量形状不正确，预期为 [N, ..., 4]".to_string(),
+        ));
+      }
+      _ => {}
+    }
+    let n = shape[0];
+    if let Some(invalid) = letterbox.iter().find(|lb| !lb.is_valid()) {
+      return Err(LetterboxError::InvalidInput(format!(
+        "预处理参数无效，原始图像尺寸为 {}x{}，缩放为 ({}, {})",
+        invalid.source_width, invalid.source_height, invalid.scale_x, invalid.scale_y
+      )));
+    }
+    let params: Vec<F> = match letterbox.len() {
+      1 => letterbox.repeat(n),
+      len if len == n => letterbox.to_vec(),
+      len => {
+        return Err(LetterboxError::InvalidInputShape(format!(
+          "预处理参数数量应为 1 或 {}，实际为 {}",
+          n, len
+        )));
+      }
+    }
+    .into_iter()
+    .flat_map(Letterbox::to_params)
+    .map(F::new)
+    .collect();
+    let params = DataBuffer::<R, F>::from_slice(&params, &[n, 6], client)?;
+
+    let output = bbox.empty_like(client);
+    let count = (bbox.shape().iter().product::<usize>() / 4).div_ceil(self.dim as usize);
+    back_project::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      bbox.into_tensor_arg(1),
+      params.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.width as f32)),
+      ScalarArg::new(F::new(self.height as f32)),
+      encoding.format,
+      encoding.unit,
+      encoding.layout,
+    )?;
+
+    Ok(output)
+  }
+}
+
+/// 将网络输入上的边界框映射回原始图像像素坐标
+/// bbox: 输入边界框，layout 为 Planar 时为 [N, 4, M]，Interleaved 时为 [N, M, 4]，紧凑布局；
+///       四个分量由 format 决定，unit 为 Normalized 时相对于网络输入宽高
+/// params: 每张图像的 (scale_x, scale_y, pad_x, pad_y, source_width, source_height) [N, 6]
+/// output: 输出边界框，与 bbox 的形状和格式相同
+#[cube(launch)]
+fn back_project<F: Float + CubeScalar>(
+  bbox: &Tensor<F>,
+  params: &Tensor<F>,
+  output: &mut Tensor<F>,
+  input_width: F,
+  input_height: F,
+  #[comptime] format: BoxFormat,
+  #[comptime] unit: BoxUnit,
+  #[comptime] layout: BoxLayout,
+) {
+  let n_dim = params.shape(0);
+  let nm = bbox.len() / 4;
+
+  let idx = ABSOLUTE_POS;
+  if idx < nm {
+    let zero = F::new(comptime!(0.0));
+    let m = nm / n_dim;
+    let n_idx = idx / m;
+    let rem = idx % m;
+
+    let p = n_idx * 6;
+    let scale_x = params[p];
+    let scale_y = params[p + 1];
+    let pad_x = params[p + 2];
+    let pad_y = params[p + 3];
+    let source_width = params[p + 4];
+    let source_height = params[p + 5];
+
+    let half = F::new(comptime!(0.5));
+    let one = F::new(comptime!(1.0));
+    let (base, step) = if comptime!(layout == BoxLayout::Planar) {
+      (n_idx * 4 * m + rem, m)
+    } else {
+      ((n_idx * m + rem) * 4, 1)
+    };
+    let v0 = bbox[base];
+    let v1 = bbox[base + step];
+    let v2 = bbox[base + 2 * step];
+    let v3 = bbox[base + 3 * step];
+
+    // 还原为网络输入上的角点 (xmin, ymin, xmax, ymax)
+    let mut xmin = v0;
+    let mut ymin = v1;
+    let mut xmax = v2;
+    let mut ymax = v3;
+    if comptime!(format == BoxFormat::Xywh) {
+      xmax = v0 + v2;
+      ymax = v1 + v3;
+    } else if comptime!(format == BoxFormat::Cxcywh) {
+      xmin = v0 - v2 * half;
+      ymin = v1 - v3 * half;
+      xmax = v0 + v2 * half;
+      ymax = v1 + v3 * half;
+    }
+    let (x_scale, y_scale) = if comptime!(unit == BoxUnit::Normalized) {
+      (input_width, input_height)
+    } else {
+      (one, one)
+    };
+
+    let xmin = ((xmin * x_scale - pad_x) / scale_x).clamp(zero, source_width);
+    let ymin = ((ymin * y_scale - pad_y) / scale_y).clamp(zero, source_height);
+    let xmax = ((xmax * x_scale - pad_x) / scale_x).clamp(zero, source_width);
+    let ymax = ((ymax * y_scale - pad_y) / scale_y).clamp(zero, source_height);
+
+    // 按输入格式写回
+    let mut v0 = xmin;
+    let mut v1 = ymin;
+    let mut v2 = xmax;
+    let mut v3 = ymax;
+    if comptime!(format == BoxFormat::Xywh) {
+      v2 = xmax - xmin;
+      v3 = ymax - ymin;
+    } else if comptime!(format == BoxFormat::Cxcywh) {
+      v0 = (xmin + xmax) * half;
+      v1 = (ymin + ymax) * half;
+      v2 = xmax - xmin;
+      v3 = ymax - ymin;
+    }
+    output[base] = v0;
+    output[base + step] = v1;
+    output[base + 2 * step] = v2;
+    output[base + 3 * step] = v3;
+  }
+}
@@ -0,0 +1,528 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/nms.rs - 非极大值抑制 (NMS) 后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  kernel::{gather, iou, rotated_iou},
+  postprocess::detection::{BoxEncoding, Candidates, candidate_shape, check_xyxy_planar},
+};
+
+#[derive(Debug, Error)]
+pub enum NmsError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 与已保留框重叠的候选的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NmsMethod {
+  /// 交并比大于阈值时直接丢弃
+  Hard,
+  /// Soft-NMS 线性衰减，交并比大于阈值时得分乘以 (1 - IoU)
+  Linear,
+  /// Soft-NMS 高斯衰减，得分乘以 exp(-IoU² / sigma)，不使用交并比阈值
+  Gaussian { sigma: f32 },
+}
+
+/// Kernel 中使用的衰减方式，高斯衰减的 sigma 作为运行时参数传入
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Decay {
+  Hard,
+  Linear,
+  Gaussian,
+}
+
+pub struct NmsConfig {
+  score_threshold: f32,
+  iou_threshold: f32,
+  max_detections: u32,
+  class_agnostic: bool,
+  method: NmsMethod,
+  dim: u32,
+  encoding: BoxEncoding,
+}
+
+impl Default for NmsConfig {
+  fn default() -> Self {
+    Self {
+      score_threshold: 0.25,
+      iou_threshold: 0.45,
+      max_detections: 300,
+      class_agnostic: false,
+      method: NmsMethod::Hard,
+      dim: 256,
+      encoding: BoxEncoding::default(),
+    }
+  }
+}
+
+impl NmsConfig {
+  /// 低于该得分的候选框直接丢弃
+  pub fn with_score_threshold(mut self, score_threshold: f32) -> Self {
+    self.score_threshold = score_threshold;
+    self
+  }
+
+  /// 与已保留框的交并比大于该阈值的候选框会被抑制
+  pub fn with_iou_threshold(mut self, iou_threshold: f32) -> Self {
+    self.iou_threshold = iou_threshold;
+    self
+  }
+
+  /// 每张图像最多保留的检测框数量
+  pub fn with_max_detections(mut self, max_detections: u32) -> Self {
+    self.max_detections = max_detections;
+    self
+  }
+
+  /// 为 true 时不同类别之间也会相互抑制
+  pub fn with_class_agnostic(mut self, class_agnostic: bool) -> Self {
+    self.class_agnostic = class_agnostic;
+    self
+  }
+
+  /// 抑制方式，默认为 Hard；使用 Soft-NMS 时输出的得分为衰减后的得分，
+  /// 衰减到 score_threshold 以下的候选会被丢弃
+  pub fn with_method(mut self, method: NmsMethod) -> Self {
+    self.method = method;
+    self
+  }
+
+  /// 每张图像使用一个 Cube 处理，dim 为 Cube 内的线程数，必须为 2 的幂
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  /// 输入边界框的编码方式，应与 Yolo26::box_encoding 一致；
+  /// execute 与 execute_candidates 仅支持 [N, 4, ...] 排列的 xyxy，其他格式或排列方式在调用时报错；
+  /// execute_rotated 不读取该设置
+  pub fn with_box_encoding(mut self, encoding: BoxEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  pub fn build(self) -> Result<Nms, NmsError> {
+    if !self.dim.is_power_of_two() {
+      return Err(NmsError::InvalidConfig(format!(
+        "dim 必须为 2 的幂，当前为 {}",
+        self.dim
+      )));
+    }
+    if self.max_detections == 0 {
+      return Err(NmsError::InvalidConfig(
+        "max_detections 必须大于 0".to_string(),
+      ));
+    }
+    if let NmsMethod::Gaussian { sigma } = self.method
+      && sigma <= 0.0
+    {
+      return Err(NmsError::InvalidConfig(format!(
+        "高斯衰减的 sigma 必须大于 0，当前为 {}",
+        sigma
+      )));
+    }
+    Ok(Nms {
+      score_threshold: self.score_threshold,
+      iou_threshold: self.iou_threshold,
+      max_detections: self.max_detections,
+      class_agnostic: self.class_agnostic,
+      method: self.method,
+      dim: self.dim,
+      encoding: self.encoding,
+    })
+  }
+}
+
+pub struct Nms {
+  score_threshold: f32,
+  iou_threshold: f32,
+  max_detections: u32,
+  class_agnostic: bool,
+  method: NmsMethod,
+  dim: u32,
+  encoding: BoxEncoding,
+}
+
+impl Nms {
+  /// 对稠密的检测结果执行非极大值抑制
+  /// score: 分类得分，形状为 [N, H, W]
+  /// index: 类别索引，形状为 [N, H, W]
+  /// bbox: 边界框坐标，形状为 [N, 4, H, W]
+  /// 返回按得分降序排列的保留框，K 为 max_detections，source 为保留框在 H * W 中的位置
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    score: DataBuffer<R, F>,
+    index: DataBuffer<R, I>,
+    bbox: DataBuffer<R, F>,
+  ) -> Result<Candidates<R, F, I>, NmsError> {
+    check_xyxy_planar(&self.encoding).map_err(NmsError::InvalidConfig)?;
+    let (n, m) = candidate_shape(score.shape(), index.shape(), bbox.shape())
+      .map_err(NmsError::InvalidInputShape)?;
+
+    let count = vec![m as u32; n];
+    let count = DataBuffer::<R, u32>::from_slice(&count, &[n], client)?;
+
+    self.run(client, n, m, score, index, bbox, count, false)
+  }
+
+  /// 对稠密的旋转框检测结果执行非极大值抑制，使用旋转框交并比
+  /// score: 分类得分，形状为 [N, H, W]
+  /// index: 类别索引，形状为 [N, H, W]
+  /// rbox: 旋转框，形状为 [N, 5, H, W]，为 cx, cy, w, h, θ (弧度)，各轴单位需一致 (如像素)
+  /// 返回的候选列表中 bbox 形状为 [N, 5, K]，其余与 execute 一致
+  pub fn execute_rotated<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    score: DataBuffer<R, F>,
+    index: DataBuffer<R, I>,
+    rbox: DataBuffer<R, F>,
+  ) -> Result<Candidates<R, F, I>, NmsError> {
+    let n = score.shape().first().copied().unwrap_or(0);
+    let m = score.shape().iter().skip(1).product::<usize>();
+    let shape = rbox.shape();
+    if n == 0
+      || index.shape() != score.shape()
+      || shape.len() < 2
+      || shape[0] != n
+      || shape[1] != 5
+      || shape[2..].iter().product::<usize>() != m
+    {
+      return Err(NmsError::InvalidInputShape(
+        "输入形状不正确，预期 score/index 为 [N, H, W]，rbox 为 [N, 5, H, W]".to_string(),
+      ));
+    }
+
+    let count = vec![m as u32; n];
+    let count = DataBuffer::<R, u32>::from_slice(&count, &[n], client)?;
+
+    self.run(client, n, m, score, index, rbox, count, true)
+  }
+
+  /// 对压缩后的候选列表执行非极大值抑制，只考虑每张图像前 count[n] 个候选
+  /// 返回结果的 source 仍指向原始稠密输入中的位置
+  pub fn execute_candidates<
+    R: Runtime,
+    F: Float + CubeElement + CubeScalar,
+    I: Int + CubeElement,
+  >(
+    &self,
+    client: &ComputeClient<R>,
+    candidates: Candidates<R, F, I>,
+  ) -> Result<Candidates<R, F, I>, NmsError> {
+    let Candidates {
+      score,
+      index,
+      bbox,
+      source,
+      count,
+    } = candidates;
+    check_xyxy_planar(&self.encoding).map_err(NmsError::InvalidConfig)?;
+    let (n, m) = candidate_shape(score.shape(), index.shape(), bbox.shape())
+      .map_err(NmsError::InvalidInputShape)?;
+
+    let mut result = self.run(client, n, m, score, index, bbox, count, false)?;
+
+    // 将候选列表中的位置映射回稠密输入中的位置
+    let mapped: DataBuffer<R, u32> = DataBuffer::with_shape(result.source.shape(), client);
+    let total = n * self.max_detections as usize;
+    gather::launch::<u32, R>(
+      client,
+      CubeCount::Static(total.div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      source.into_tensor_arg(1),
+      result.source.into_tensor_arg(1),
+      result.count.into_tensor_arg(1),
+      mapped.into_tensor_arg(1),
+    )?;
+    result.source = mapped;
+
+    Ok(result)
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn run<R: Runtime, F: Float + CubeElement + CubeScalar, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    n: usize,
+    m: usize,
+    score: DataBuffer<R, F>,
+    index: DataBuffer<R, I>,
+    bbox: DataBuffer<R, F>,
+    count: DataBuffer<R, u32>,
+    rotated: bool,
+  ) -> Result<Candidates<R, F, I>, NmsError> {
+    let k = self.max_detections as usize;
+    let channels = if rotated { 5 } else { 4 };
+    let alive: DataBuffer<R, F> = DataBuffer::with_shape(&[n, m], client);
+    let out_score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, k], client);
+    let out_index: DataBuffer<R, I> = DataBuffer::with_shape(&[n, k], client);
+    let out_bbox: DataBuffer<R, F> = DataBuffer::with_shape(&[n, channels, k], client);
+    let out_source: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, k], client);
+    let out_count: DataBuffer<R, u32> = DataBuffer::with_shape(&[n], client);
+
+    let (decay, sigma) = match self.method {
+      NmsMethod::Hard => (Decay::Hard, 1.0),
+      NmsMethod::Linear => (Decay::Linear, 1.0),
+      NmsMethod::Gaussian { sigma } => (Decay::Gaussian, sigma),
+    };
+
+    nms::launch::<F, I, R>(
+      client,
+      CubeCount::Static(n as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      score.into_tensor_arg(1),
+      index.into_tensor_arg(1),
+      bbox.into_tensor_arg(1),
+      count.into_tensor_arg(1),
+      alive.into_tensor_arg(1),
+      out_score.into_tensor_arg(1),
+      out_index.into_tensor_arg(1),
+      out_bbox.into_tensor_arg(1),
+      out_source.into_tensor_arg(1),
+      out_count.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.score_threshold)),
+      ScalarArg::new(F::new(self.iou_threshold)),
+      ScalarArg::new(F::new(sigma)),
+      self.class_agnostic,
+      decay,
+      rotated,
+      self.dim as usize,
+    )?;
+
+    Ok(Candidates {
+      score: out_score,
+      index: out_index,
+      bbox: out_bbox,
+      source: out_source,
+      count: out_count,
+    })
+  }
+}
+
+/// 贪心非极大值抑制，每个 Cube 处理一张图像
+///
+/// 每一轮先在 Cube 内归约出剩余候选中得分最高者（得分相同时取位置靠前者），
+/// 将其写入输出，再由各线程并行抑制与其重叠的候选框，直到没有剩余候选或达到 K 个。
+/// 每个线程只读写自己负责的 alive 元素，因此只需同步共享内存。
+/// decay 为 Linear/Gaussian 时按 Soft-NMS 衰减重叠候选的得分，衰减到 score_threshold 以下才丢弃。
+///
+/// score/index: 输入 [N, M]，bbox: 输入 [N, 4, M]，均为紧凑布局，
+///   rotated 为 true 时 bbox 为 [N, 5, M] 的旋转框 (cx, cy, w, h, θ)，使用旋转框交并比
+/// count: 输入 [N]，每张图像只考虑前 count[n] 个位置
+/// alive: 临时缓冲 [N, M]，记录尚未被抑制的候选得分
+/// out_*: 输出 [N, K] / [N, 4, K] (旋转框为 [N, 5, K])，out_count: 输出 [N]
+#[cube(launch)]
+#[allow(clippy::too_many_arguments)]
+fn nms<F: Float + CubeScalar, I: Int>(
+  score: Tensor<F>,
+  index: Tensor<I>,
+  bbox: Tensor<F>,
+  count: Tensor<u32>,
+  alive: &mut Tensor<F>,
+  out_score: &mut Tensor<F>,
+  out_index: &mut Tensor<I>,
+  out_bbox: &mut Tensor<F>,
+  out_source: &mut Tensor<u32>,
+  out_count: &mut Tensor<u32>,
+  score_threshold: F,
+  iou_threshold: F,
+  sigma: F,
+  #[comptime] class_agnostic: bool,
+  #[comptime] decay: Decay,
+  #[comptime] rotated: bool,
+  #[comptime] block: usize,
+) {
+  let n_idx = CUBE_POS;
+  let unit = UNIT_POS as usize;
+  let dim = CUBE_DIM as usize;
+
+  let n_dim = score.shape(0);
+  let m = score.len() / n_dim;
+  let k_dim = out_score.len() / n_dim;
+
+  // 旋转框为 (cx, cy, w, h, θ) 五个通道
+  let channels = comptime!(if rotated { 5usize } else { 4usize });
+
+  let base = n_idx * m;
+  let bbox_base = n_idx * channels * m;
+  let out_base = n_idx * k_dim;
+  let out_bbox_base = n_idx * channels * k_dim;
+
+  let invalid = F::min_value();
+  let zero = F::new(comptime!(0.0));
+  let one = F::new(comptime!(1.0));
+
+  // 初始化: 无效位置和低于阈值的候选直接视为已抑制
+  let valid = count[n_idx] as usize;
+  let mut i = unit;
+  while i < m {
+    let s = score[base + i];
+    let mut v = invalid;
+    if i < valid && s >= score_threshold {
+      v = s;
+    }
+    alive[base + i] = v;
+    i += dim;
+  }
+
+  let mut best_score = SharedMemory::<F>::new(block);
+  let mut best_pos = SharedMemory::<u32>::new(block);
+
+  let mut kept = 0;
+  for _ in 0..k_dim {
+    // 线程内求局部最大值，按位置升序遍历，相同得分保留靠前者
+    let mut local_score = invalid;
+    let mut local_pos = m;
+    let mut i = unit;
+    while i < m {
+      let s = alive[base + i];
+      if s > local_score {
+        local_score = s;
+        local_pos = i;
+      }
+      i += dim;
+    }
+
+    best_score[unit] = local_score;
+    best_pos[unit] = u32::cast_from(local_pos);
+    sync_cube();
+
+    // Cube 内树形归约
+    let mut offset = dim / 2;
+    while offset > 0 {
+      if unit < offset {
+        let other_score = best_score[unit + offset];
+        let other_pos = best_pos[unit + offset];
+        let self_score = best_score[unit];
+        let self_pos = best_pos[unit];
+        if other_score > self_score || (other_score == self_score && other_pos < self_pos) {
+          best_score[unit] = other_score;
+          best_pos[unit] = other_pos;
+        }
+      }
+      sync_cube();
+      offset /= 2;
+    }
+
+    let top_score = best_score[0];
+    let top_pos = best_pos[0] as usize;
+    sync_cube();
+
+    // alive 中只有不低于阈值的得分或 invalid，最高者无效说明已没有剩余候选；
+    // top_score 来自共享内存，Cube 内所有线程一致地退出
+    if top_score <= invalid {
+      break;
+    }
+
+    let top_class = index[base + top_pos];
+    let top_xmin = bbox[bbox_base + top_pos];
+    let top_ymin = bbox[bbox_base + m + top_pos];
+    let top_xmax = bbox[bbox_base + 2 * m + top_pos];
+    let top_ymax = bbox[bbox_base + 3 * m + top_pos];
+    let mut top_theta = zero;
+    if comptime!(rotated) {
+      top_theta = bbox[bbox_base + 4 * m + top_pos];
+    }
+
+    if unit == 0 {
+      out_score[out_base + kept] = top_score;
+      out_index[out_base + kept] = top_class;
+      out_bbox[out_bbox_base + kept] = top_xmin;
+      out_bbox[out_bbox_base + k_dim + kept] = top_ymin;
+      out_bbox[out_bbox_base + 2 * k_dim + kept] = top_xmax;
+      out_bbox[out_bbox_base + 3 * k_dim + kept] = top_ymax;
+      if comptime!(rotated) {
+        out_bbox[out_bbox_base + 4 * k_dim + kept] = top_theta;
+      }
+      out_source[out_base + kept] = u32::cast_from(top_pos);
+    }
+
+    // 抑制与当前保留框重叠的候选
+    let mut i = unit;
+    while i < m {
+      if alive[base + i] > invalid {
+        let same_class = class_agnostic || index[base + i] == top_class;
+        if i == top_pos {
+          alive[base + i] = invalid;
+        } else if same_class {
+          let overlap = if comptime!(rotated) {
+            rotated_iou::<F>(
+              top_xmin,
+              top_ymin,
+              top_xmax,
+              top_ymax,
+              top_theta,
+              bbox[bbox_base + i],
+              bbox[bbox_base + m + i],
+              bbox[bbox_base + 2 * m + i],
+              bbox[bbox_base + 3 * m + i],
+              bbox[bbox_base + 4 * m + i],
+            )
+          } else {
+            iou::<F>(
+              top_xmin,
+              top_ymin,
+              top_xmax,
+              top_ymax,
+              bbox[bbox_base + i],
+              bbox[bbox_base + m + i],
+              bbox[bbox_base + 2 * m + i],
+              bbox[bbox_base + 3 * m + i],
+            )
+          };
+          let mut decayed = alive[base + i];
+          if comptime!(decay == Decay::Gaussian) {
+            decayed *= F::exp(-(overlap * overlap) / sigma);
+          } else if overlap > iou_threshold {
+            if comptime!(decay == Decay::Linear) {
+              decayed *= one - overlap;
+            } else {
+              decayed = invalid;
+            }
+          }
+          if decayed < score_threshold {
+            decayed = invalid;
+          }
+          alive[base + i] = decayed;
+        }
+      }
+      i += dim;
+    }
+
+    kept += 1;
+  }
+
+  // 填充剩余位置并写入有效数量
+  if unit == 0 {
+    for j in kept..k_dim {
+      out_score[out_base + j] = zero;
+      out_index[out_base + j] = I::cast_from(0);
+      for c in 0..channels {
+        out_bbox[out_bbox_base + c * k_dim + j] = zero;
+      }
+      out_source[out_base + j] = 0;
+    }
+    out_count[n_idx] = u32::cast_from(kept);
+  }
+}
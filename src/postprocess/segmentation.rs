@@ -0,0 +1,196 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/segmentation.rs - 语义分割的逐像素分类与着色
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use thiserror::Error;
+
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  kernel::resize_bilinear,
+  postprocess::detection::classify,
+};
+
+#[derive(Debug, Error)]
+pub enum SegmentationError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+pub struct SegmentationConfig {
+  resize: Option<(u32, u32)>,
+  dim: u32,
+}
+
+impl Default for SegmentationConfig {
+  fn default() -> Self {
+    Self {
+      resize: None,
+      dim: 256,
+    }
+  }
+}
+
+impl SegmentationConfig {
+  /// 在逐像素分类前先将 logits 双线性缩放到指定尺寸 (通常为原始图像尺寸)
+  pub fn with_resize(mut self, width: u32, height: u32) -> Self {
+    self.resize = Some((width, height));
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<Segmentation, SegmentationError> {
+    if let Some((width, height)) = self.resize
+      && (width == 0 || height == 0)
+    {
+      return Err(SegmentationError::InvalidConfig(
+        "缩放尺寸必须大于 0".to_string(),
+      ));
+    }
+    Ok(Segmentation {
+      resize: self.resize,
+      dim: self.dim,
+    })
+  }
+}
+
+/// 逐像素分类结果 (score, label)，形状均为 [N, H, W]
+pub type SegmentationResult<R, F, I> = (DataBuffer<R, F>, DataBuffer<R, I>);
+
+pub struct Segmentation {
+  resize: Option<(u32, u32)>,
+  dim: u32,
+}
+
+impl Segmentation {
+  /// 执行后处理操作
+  /// logits: 分割结果，形状为 [N, C, H, W]
+  /// 返回 (score, label)，形状均为 [N, H', W']，score 为最大的 logit，label 为对应的类别索引，
+  /// 配置了缩放时 H'/W' 为缩放后的尺寸，否则与输入一致
+  pub fn execute<R: Runtime, F: Float + CubeElement, I: Int + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    logits: DataBuffer<R, F>,
+  ) -> Result<SegmentationResult<R, F, I>, SegmentationError> {
+    let [n, c, h, w] = *logits.shape() else {
+      return Err(SegmentationError::InvalidInputShape(
+        "分割结果张量形状不正确，预期为 [N, C, H, W]".to_string(),
+      ));
+    };
+
+    let (logits, h, w) = match self.resize {
+      Some((width, height)) => {
+        let (width, height) = (width as usize, height as usize);
+        let resized: DataBuffer<R, F> = DataBuffer::with_shape(&[n, c, height, width], client);
+        let count = (n * c * height * width).div_ceil(self.dim as usize);
+        resize_bilinear::launch::<F, R>(
+          client,
+          CubeCount::Static(count as u32, 1, 1),
+          CubeDim::new_1d(self.dim),
+          logits.into_tensor_arg(1),
+          resized.into_tensor_arg(1),
+        )?;
+        (resized, height, width)
+      }
+      None => (logits, h, w),
+    };
+
+    let score: DataBuffer<R, F> = DataBuffer::with_shape(&[n, h, w], client);
+    let label: DataBuffer<R, I> = DataBuffer::with_shape(&[n, h, w], client);
+
+    let count = (n * h * w).div_ceil(self.dim as usize);
+    classify::launch::<F, I, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      logits.into_tensor_arg(1),
+      score.into_tensor_arg(1),
+      label.into_tensor_arg(1),
+      ScalarArg::new(0),
+    )?;
+
+    Ok((score, label))
+  }
+
+  /// 按调色板将类别索引转换为 RGB 图像
+  /// label: 类别索引，形状为 [N, H, W]
+  /// palette: 每个类别的 (r, g, b)，超出调色板范围的类别输出为 0
+  /// 返回形状为 [N, 3, H, W] 的 RGB 图像，数值类型与调色板一致
+  pub fn colorize<R: Runtime, I: Int + CubeElement, T: Numeric + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    label: DataBuffer<R, I>,
+    palette: &[[T; 3]],
+  ) -> Result<DataBuffer<R, T>, SegmentationError> {
+    let [n, h, w] = *label.shape() else {
+      return Err(SegmentationError::InvalidInputShape(
+        "类别索引张量形状不正确，预期为 [N, H, W]".to_string(),
+      ));
+    };
+    if palette.is_empty() {
+      return Err(SegmentationError::InvalidConfig(
+        "调色板不能为空".to_string(),
+      ));
+    }
+
+    let colors: Vec<T> = palette.iter().flatten().copied().collect();
+    let palette = DataBuffer::<R, T>::from_slice(&colors, &[colors.len() / 3, 3], client)?;
+
+    let output: DataBuffer<R, T> = DataBuffer::with_shape(&[n, 3, h, w], client);
+    let count = (n * h * w).div_ceil(self.dim as usize);
+    colorize::launch::<I, T, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      label.into_tensor_arg(1),
+      palette.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+    )?;
+
+    Ok(output)
+  }
+}
+
+/// 调色板查找
+/// label: 输入 [N, H, W]，palette: [P, 3]，output: 输出 [N, 3, H, W]
+#[cube(launch)]
+fn colorize<I: Int, T: Numeric>(label: &Tensor<I>, palette: &Tensor<T>, output: &mut Tensor<T>) {
+  let idx = ABSOLUTE_POS;
+  if idx < label.len() {
+    let n_dim = label.shape(0);
+    let hw = label.len() / n_dim;
+    let n_idx = idx / hw;
+    let rem = idx % hw;
+
+    let class_id = u32::cast_from(label[idx]) as usize;
+    let out = n_idx * 3 * hw + rem;
+    if class_id < palette.shape(0) {
+      output[out] = palette[class_id * 3];
+      output[out + hw] = palette[class_id * 3 + 1];
+      output[out + 2 * hw] = palette[class_id * 3 + 2];
+    } else {
+      output[out] = T::from_int(0);
+      output[out + hw] = T::from_int(0);
+      output[out + 2 * hw] = T::from_int(0);
+    }
+  }
+}
@@ -0,0 +1,507 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/text.rs - DBNet 文本检测后处理
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use std::collections::{BTreeMap, VecDeque};
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::data::{DataBuffer, DataBufferError};
+
+#[derive(Debug, Error)]
+pub enum DbNetError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 旋转矩形，angle 为宽边方向相对 x 轴的角度 (弧度)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotatedRect {
+  pub center: [f32; 2],
+  pub size: [f32; 2],
+  pub angle: f32,
+}
+
+impl RotatedRect {
+  /// 四个顶点，顺序为左上、右上、右下、左下
+  pub fn points(&self) -> [[f32; 2]; 4] {
+    let (sin, cos) = self.angle.sin_cos();
+    let (hw, hh) = (self.size[0] / 2.0, self.size[1] / 2.0);
+    let [cx, cy] = self.center;
+    let corner = |su: f32, sv: f32| {
+      [
+        cx + su * hw * cos - sv * hh * sin,
+        cy + su * hw * sin + sv * hh * cos,
+      ]
+    };
+    order_points([
+      corner(-1.0, -1.0),
+      corner(1.0, -1.0),
+      corner(1.0, 1.0),
+      corner(-1.0, 1.0),
+    ])
+  }
+
+  /// 较短边的长度
+  pub fn short_side(&self) -> f32 {
+    self.size[0].min(self.size[1])
+  }
+}
+
+/// 单个文本框
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextBox {
+  /// 四个顶点，顺序为左上、右上、右下、左下
+  pub points: [[f32; 2]; 4],
+  /// 外扩后的最小外接矩形
+  pub rect: RotatedRect,
+  /// 外扩前矩形内的平均概率
+  pub score: f32,
+}
+
+pub struct DbNetConfig {
+  threshold: f32,
+  box_threshold: f32,
+  unclip_ratio: f32,
+  min_size: f32,
+  max_candidates: usize,
+  shape: Option<(u32, u32)>,
+  dim: u32,
+}
+
+impl Default for DbNetConfig {
+  fn default() -> Self {
+    Self {
+      threshold: 0.3,
+      box_threshold: 0.7,
+      unclip_ratio: 1.5,
+      min_size: 3.0,
+      max_candidates: 1000,
+      shape: None,
+      dim: 256,
+    }
+  }
+}
+
+impl DbNetConfig {
+  /// 概率高于该阈值的像素视为文本
+  pub fn with_threshold(mut self, threshold: f32) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  /// 矩形内平均概率低于该阈值的文本框会被丢弃
+  pub fn with_box_threshold(mut self, box_threshold: f32) -> Self {
+    self.box_threshold = box_threshold;
+    self
+  }
+
+  /// 外扩距离为 面积 * unclip_ratio / 周长
+  pub fn with_unclip_ratio(mut self, unclip_ratio: f32) -> Self {
+    self.unclip_ratio = unclip_ratio;
+    self
+  }
+
+  /// 短边小于该值的文本框会被丢弃，外扩后的阈值为 min_size + 2
+  pub fn with_min_size(mut self, min_size: f32) -> Self {
+    self.min_size = min_size;
+    self
+  }
+
+  /// 每张图像最多处理的连通区域数量
+  pub fn with_max_candidates(mut self, max_candidates: usize) -> Self {
+    self.max_candidates = max_candidates;
+    self
+  }
+
+  /// 将坐标由概率图尺寸缩放到指定的图像尺寸，未设置时保持概率图的像素坐标
+  pub fn with_shape(mut self, width: u32, height: u32) -> Self {
+    self.shape = Some((width, height));
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<DbNet, DbNetError> {
+    if !(0.0..1.0).contains(&self.threshold) {
+      return Err(DbNetError::InvalidConfig(format!(
+        "threshold 必须在 [0, 1) 范围内，当前为 {}",
+        self.threshold
+      )));
+    }
+    if self.unclip_ratio < 0.0 {
+      return Err(DbNetError::InvalidConfig(
+        "unclip_ratio 不能为负数".to_string(),
+      ));
+    }
+    Ok(DbNet {
+      threshold: self.threshold,
+      box_threshold: self.box_threshold,
+      unclip_ratio: self.unclip_ratio,
+      min_size: self.min_size,
+      max_candidates: self.max_candidates,
+      shape: self.shape,
+      dim: self.dim,
+    })
+  }
+}
+
+/// DBNet 文本检测后处理
+///
+/// 二值化在设备上完成，连通区域提取、最小外接矩形与外扩依赖逐区域的串行遍历，
+/// 读回二值图与概率图后在主机上完成
+pub struct DbNet {
+  threshold: f32,
+  box_threshold: f32,
+  unclip_ratio: f32,
+  min_size: f32,
+  max_candidates: usize,
+  shape: Option<(u32, u32)>,
+  dim: u32,
+}
+
+impl DbNet {
+  /// 执行后处理操作
+  /// prob: 概率图，形状为 [N, 1, H, W] 或 [N, H, W]
+  /// 返回每张图像的文本框，按连通区域的扫描顺序排列
+  pub fn execute<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    prob: DataBuffer<R, F>,
+  ) -> Result<Vec<Vec<TextBox>>, DbNetError> {
+    let (n, h, w) = match *prob.shape() {
+      [n, 1, h, w] | [n, h, w] => (n, h, w),
+      _ => {
+        return Err(DbNetError::InvalidInputShape(
+          "概率图张量形状不正确，预期为 [N, 1, H, W] 或 [N, H, W]".to_string(),
+        ));
+      }
+    };
+
+    let mask = self.binarize(client, prob.clone())?.into_vec(client)?;
+    let prob = prob
+      .into_vec(client)?
+      .iter()
+      .map(|v| {
+        v.to_f32()
+          .ok_or_else(|| DataBufferError::InvalidData("无法将概率转换为 f32".to_string()))
+      })
+      .collect::<Result<Vec<f32>, _>>()?;
+
+    let (scale_x, scale_y) = match self.shape {
+      Some((width, height)) => (width as f32 / w as f32, height as f32 / h as f32),
+      None => (1.0, 1.0),
+    };
+
+    let hw = h * w;
+    Ok(
+      (0..n)
+        .map(|b| {
+          let prob = &prob[b * hw..(b + 1) * hw];
+          regions(&mask[b * hw..(b + 1) * hw], w, h)
+            .into_iter()
+            .take(self.max_candidates)
+            .filter_map(|points| self.text_box(&points, prob, w, h))
+            .map(|text| scale_text_box(text, scale_x, scale_y))
+            .collect()
+        })
+        .collect(),
+    )
+  }
+
+  /// 按阈值二值化概率图，返回形状为 [N, H, W] 的掩码，文本像素为 1
+  pub fn binarize<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    prob: DataBuffer<R, F>,
+  ) -> Result<DataBuffer<R, u32>, DbNetError> {
+    let (n, h, w) = match *prob.shape() {
+      [n, 1, h, w] | [n, h, w] => (n, h, w),
+      _ => {
+        return Err(DbNetError::InvalidInputShape(
+          "概率图张量形状不正确，预期为 [N, 1, H, W] 或 [N, H, W]".to_string(),
+        ));
+      }
+    };
+
+    let mask: DataBuffer<R, u32> = DataBuffer::with_shape(&[n, h, w], client);
+    binarize::launch::<F, R>(
+      client,
+      CubeCount::Static((n * h * w).div_ceil(self.dim as usize) as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      prob.into_tensor_arg(1),
+      mask.into_tensor_arg(1),
+      ScalarArg::new(F::new(self.threshold)),
+    )?;
+
+    Ok(mask)
+  }
+
+  /// 由单个连通区域生成文本框，不满足尺寸或得分要求时返回 None
+  fn text_box(&self, points: &[[f32; 2]], prob: &[f32], w: usize, h: usize) -> Option<TextBox> {
+    let rect = min_area_rect(points)?;
+    if rect.short_side() < self.min_size {
+      return None;
+    }
+
+    let score = mean_score(&rect, prob, w, h);
+    if score < self.box_threshold {
+      return None;
+    }
+
+    let rect = unclip(&rect, self.unclip_ratio);
+    if rect.short_side() < self.min_size + 2.0 {
+      return None;
+    }
+
+    Some(TextBox {
+      points: rect.points(),
+      rect,
+      score,
+    })
+  }
+}
+
+/// 按阈值二值化
+/// prob: 输入 [N, 1, H, W] 或 [N, H, W]，连续布局，mask: 输出 [N, H, W]
+#[cube(launch)]
+fn binarize<F: Float + CubeScalar>(prob: &Tensor<F>, mask: &mut Tensor<u32>, threshold: F) {
+  let idx = ABSOLUTE_POS;
+  if idx < mask.len() {
+    if prob[idx] > threshold {
+      mask[idx] = 1;
+    } else {
+      mask[idx] = 0;
+    }
+  }
+}
+
+/// 计算点集的最小面积外接矩形，点集为空时返回 None
+///
+/// 先求凸包，再以凸包的每条边为矩形一边，取面积最小者 (旋转卡壳)
+pub fn min_area_rect(points: &[[f32; 2]]) -> Option<RotatedRect> {
+  let hull = convex_hull(points);
+  let first = *hull.first()?;
+  if hull.len() == 1 {
+    return Some(RotatedRect {
+      center: first,
+      size: [0.0, 0.0],
+      angle: 0.0,
+    });
+  }
+
+  let mut best: Option<(f32, RotatedRect)> = None;
+  for i in 0..hull.len() {
+    let [ax, ay] = hull[i];
+    let [bx, by] = hull[(i + 1) % hull.len()];
+    let length = ((bx - ax).powi(2) + (by - ay).powi(2)).sqrt();
+    if length == 0.0 {
+      continue;
+    }
+    let (ux, uy) = ((bx - ax) / length, (by - ay) / length);
+
+    let (mut min_u, mut max_u, mut min_v, mut max_v) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+    for &[x, y] in &hull {
+      let u = x * ux + y * uy;
+      let v = -x * uy + y * ux;
+      min_u = min_u.min(u);
+      max_u = max_u.max(u);
+      min_v = min_v.min(v);
+      max_v = max_v.max(v);
+    }
+
+    let area = (max_u - min_u) * (max_v - min_v);
+    if best.as_ref().is_none_or(|(a, _)| area < *a) {
+      let (cu, cv) = ((min_u + max_u) / 2.0, (min_v + max_v) / 2.0);
+      let rect = RotatedRect {
+        center: [cu * ux - cv * uy, cu * uy + cv * ux],
+        size: [max_u - min_u, max_v - min_v],
+        angle: uy.atan2(ux),
+      };
+      best = Some((area, rect));
+    }
+  }
+  best.map(|(_, rect)| rect)
+}
+
+/// Andrew 单调链凸包，按逆时针 (y 轴向下时为顺时针) 返回，不含共线点
+fn convex_hull(points: &[[f32; 2]]) -> Vec<[f32; 2]> {
+  let mut points = points.to_vec();
+  points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+  points.dedup();
+  if points.len() < 3 {
+    return points;
+  }
+
+  let cross = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| {
+    (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+  };
+  let mut hull: Vec<[f32; 2]> = Vec::with_capacity(points.len() * 2);
+  for pass in 0..2 {
+    let start = hull.len();
+    let iter: Box<dyn Iterator<Item = &[f32; 2]>> = if pass == 0 {
+      Box::new(points.iter())
+    } else {
+      Box::new(points.iter().rev())
+    };
+    for &p in iter {
+      while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+        hull.pop();
+      }
+      hull.push(p);
+    }
+    hull.pop();
+  }
+  hull
+}
+
+/// 将四个顶点排列为左上、右上、右下、左下，与 DBNet 的 get_mini_boxes 一致
+fn order_points(mut points: [[f32; 2]; 4]) -> [[f32; 2]; 4] {
+  points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+  let (top_left, bottom_left) = if points[1][1] > points[0][1] {
+    (points[0], points[1])
+  } else {
+    (points[1], points[0])
+  };
+  let (top_right, bottom_right) = if points[3][1] > points[2][1] {
+    (points[2], points[3])
+  } else {
+    (points[3], points[2])
+  };
+  [top_left, top_right, bottom_right, bottom_left]
+}
+
+/// 提取 8 邻域连通区域，按扫描顺序返回每个区域每行最左与最右像素的坐标
+///
+/// 凸包只取决于每行的两端，因此无需保留区域内的全部像素
+fn regions(mask: &[u32], w: usize, h: usize) -> Vec<Vec<[f32; 2]>> {
+  let mut visited = vec![false; w * h];
+  let mut regions = Vec::new();
+  let mut queue = VecDeque::new();
+
+  for start in 0..w * h {
+    if mask[start] == 0 || visited[start] {
+      continue;
+    }
+    visited[start] = true;
+    queue.push_back(start);
+
+    // 每行的 (最左, 最右) 列
+    let mut extent: BTreeMap<usize, (usize, usize)> = BTreeMap::new();
+    while let Some(pos) = queue.pop_front() {
+      let (x, y) = (pos % w, pos / w);
+      let (left, right) = extent.entry(y).or_insert((x, x));
+      *left = (*left).min(x);
+      *right = (*right).max(x);
+
+      for ny in y.saturating_sub(1)..(y + 2).min(h) {
+        for nx in x.saturating_sub(1)..(x + 2).min(w) {
+          let next = ny * w + nx;
+          if mask[next] != 0 && !visited[next] {
+            visited[next] = true;
+            queue.push_back(next);
+          }
+        }
+      }
+    }
+
+    regions.push(
+      extent
+        .into_iter()
+        .flat_map(|(y, (left, right))| [[left as f32, y as f32], [right as f32, y as f32]])
+        .collect(),
+    );
+  }
+  regions
+}
+
+/// 计算像素中心落在矩形内 (含边界) 的平均概率
+fn mean_score(rect: &RotatedRect, prob: &[f32], w: usize, h: usize) -> f32 {
+  const EPSILON: f32 = 1e-3;
+
+  let points = rect.points();
+  let clamp = |v: f32, max: usize| (v.max(0.0) as usize).min(max - 1);
+  let xmin = clamp(
+    points.iter().map(|p| p[0]).fold(f32::MAX, f32::min).floor(),
+    w,
+  );
+  let xmax = clamp(
+    points.iter().map(|p| p[0]).fold(f32::MIN, f32::max).ceil(),
+    w,
+  );
+  let ymin = clamp(
+    points.iter().map(|p| p[1]).fold(f32::MAX, f32::min).floor(),
+    h,
+  );
+  let ymax = clamp(
+    points.iter().map(|p| p[1]).fold(f32::MIN, f32::max).ceil(),
+    h,
+  );
+
+  let (sin, cos) = rect.angle.sin_cos();
+  let (hw, hh) = (rect.size[0] / 2.0 + EPSILON, rect.size[1] / 2.0 + EPSILON);
+  let (mut sum, mut count) = (0.0, 0usize);
+  for y in ymin..=ymax {
+    for x in xmin..=xmax {
+      let (dx, dy) = (x as f32 - rect.center[0], y as f32 - rect.center[1]);
+      let u = dx * cos + dy * sin;
+      let v = -dx * sin + dy * cos;
+      if u.abs() <= hw && v.abs() <= hh {
+        sum += prob[y * w + x];
+        count += 1;
+      }
+    }
+  }
+  if count == 0 { 0.0 } else { sum / count as f32 }
+}
+
+/// 按 DBNet 的 unclip 外扩矩形
+///
+/// 多边形按距离 d = 面积 * ratio / 周长 外扩 (圆角连接) 后，其最小外接矩形即为宽高各加 2d 的矩形
+fn unclip(rect: &RotatedRect, ratio: f32) -> RotatedRect {
+  let [w, h] = rect.size;
+  let perimeter = 2.0 * (w + h);
+  let distance = if perimeter > 0.0 {
+    w * h * ratio / perimeter
+  } else {
+    0.0
+  };
+  RotatedRect {
+    size: [w + 2.0 * distance, h + 2.0 * distance],
+    ..*rect
+  }
+}
+
+/// 将文本框由概率图坐标缩放到图像坐标
+fn scale_text_box(text: TextBox, scale_x: f32, scale_y: f32) -> TextBox {
+  if scale_x == 1.0 && scale_y == 1.0 {
+    return text;
+  }
+  let points = text.points.map(|[x, y]| [x * scale_x, y * scale_y]);
+  // 非等比缩放会改变角度，重新求缩放后顶点的最小外接矩形
+  let rect = min_area_rect(&points).unwrap_or(text.rect);
+  TextBox {
+    points,
+    rect,
+    score: text.score,
+  }
+}
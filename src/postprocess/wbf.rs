@@ -0,0 +1,202 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/postprocess/wbf.rs - 加权框融合 (Weighted Box Fusion)
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use thiserror::Error;
+
+use crate::postprocess::{
+  detection::{Detection, DetectionSet},
+  geometry::box_iou,
+};
+
+#[derive(Debug, Error)]
+pub enum WbfError {
+  #[error("无效的输入: {0}")]
+  InvalidInput(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+}
+
+pub struct WbfConfig {
+  iou_threshold: f32,
+  skip_box_threshold: f32,
+  weights: Option<Vec<f32>>,
+}
+
+impl Default for WbfConfig {
+  fn default() -> Self {
+    Self {
+      iou_threshold: 0.55,
+      skip_box_threshold: 0.0,
+      weights: None,
+    }
+  }
+}
+
+impl WbfConfig {
+  /// 与融合框的交并比大于该阈值的检测框会被合并到同一个簇
+  pub fn with_iou_threshold(mut self, iou_threshold: f32) -> Self {
+    self.iou_threshold = iou_threshold;
+    self
+  }
+
+  /// 低于该得分的检测框不参与融合
+  pub fn with_skip_box_threshold(mut self, skip_box_threshold: f32) -> Self {
+    self.skip_box_threshold = skip_box_threshold;
+    self
+  }
+
+  /// 每个模型 (或每种增强) 的权重，长度需与输入的结果数量一致，默认均为 1
+  pub fn with_weights(mut self, weights: Vec<f32>) -> Self {
+    self.weights = Some(weights);
+    self
+  }
+
+  pub fn build(self) -> Result<Wbf, WbfError> {
+    if let Some(weights) = &self.weights
+      && (weights.is_empty() || weights.iter().any(|&w| w <= 0.0))
+    {
+      return Err(WbfError::InvalidConfig(
+        "权重不能为空且必须大于 0".to_string(),
+      ));
+    }
+    Ok(Wbf {
+      iou_threshold: self.iou_threshold,
+      skip_box_threshold: self.skip_box_threshold,
+      weights: self.weights,
+    })
+  }
+}
+
+/// 加权框融合，用于合并多个模型或测试时增强的检测结果
+///
+/// 每张图像的每个类别独立处理：检测框按加权得分降序依次加入与其交并比最大且超过阈值的簇，
+/// 簇的坐标为成员坐标按得分加权的平均值；融合后的得分为簇内平均加权得分，
+/// 再乘以 min(成员数, 模型数) / 权重之和，以降低只被少数模型检出的框的得分
+pub struct Wbf {
+  iou_threshold: f32,
+  skip_box_threshold: f32,
+  weights: Option<Vec<f32>>,
+}
+
+/// 融合中的簇
+struct Cluster {
+  /// 融合后的坐标
+  bbox: [f32; 4],
+  /// 成员坐标按得分加权的和
+  weighted: [f32; 4],
+  /// 成员加权得分之和
+  score: f32,
+  /// 成员数量
+  count: usize,
+}
+
+impl Wbf {
+  /// 执行融合
+  /// sets: 各模型的检测结果，坐标单位必须一致，按 batch_index 对应同一张图像
+  /// 返回融合后的检测结果，每张图像内按得分降序排列
+  pub fn execute(&self, sets: &[DetectionSet]) -> Result<DetectionSet, WbfError> {
+    let Some(first) = sets.first() else {
+      return Err(WbfError::InvalidInput("至少需要一组检测结果".to_string()));
+    };
+    if sets.iter().any(|s| s.unit() != first.unit()) {
+      return Err(WbfError::InvalidInput(
+        "各组检测结果的坐标单位必须一致".to_string(),
+      ));
+    }
+    let weights = match &self.weights {
+      Some(weights) if weights.len() != sets.len() => {
+        return Err(WbfError::InvalidInput(format!(
+          "权重数量 {} 与检测结果数量 {} 不一致",
+          weights.len(),
+          sets.len()
+        )));
+      }
+      Some(weights) => weights.clone(),
+      None => vec![1.0; sets.len()],
+    };
+    let weight_sum: f32 = weights.iter().sum();
+    let batch_size = sets.iter().map(|s| s.batch_size()).max().unwrap_or(0);
+
+    let mut fused = Vec::new();
+    for b in 0..batch_size {
+      let mut boxes: Vec<Detection> = sets
+        .iter()
+        .zip(weights.iter())
+        .flat_map(|(set, &weight)| {
+          set
+            .batch(b)
+            .iter()
+            .filter(|d| d.score >= self.skip_box_threshold)
+            .map(move |d| Detection {
+              score: d.score * weight,
+              ..*d
+            })
+        })
+        .collect();
+      boxes.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+      let mut classes: Vec<u32> = boxes.iter().map(|d| d.class_id).collect();
+      classes.sort_unstable();
+      classes.dedup();
+
+      let mut image = Vec::new();
+      for class_id in classes {
+        let clusters = self.cluster(boxes.iter().filter(|d| d.class_id == class_id));
+        image.extend(clusters.into_iter().map(|c| Detection {
+          bbox: c.bbox,
+          score: c.score / c.count as f32 * c.count.min(weights.len()) as f32 / weight_sum,
+          class_id,
+          batch_index: b,
+        }));
+      }
+      image.sort_by(|a, b| b.score.total_cmp(&a.score));
+      fused.extend(image);
+    }
+
+    Ok(DetectionSet::new(fused, batch_size, first.unit()))
+  }
+
+  /// 将同一类别、按得分降序排列的检测框聚类
+  fn cluster<'a>(&self, boxes: impl Iterator<Item = &'a Detection>) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for d in boxes {
+      let matched = clusters
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, box_iou(c.bbox, d.bbox)))
+        .filter(|&(_, overlap)| overlap > self.iou_threshold)
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)));
+
+      match matched {
+        Some((i, _)) => {
+          let c = &mut clusters[i];
+          for j in 0..4 {
+            c.weighted[j] += d.bbox[j] * d.score;
+          }
+          c.score += d.score;
+          c.count += 1;
+          if c.score > 0.0 {
+            c.bbox = c.weighted.map(|v| v / c.score);
+          }
+        }
+        None => clusters.push(Cluster {
+          bbox: d.bbox,
+          weighted: d.bbox.map(|v| v * d.score),
+          score: d.score,
+          count: 1,
+        }),
+      }
+    }
+    clusters
+  }
+}
@@ -0,0 +1,456 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/slicing.rs - 切片推理 (SAHI)：生成重叠的图块并合并各图块的检测结果
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::{CubeScalar, prelude::*};
+use thiserror::Error;
+
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  kernel::crop_bilinear,
+  postprocess::{
+    detection::{BoxEncoding, BoxUnit, Candidates, Detection, DetectionSet},
+    geometry::{box_area, box_intersection, box_iou},
+    letterbox::{BackProject, BackProjectConfig, Letterbox, LetterboxError},
+  },
+};
+
+#[derive(Debug, Error)]
+pub enum SlicingError {
+  #[error("无效的输入形状: {0}")]
+  InvalidInputShape(String),
+  #[error("无效的输入: {0}")]
+  InvalidInput(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("坐标映射错误: {0}")]
+  LetterboxError(#[from] LetterboxError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 合并跨图块重复检测的方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeMethod {
+  /// 贪心非极大值抑制，只保留得分最高的框
+  Nms,
+  /// 贪心非极大值合并，得分最高的框扩展为与其匹配的框的并集
+  Nmm,
+}
+
+/// 判断两个框是否为同一目标的重叠度量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMetric {
+  /// 交并比
+  Iou,
+  /// 交集与较小框面积之比，适合被图块边界截断的目标
+  Ios,
+}
+
+/// 原始图像中的一个图块
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+  /// 所属图像在输入批次中的索引
+  pub image: usize,
+  /// 图块在原始图像中的像素区域
+  pub x: u32,
+  pub y: u32,
+  pub width: u32,
+  pub height: u32,
+  pub source_width: u32,
+  pub source_height: u32,
+}
+
+impl Tile {
+  /// 图块缩放到网络输入 (input_width, input_height) 时对应的预处理参数，可用于 BackProject
+  pub fn letterbox(&self, input_width: u32, input_height: u32) -> Letterbox {
+    let scale_x = input_width as f32 / self.width as f32;
+    let scale_y = input_height as f32 / self.height as f32;
+    Letterbox {
+      source_width: self.source_width,
+      source_height: self.source_height,
+      scale_x,
+      scale_y,
+      pad_x: -(self.x as f32) * scale_x,
+      pad_y: -(self.y as f32) * scale_y,
+    }
+  }
+}
+
+pub struct SlicingConfig {
+  tile_width: u32,
+  tile_height: u32,
+  overlap_x: f32,
+  overlap_y: f32,
+  full_image: bool,
+  method: MergeMethod,
+  metric: MatchMetric,
+  match_threshold: f32,
+  class_agnostic: bool,
+  dim: u32,
+  encoding: BoxEncoding,
+}
+
+impl Default for SlicingConfig {
+  fn default() -> Self {
+    Self {
+      tile_width: 640,
+      tile_height: 640,
+      overlap_x: 0.2,
+      overlap_y: 0.2,
+      full_image: false,
+      method: MergeMethod::Nmm,
+      metric: MatchMetric::Ios,
+      match_threshold: 0.5,
+      class_agnostic: false,
+      dim: 256,
+      encoding: BoxEncoding::default(),
+    }
+  }
+}
+
+impl SlicingConfig {
+  /// 图块尺寸，即网络输入尺寸，应与 Yolo26Config::with_shape 一致
+  pub fn with_tile_size(mut self, width: u32, height: u32) -> Self {
+    self.tile_width = width;
+    self.tile_height = height;
+    self
+  }
+
+  /// 相邻图块重叠部分占图块尺寸的比例，取值范围为 [0, 1)
+  pub fn with_overlap(mut self, overlap_x: f32, overlap_y: f32) -> Self {
+    self.overlap_x = overlap_x;
+    self.overlap_y = overlap_y;
+    self
+  }
+
+  /// 为 true 时额外加入一个缩放到图块尺寸的整图，用于检测大目标
+  pub fn with_full_image(mut self, full_image: bool) -> Self {
+    self.full_image = full_image;
+    self
+  }
+
+  pub fn with_merge_method(mut self, method: MergeMethod) -> Self {
+    self.method = method;
+    self
+  }
+
+  /// 重叠度量大于该阈值的两个框视为同一目标
+  pub fn with_match(mut self, metric: MatchMetric, threshold: f32) -> Self {
+    self.metric = metric;
+    self.match_threshold = threshold;
+    self
+  }
+
+  /// 为 true 时不同类别的框之间也会合并
+  pub fn with_class_agnostic(mut self, class_agnostic: bool) -> Self {
+    self.class_agnostic = class_agnostic;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  /// 各图块边界框的编码方式，应与 Yolo26::box_encoding 一致，见 BackProjectConfig::with_box_encoding
+  pub fn with_box_encoding(mut self, encoding: BoxEncoding) -> Self {
+    self.encoding = encoding;
+    self
+  }
+
+  pub fn build(self) -> Result<Slicing, SlicingError> {
+    if self.tile_width == 0 || self.tile_height == 0 {
+      return Err(SlicingError::InvalidConfig(
+        "图块尺寸必须大于 0".to_string(),
+      ));
+    }
+    if !(0.0..1.0).contains(&self.overlap_x) || !(0.0..1.0).contains(&self.overlap_y) {
+      return Err(SlicingError::InvalidConfig(
+        "重叠比例必须位于 [0, 1) 内".to_string(),
+      ));
+    }
+    let back_project = BackProjectConfig::default()
+      .with_shape(self.tile_width, self.tile_height)
+      .with_dim(self.dim)
+      .with_box_encoding(self.encoding)
+      .build()?;
+    Ok(Slicing {
+      tile_width: self.tile_width,
+      tile_height: self.tile_height,
+      overlap_x: self.overlap_x,
+      overlap_y: self.overlap_y,
+      full_image: self.full_image,
+      method: self.method,
+      metric: self.metric,
+      match_threshold: self.match_threshold,
+      class_agnostic: self.class_agnostic,
+      dim: self.dim,
+      back_project,
+    })
+  }
+}
+
+/// 切片推理
+///
+/// 典型流程为 crop 生成图块批次、模型推理与 Yolo26::execute 解码、remap 映射回原图像素坐标、
+/// 读取为 DetectionSet 后由 merge 合并跨图块的重复检测
+pub struct Slicing {
+  tile_width: u32,
+  tile_height: u32,
+  overlap_x: f32,
+  overlap_y: f32,
+  full_image: bool,
+  method: MergeMethod,
+  metric: MatchMetric,
+  match_threshold: f32,
+  class_agnostic: bool,
+  dim: u32,
+  back_project: BackProject,
+}
+
+impl Slicing {
+  /// 为批次中每张尺寸为 (source_width, source_height) 的图像生成图块
+  ///
+  /// 图块按图像、行、列的顺序排列，每张图像的图块数量相同；
+  /// 最后一行与最后一列向内对齐到图像边界，图像小于图块时区域为整幅图像；
+  /// 图像宽或高为 0 时没有图块，避免 Tile::letterbox 得到无效的缩放
+  pub fn tiles(&self, batch_size: usize, source_width: u32, source_height: u32) -> Vec<Tile> {
+    if source_width == 0 || source_height == 0 {
+      return Vec::new();
+    }
+    let xs = starts(source_width, self.tile_width, self.overlap_x);
+    let ys = starts(source_height, self.tile_height, self.overlap_y);
+    let width = self.tile_width.min(source_width);
+    let height = self.tile_height.min(source_height);
+
+    let mut regions: Vec<[u32; 4]> = ys
+      .iter()
+      .flat_map(|&y| xs.iter().map(move |&x| [x, y, width, height]))
+      .collect();
+    if self.full_image && regions.len() > 1 {
+      regions.push([0, 0, source_width, source_height]);
+    }
+
+    (0..batch_size)
+      .flat_map(|image| {
+        regions.iter().map(move |&[x, y, width, height]| Tile {
+          image,
+          x,
+          y,
+          width,
+          height,
+          source_width,
+          source_height,
+        })
+      })
+      .collect()
+  }
+
+  /// 裁剪图块并缩放到图块尺寸
+  /// image: 输入图像 [N, C, H, W]
+  /// 返回图块批次 [T, C, tile_height, tile_width] 及对应的图块列表
+  pub fn crop<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    image: DataBuffer<R, F>,
+  ) -> Result<(DataBuffer<R, F>, Vec<Tile>), SlicingError> {
+    let [n, c, h, w] = *image.shape() else {
+      return Err(SlicingError::InvalidInputShape(
+        "图像张量形状不正确，预期为 [N, C, H, W]".to_string(),
+      ));
+    };
+    if n == 0 || h == 0 || w == 0 {
+      return Err(SlicingError::InvalidInputShape(
+        "图像张量不能为空".to_string(),
+      ));
+    }
+
+    let tiles = self.tiles(n, w as u32, h as u32);
+    let regions: Vec<u32> = tiles
+      .iter()
+      .flat_map(|t| [t.image as u32, t.x, t.y, t.width, t.height])
+      .collect();
+    let regions = DataBuffer::<R, u32>::from_slice(&regions, &[tiles.len(), 5], client)?;
+
+    let shape = [
+      tiles.len(),
+      c,
+      self.tile_height as usize,
+      self.tile_width as usize,
+    ];
+    let output: DataBuffer<R, F> = DataBuffer::with_shape(&shape, client);
+    let count = shape.iter().product::<usize>().div_ceil(self.dim as usize);
+    crop_bilinear::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      image.into_tensor_arg(1),
+      regions.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+    )?;
+
+    Ok((output, tiles))
+  }
+
+  /// 将各图块的边界框 (如 Yolo26::execute 的输出) 映射为原始图像的像素坐标
+  /// bbox: 按 with_box_encoding 编码，默认为归一化的 [T, 4, ...] xyxy，T 与图块数量一致
+  pub fn remap<R: Runtime, F: Float + CubeElement + CubeScalar>(
+    &self,
+    client: &ComputeClient<R>,
+    bbox: DataBuffer<R, F>,
+    tiles: &[Tile],
+  ) -> Result<DataBuffer<R, F>, SlicingError> {
+    self.check_batch(bbox.shape(), tiles)?;
+    Ok(
+      self
+        .back_project
+        .execute(client, bbox, &self.letterboxes(tiles))?,
+    )
+  }
+
+  /// 对候选列表 (如 NMS 的输出) 中的边界框执行映射，其余字段保持不变
+  pub fn remap_candidates<R: Runtime, F: Float + CubeElement + CubeScalar, I: CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    candidates: Candidates<R, F, I>,
+    tiles: &[Tile],
+  ) -> Result<Candidates<R, F, I>, SlicingError> {
+    self.check_batch(candidates.bbox.shape(), tiles)?;
+    Ok(
+      self
+        .back_project
+        .execute_candidates(client, candidates, &self.letterboxes(tiles))?,
+    )
+  }
+
+  fn check_batch(&self, shape: &[usize], tiles: &[Tile]) -> Result<(), SlicingError> {
+    if shape.first() != Some(&tiles.len()) {
+      return Err(SlicingError::InvalidInputShape(format!(
+        "边界框张量的批次大小应与图块数量 {} 一致",
+        tiles.len()
+      )));
+    }
+    Ok(())
+  }
+
+  /// 合并各图块的检测结果
+  /// detections: 已映射到原始图像像素坐标的检测结果，batch_index 为图块索引
+  /// 返回按原始图像分组的检测结果，每张图像内按得分降序排列
+  pub fn merge(
+    &self,
+    detections: &DetectionSet,
+    tiles: &[Tile],
+  ) -> Result<DetectionSet, SlicingError> {
+    if detections.unit() != BoxUnit::Pixel {
+      return Err(SlicingError::InvalidInput(
+        "检测结果应为映射后的原始图像像素坐标".to_string(),
+      ));
+    }
+    if detections.batch_size() > tiles.len() {
+      return Err(SlicingError::InvalidInput(format!(
+        "检测结果的批次大小 {} 超过图块数量 {}",
+        detections.batch_size(),
+        tiles.len()
+      )));
+    }
+
+    let batch_size = tiles.iter().map(|t| t.image + 1).max().unwrap_or(0);
+    let mut images = vec![Vec::new(); batch_size];
+    for d in detections.detections() {
+      let Some(tile) = tiles.get(d.batch_index) else {
+        return Err(SlicingError::InvalidInput(format!(
+          "检测结果的图块索引 {} 超过图块数量 {}",
+          d.batch_index,
+          tiles.len()
+        )));
+      };
+      let image = tile.image;
+      images[image].push(Detection {
+        batch_index: image,
+        ..*d
+      });
+    }
+
+    let merged = images
+      .into_iter()
+      .flat_map(|dets| self.merge_image(dets))
+      .collect();
+    Ok(DetectionSet::new(merged, batch_size, BoxUnit::Pixel))
+  }
+
+  fn letterboxes(&self, tiles: &[Tile]) -> Vec<Letterbox> {
+    tiles
+      .iter()
+      .map(|t| t.letterbox(self.tile_width, self.tile_height))
+      .collect()
+  }
+
+  /// 按得分降序贪心合并同一张图像的检测结果
+  fn merge_image(&self, mut dets: Vec<Detection>) -> Vec<Detection> {
+    dets.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut used = vec![false; dets.len()];
+    let mut merged = Vec::new();
+    for i in 0..dets.len() {
+      if used[i] {
+        continue;
+      }
+      let mut keep = dets[i];
+      for (d, used) in dets.iter().zip(used.iter_mut()).skip(i + 1) {
+        if *used || (!self.class_agnostic && d.class_id != keep.class_id) {
+          continue;
+        }
+        // 与得分最高的原始框比较，而不是与不断扩展的合并框比较
+        if self.overlap(dets[i].bbox, d.bbox) > self.match_threshold {
+          *used = true;
+          if self.method == MergeMethod::Nmm {
+            keep.bbox = [
+              keep.bbox[0].min(d.bbox[0]),
+              keep.bbox[1].min(d.bbox[1]),
+              keep.bbox[2].max(d.bbox[2]),
+              keep.bbox[3].max(d.bbox[3]),
+            ];
+          }
+        }
+      }
+      merged.push(keep);
+    }
+    merged
+  }
+
+  fn overlap(&self, a: [f32; 4], b: [f32; 4]) -> f32 {
+    match self.metric {
+      MatchMetric::Iou => box_iou(a, b),
+      MatchMetric::Ios => {
+        let smaller = box_area(a).min(box_area(b));
+        if smaller > 0.0 {
+          box_intersection(a, b) / smaller
+        } else {
+          0.0
+        }
+      }
+    }
+  }
+}
+
+/// 沿一个维度的图块起点，步长为 tile * (1 - overlap)，最后一个图块与图像边界对齐
+fn starts(size: u32, tile: u32, overlap: f32) -> Vec<u32> {
+  if size <= tile {
+    return vec![0];
+  }
+  let step = (tile - (tile as f32 * overlap).round() as u32).max(1);
+  let mut starts: Vec<u32> = (0..size - tile).step_by(step as usize).collect();
+  starts.push(size - tile);
+  starts
+}
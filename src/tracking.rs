@@ -0,0 +1,451 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/tracking.rs - 基于检测结果的多目标跟踪 (ByteTrack)
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use thiserror::Error;
+
+use crate::{
+  data::{DataBuffer, DataBufferError},
+  kernel::iou,
+  postprocess::detection::Detection,
+};
+
+mod assignment;
+mod kalman;
+pub use assignment::{Assignment, linear_assignment};
+pub use kalman::{KalmanFilter, KalmanState, bbox_to_xyah};
+
+#[derive(Debug, Error)]
+pub enum TrackingError {
+  #[error("无效的输入: {0}")]
+  InvalidInput(String),
+  #[error("无效的配置: {0}")]
+  InvalidConfig(String),
+  #[error("数据错误: {0}")]
+  DataError(#[from] DataBufferError),
+  #[error("运行时错误: {0}")]
+  LaunchError(#[from] LaunchError),
+}
+
+/// 轨迹状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackState {
+  /// 新建的轨迹，需要在下一帧再次匹配才会确认 (第一帧除外)
+  Tentative,
+  /// 当前帧匹配成功的轨迹
+  Tracked,
+  /// 暂时丢失的轨迹，在 max_lost 帧内仍可重新匹配
+  Lost,
+  /// 已移除的轨迹
+  Removed,
+}
+
+/// 单条轨迹
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Track {
+  /// 轨迹编号，从 1 开始递增
+  pub id: u64,
+  /// 卡尔曼滤波估计的边界框 (xmin, ymin, xmax, ymax)
+  pub bbox: [f32; 4],
+  /// 最近一次匹配的检测得分
+  pub score: f32,
+  /// 最近一次匹配的类别索引
+  pub class_id: u32,
+  pub state: TrackState,
+  /// 轨迹创建时的帧号
+  pub start_frame: u64,
+  /// 最近一次匹配的帧号
+  pub frame: u64,
+  /// 匹配成功的次数
+  pub hits: u32,
+}
+
+struct TrackEntry {
+  track: Track,
+  kalman: KalmanState,
+}
+
+pub struct ByteTrackConfig {
+  high_threshold: f32,
+  low_threshold: f32,
+  new_track_threshold: f32,
+  match_threshold: f32,
+  second_match_threshold: f32,
+  unconfirmed_match_threshold: f32,
+  max_lost: u64,
+  class_aware: bool,
+  kalman: KalmanFilter,
+  dim: u32,
+}
+
+impl Default for ByteTrackConfig {
+  fn default() -> Self {
+    Self {
+      high_threshold: 0.5,
+      low_threshold: 0.1,
+      new_track_threshold: 0.6,
+      match_threshold: 0.8,
+      second_match_threshold: 0.5,
+      unconfirmed_match_threshold: 0.7,
+      max_lost: 30,
+      class_aware: false,
+      kalman: KalmanFilter::default(),
+      dim: 256,
+    }
+  }
+}
+
+impl ByteTrackConfig {
+  /// 得分不低于 high 的检测参与第一阶段匹配，介于 low 与 high 之间的检测只用于第二阶段匹配已跟踪的轨迹
+  pub fn with_score_thresholds(mut self, high: f32, low: f32) -> Self {
+    self.high_threshold = high;
+    self.low_threshold = low;
+    self
+  }
+
+  /// 未匹配的高分检测中，得分不低于该阈值的才会创建新轨迹
+  pub fn with_new_track_threshold(mut self, new_track_threshold: f32) -> Self {
+    self.new_track_threshold = new_track_threshold;
+    self
+  }
+
+  /// 三次匹配的代价上限，代价为 1 - IoU，依次为高分检测、低分检测与未确认轨迹的匹配
+  pub fn with_match_thresholds(mut self, first: f32, second: f32, unconfirmed: f32) -> Self {
+    self.match_threshold = first;
+    self.second_match_threshold = second;
+    self.unconfirmed_match_threshold = unconfirmed;
+    self
+  }
+
+  /// 轨迹丢失超过该帧数后被移除
+  pub fn with_max_lost(mut self, max_lost: u64) -> Self {
+    self.max_lost = max_lost;
+    self
+  }
+
+  /// 为 true 时只在相同类别之间匹配
+  pub fn with_class_aware(mut self, class_aware: bool) -> Self {
+    self.class_aware = class_aware;
+    self
+  }
+
+  pub fn with_kalman(mut self, kalman: KalmanFilter) -> Self {
+    self.kalman = kalman;
+    self
+  }
+
+  pub fn with_dim(mut self, dim: u32) -> Self {
+    self.dim = dim;
+    self
+  }
+
+  pub fn build(self) -> Result<ByteTrack, TrackingError> {
+    if self.low_threshold > self.high_threshold {
+      return Err(TrackingError::InvalidConfig(format!(
+        "低分阈值 {} 不能大于高分阈值 {}",
+        self.low_threshold, self.high_threshold
+      )));
+    }
+    if self.dim == 0 {
+      return Err(TrackingError::InvalidConfig("dim 必须大于 0".to_string()));
+    }
+    Ok(ByteTrack {
+      high_threshold: self.high_threshold,
+      low_threshold: self.low_threshold,
+      new_track_threshold: self.new_track_threshold,
+      match_threshold: self.match_threshold,
+      second_match_threshold: self.second_match_threshold,
+      unconfirmed_match_threshold: self.unconfirmed_match_threshold,
+      max_lost: self.max_lost,
+      class_aware: self.class_aware,
+      kalman: self.kalman,
+      dim: self.dim,
+      tracks: Vec::new(),
+      frame: 0,
+      next_id: 1,
+    })
+  }
+}
+
+/// ByteTrack 多目标跟踪器，逐帧输入检测结果并维护轨迹
+///
+/// 每一帧先用卡尔曼滤波预测已有轨迹的位置，然后依次进行三次匹配：
+/// 已跟踪与丢失的轨迹匹配高分检测；剩余的已跟踪轨迹匹配低分检测，仍未匹配的标记为丢失；
+/// 未确认的轨迹匹配剩余的高分检测，未匹配的直接移除。剩余的高分检测创建新轨迹。
+/// 匹配代价为 1 - IoU，在 GPU 上计算交并比矩阵，使用匈牙利算法求解分配。
+pub struct ByteTrack {
+  high_threshold: f32,
+  low_threshold: f32,
+  new_track_threshold: f32,
+  match_threshold: f32,
+  second_match_threshold: f32,
+  unconfirmed_match_threshold: f32,
+  max_lost: u64,
+  class_aware: bool,
+  kalman: KalmanFilter,
+  dim: u32,
+  tracks: Vec<TrackEntry>,
+  frame: u64,
+  next_id: u64,
+}
+
+impl ByteTrack {
+  /// 处理一帧的检测结果 (如 DetectionSet::batch 的输出)，返回当前帧已确认且匹配成功的轨迹
+  pub fn update<R: Runtime>(
+    &mut self,
+    client: &ComputeClient<R>,
+    detections: &[Detection],
+  ) -> Result<Vec<Track>, TrackingError> {
+    self.frame += 1;
+    let frame = self.frame;
+
+    let high: Vec<usize> = (0..detections.len())
+      .filter(|&i| detections[i].score >= self.high_threshold)
+      .collect();
+    let low: Vec<usize> = (0..detections.len())
+      .filter(|&i| {
+        detections[i].score > self.low_threshold && detections[i].score < self.high_threshold
+      })
+      .collect();
+
+    // 预测已有轨迹的位置，丢失的轨迹不再估计高度的变化
+    let mut pool = Vec::new();
+    let mut unconfirmed = Vec::new();
+    for (i, entry) in self.tracks.iter_mut().enumerate() {
+      match entry.track.state {
+        TrackState::Tentative => unconfirmed.push(i),
+        TrackState::Tracked | TrackState::Lost => pool.push(i),
+        TrackState::Removed => continue,
+      }
+      if entry.track.state != TrackState::Tracked {
+        entry.kalman.mean[7] = 0.0;
+      }
+      self.kalman.predict(&mut entry.kalman);
+      entry.track.bbox = entry.kalman.bbox();
+    }
+
+    // 第一阶段: 已跟踪与丢失的轨迹匹配高分检测
+    let first = self.associate(client, &pool, detections, &high, self.match_threshold)?;
+    for &(t, d) in &first.matches {
+      self.update_track(pool[t], &detections[high[d]], frame);
+    }
+
+    // 第二阶段: 剩余的已跟踪轨迹匹配低分检测
+    let remain: Vec<usize> = first
+      .unmatched_rows
+      .iter()
+      .map(|&t| pool[t])
+      .filter(|&t| self.tracks[t].track.state == TrackState::Tracked)
+      .collect();
+    let second = self.associate(
+      client,
+      &remain,
+      detections,
+      &low,
+      self.second_match_threshold,
+    )?;
+    for &(t, d) in &second.matches {
+      self.update_track(remain[t], &detections[low[d]], frame);
+    }
+    for &t in &second.unmatched_rows {
+      self.tracks[remain[t]].track.state = TrackState::Lost;
+    }
+
+    // 第三阶段: 未确认的轨迹匹配剩余的高分检测
+    let high: Vec<usize> = first.unmatched_cols.iter().map(|&d| high[d]).collect();
+    let third = self.associate(
+      client,
+      &unconfirmed,
+      detections,
+      &high,
+      self.unconfirmed_match_threshold,
+    )?;
+    for &(t, d) in &third.matches {
+      self.update_track(unconfirmed[t], &detections[high[d]], frame);
+    }
+    for &t in &third.unmatched_rows {
+      self.tracks[unconfirmed[t]].track.state = TrackState::Removed;
+    }
+
+    // 剩余的高分检测创建新轨迹，第一帧的轨迹直接确认
+    for &d in &third.unmatched_cols {
+      let detection = &detections[high[d]];
+      if detection.score < self.new_track_threshold {
+        continue;
+      }
+      let kalman = self.kalman.initiate(bbox_to_xyah(detection.bbox));
+      let state = if frame == 1 {
+        TrackState::Tracked
+      } else {
+        TrackState::Tentative
+      };
+      self.tracks.push(TrackEntry {
+        track: Track {
+          id: self.next_id,
+          bbox: kalman.bbox(),
+          score: detection.score,
+          class_id: detection.class_id,
+          state,
+          start_frame: frame,
+          frame,
+          hits: 1,
+        },
+        kalman,
+      });
+      self.next_id += 1;
+    }
+
+    // 移除丢失过久的轨迹
+    for entry in &mut self.tracks {
+      if entry.track.state == TrackState::Lost && frame - entry.track.frame > self.max_lost {
+        entry.track.state = TrackState::Removed;
+      }
+    }
+    self
+      .tracks
+      .retain(|entry| entry.track.state != TrackState::Removed);
+
+    Ok(
+      self
+        .tracks
+        .iter()
+        .filter(|entry| entry.track.state == TrackState::Tracked)
+        .map(|entry| entry.track)
+        .collect(),
+    )
+  }
+
+  /// 当前维护的所有轨迹，包括未确认与丢失的轨迹
+  pub fn tracks(&self) -> Vec<Track> {
+    self.tracks.iter().map(|entry| entry.track).collect()
+  }
+
+  /// 已处理的帧数
+  pub fn frame(&self) -> u64 {
+    self.frame
+  }
+
+  /// 清空所有轨迹，帧号与轨迹编号重新开始计数
+  pub fn reset(&mut self) {
+    self.tracks.clear();
+    self.frame = 0;
+    self.next_id = 1;
+  }
+
+  /// 在 GPU 上计算两组边界框两两之间的交并比
+  /// a: 形状为 [4, A]，b: 形状为 [4, B]，均为 xmin, ymin, xmax, ymax
+  /// 返回形状为 [A, B] 的交并比矩阵
+  pub fn iou_matrix<R: Runtime, F: Float + CubeElement>(
+    &self,
+    client: &ComputeClient<R>,
+    a: DataBuffer<R, F>,
+    b: DataBuffer<R, F>,
+  ) -> Result<DataBuffer<R, F>, TrackingError> {
+    let (&[4, a_dim], &[4, b_dim]) = (a.shape(), b.shape()) else {
+      return Err(TrackingError::InvalidInput(
+        "边界框张量形状不正确，预期为 [4, A] 与 [4, B]".to_string(),
+      ));
+    };
+
+    let output: DataBuffer<R, F> = DataBuffer::with_shape(&[a_dim, b_dim], client);
+    let count = (a_dim * b_dim).div_ceil(self.dim as usize);
+    iou_matrix::launch::<F, R>(
+      client,
+      CubeCount::Static(count as u32, 1, 1),
+      CubeDim::new_1d(self.dim),
+      a.into_tensor_arg(1),
+      b.into_tensor_arg(1),
+      output.into_tensor_arg(1),
+    )?;
+
+    Ok(output)
+  }
+
+  /// 使用 1 - IoU 作为代价匹配轨迹与检测，代价大于 limit 的配对不会被匹配
+  /// tracks/candidates 分别为轨迹在 self.tracks 中与检测在 detections 中的位置
+  fn associate<R: Runtime>(
+    &self,
+    client: &ComputeClient<R>,
+    tracks: &[usize],
+    detections: &[Detection],
+    candidates: &[usize],
+    limit: f32,
+  ) -> Result<Assignment, TrackingError> {
+    let (rows, cols) = (tracks.len(), candidates.len());
+    if rows == 0 || cols == 0 {
+      return linear_assignment(&[], rows, cols, limit);
+    }
+
+    let planar = |boxes: Vec<[f32; 4]>| -> Vec<f32> {
+      (0..4)
+        .flat_map(|c| boxes.iter().map(move |b| b[c]))
+        .collect()
+    };
+    let a = planar(tracks.iter().map(|&t| self.tracks[t].track.bbox).collect());
+    let b = planar(candidates.iter().map(|&d| detections[d].bbox).collect());
+    let a = DataBuffer::<R, f32>::from_slice(&a, &[4, rows], client)?;
+    let b = DataBuffer::<R, f32>::from_slice(&b, &[4, cols], client)?;
+    let overlap = self.iou_matrix(client, a, b)?.into_vec(client)?;
+
+    let cost: Vec<f32> = overlap
+      .iter()
+      .enumerate()
+      .map(|(i, v)| {
+        let class_id = self.tracks[tracks[i / cols]].track.class_id;
+        if self.class_aware && class_id != detections[candidates[i % cols]].class_id {
+          f32::INFINITY
+        } else {
+          1.0 - v
+        }
+      })
+      .collect();
+    linear_assignment(&cost, rows, cols, limit)
+  }
+
+  /// 使用匹配的检测校正轨迹，丢失的轨迹重新激活
+  fn update_track(&mut self, index: usize, detection: &Detection, frame: u64) {
+    let entry = &mut self.tracks[index];
+    self
+      .kalman
+      .update(&mut entry.kalman, bbox_to_xyah(detection.bbox));
+    entry.track.bbox = entry.kalman.bbox();
+    entry.track.score = detection.score;
+    entry.track.class_id = detection.class_id;
+    entry.track.state = TrackState::Tracked;
+    entry.track.frame = frame;
+    entry.track.hits += 1;
+  }
+}
+
+/// 两组边界框两两之间的交并比
+/// a: [4, A]，b: [4, B]，output: [A, B]
+#[cube(launch)]
+fn iou_matrix<F: Float>(a: &Tensor<F>, b: &Tensor<F>, output: &mut Tensor<F>) {
+  let idx = ABSOLUTE_POS;
+  if idx < output.len() {
+    let a_dim = a.shape(1);
+    let b_dim = b.shape(1);
+    let i = idx / b_dim;
+    let j = idx % b_dim;
+    output[idx] = iou::<F>(
+      a[i],
+      a[a_dim + i],
+      a[2 * a_dim + i],
+      a[3 * a_dim + i],
+      b[j],
+      b[b_dim + j],
+      b[2 * b_dim + j],
+      b[3 * b_dim + j],
+    );
+  }
+}
@@ -0,0 +1,146 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/tracking/assignment.rs - 基于匈牙利算法的线性分配
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use crate::tracking::TrackingError;
+
+/// 线性分配的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Assignment {
+  /// 匹配的 (行, 列)，按行升序排列
+  pub matches: Vec<(usize, usize)>,
+  /// 未匹配的行，升序排列
+  pub unmatched_rows: Vec<usize>,
+  /// 未匹配的列，升序排列
+  pub unmatched_cols: Vec<usize>,
+}
+
+/// 求解代价矩阵上的最小代价匹配，代价大于 limit 的配对不会被匹配
+///
+/// cost: 行优先的代价矩阵，形状为 [rows, cols]
+///
+/// 与 lapjv 的 cost_limit 相同，将矩阵扩展为 (rows + cols) 阶方阵，
+/// 不匹配的行与列各自以 limit / 2 的代价分配给虚拟节点，因此只有代价不超过 limit 的配对才会被选中
+pub fn linear_assignment(
+  cost: &[f32],
+  rows: usize,
+  cols: usize,
+  limit: f32,
+) -> Result<Assignment, TrackingError> {
+  if cost.len() != rows * cols {
+    return Err(TrackingError::InvalidInput(format!(
+      "代价矩阵大小 {} 与形状 [{}, {}] 不一致",
+      cost.len(),
+      rows,
+      cols
+    )));
+  }
+  if rows == 0 || cols == 0 {
+    return Ok(Assignment {
+      matches: Vec::new(),
+      unmatched_rows: (0..rows).collect(),
+      unmatched_cols: (0..cols).collect(),
+    });
+  }
+
+  let size = rows + cols;
+  let dummy = limit as f64 / 2.0;
+  let mut extended = vec![0.0f64; size * size];
+  for i in 0..size {
+    for j in 0..size {
+      extended[i * size + j] = match (i < rows, j < cols) {
+        (true, true) => (cost[i * cols + j] as f64).min(limit as f64 + 1.0),
+        (false, false) => 0.0,
+        _ => dummy,
+      };
+    }
+  }
+
+  let assigned = hungarian(&extended, size);
+  let mut result = Assignment::default();
+  let mut col_matched = vec![false; cols];
+  for (i, &j) in assigned.iter().enumerate().take(rows) {
+    if j < cols && cost[i * cols + j] <= limit {
+      result.matches.push((i, j));
+      col_matched[j] = true;
+    } else {
+      result.unmatched_rows.push(i);
+    }
+  }
+  result.unmatched_cols = (0..cols).filter(|&j| !col_matched[j]).collect();
+  Ok(result)
+}
+
+/// 匈牙利算法 (Kuhn-Munkres，基于势能的 O(n³) 实现)，求 n 阶方阵的最小代价完美匹配
+/// 返回每一行分配到的列
+fn hungarian(cost: &[f64], n: usize) -> Vec<usize> {
+  // 行列均从 1 开始编号，0 号列作为增广路径的起点
+  let mut u = vec![0.0f64; n + 1];
+  let mut v = vec![0.0f64; n + 1];
+  let mut owner = vec![0usize; n + 1];
+  let mut way = vec![0usize; n + 1];
+
+  for i in 1..=n {
+    owner[0] = i;
+    let mut j0 = 0;
+    let mut min_value = vec![f64::INFINITY; n + 1];
+    let mut used = vec![false; n + 1];
+    loop {
+      used[j0] = true;
+      let i0 = owner[j0];
+      let mut delta = f64::INFINITY;
+      let mut j1 = 0;
+      for j in 1..=n {
+        if !used[j] {
+          let reduced = cost[(i0 - 1) * n + (j - 1)] - u[i0] - v[j];
+          if reduced < min_value[j] {
+            min_value[j] = reduced;
+            way[j] = j0;
+          }
+          if min_value[j] < delta {
+            delta = min_value[j];
+            j1 = j;
+          }
+        }
+      }
+      for j in 0..=n {
+        if used[j] {
+          u[owner[j]] += delta;
+          v[j] -= delta;
+        } else {
+          min_value[j] -= delta;
+        }
+      }
+      j0 = j1;
+      if owner[j0] == 0 {
+        break;
+      }
+    }
+    // 沿增广路径翻转匹配
+    loop {
+      let j1 = way[j0];
+      owner[j0] = owner[j1];
+      j0 = j1;
+      if j0 == 0 {
+        break;
+      }
+    }
+  }
+
+  let mut assigned = vec![0; n];
+  for j in 1..=n {
+    if owner[j] != 0 {
+      assigned[owner[j] - 1] = j - 1;
+    }
+  }
+  assigned
+}
@@ -0,0 +1,184 @@
+// 该文件是 Shanan CV 项目的一部分。
+// src/tracking/kalman.rs - 跟踪使用的卡尔曼滤波运动模型
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+/// 卡尔曼滤波的状态
+///
+/// 状态向量为 (cx, cy, a, h, vx, vy, va, vh)，其中 a = w / h 为宽高比，后四项为对应的速度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KalmanState {
+  pub mean: [f32; 8],
+  pub covariance: [[f32; 8]; 8],
+}
+
+impl KalmanState {
+  /// 当前状态对应的边界框 (xmin, ymin, xmax, ymax)
+  pub fn bbox(&self) -> [f32; 4] {
+    let [cx, cy, a, h, ..] = self.mean;
+    let w = a * h;
+    [cx - w / 2.0, cy - h / 2.0, cx + w / 2.0, cy + h / 2.0]
+  }
+}
+
+/// 将边界框 (xmin, ymin, xmax, ymax) 转换为观测 (cx, cy, a, h)
+pub fn bbox_to_xyah(bbox: [f32; 4]) -> [f32; 4] {
+  let w = bbox[2] - bbox[0];
+  let h = bbox[3] - bbox[1];
+  let a = if h > 0.0 { w / h } else { 0.0 };
+  [bbox[0] + w / 2.0, bbox[1] + h / 2.0, a, h]
+}
+
+/// 匀速模型的卡尔曼滤波，与 SORT/DeepSORT/ByteTrack 使用的模型一致
+///
+/// 过程噪声与观测噪声的标准差与目标高度成正比，宽高比使用固定的小噪声
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KalmanFilter {
+  std_position: f32,
+  std_velocity: f32,
+}
+
+impl Default for KalmanFilter {
+  fn default() -> Self {
+    Self {
+      std_position: 1.0 / 20.0,
+      std_velocity: 1.0 / 160.0,
+    }
+  }
+}
+
+impl KalmanFilter {
+  /// std_position/std_velocity 为相对于目标高度的位置与速度噪声标准差
+  pub fn new(std_position: f32, std_velocity: f32) -> Self {
+    Self {
+      std_position,
+      std_velocity,
+    }
+  }
+
+  /// 由第一次观测 (cx, cy, a, h) 初始化状态，速度为 0
+  pub fn initiate(&self, measurement: [f32; 4]) -> KalmanState {
+    let h = measurement[3];
+    let p = 2.0 * self.std_position * h;
+    let v = 10.0 * self.std_velocity * h;
+    let std = [p, p, 1e-2, p, v, v, 1e-5, v];
+
+    let mut mean = [0.0; 8];
+    mean[..4].copy_from_slice(&measurement);
+    let mut covariance = [[0.0; 8]; 8];
+    for (i, s) in std.iter().enumerate() {
+      covariance[i][i] = s * s;
+    }
+    KalmanState { mean, covariance }
+  }
+
+  /// 预测下一帧的状态
+  pub fn predict(&self, state: &mut KalmanState) {
+    let h = state.mean[3];
+    let p = self.std_position * h;
+    let v = self.std_velocity * h;
+    let std = [p, p, 1e-2, p, v, v, 1e-5, v];
+
+    // x' = F x，F 为单位阵加上位置对速度的一阶项
+    for i in 0..4 {
+      state.mean[i] += state.mean[i + 4];
+    }
+
+    // P' = F P F^T + Q
+    let mut fp = state.covariance;
+    for (row, velocity) in fp.iter_mut().zip(state.covariance[4..].iter()) {
+      for (p, v) in row.iter_mut().zip(velocity.iter()) {
+        *p += v;
+      }
+    }
+    let mut covariance = fp;
+    for row in covariance.iter_mut() {
+      for j in 0..4 {
+        row[j] += row[j + 4];
+      }
+    }
+    for (i, s) in std.iter().enumerate() {
+      covariance[i][i] += s * s;
+    }
+    state.covariance = covariance;
+  }
+
+  /// 使用观测 (cx, cy, a, h) 校正状态
+  pub fn update(&self, state: &mut KalmanState, measurement: [f32; 4]) {
+    let h = state.mean[3];
+    let p = self.std_position * h;
+    let std = [p, p, 1e-1, p];
+
+    // 投影到观测空间: S = H P H^T + R
+    let mut projected: [[f32; 4]; 4] =
+      std::array::from_fn(|i| std::array::from_fn(|j| state.covariance[i][j]));
+    for (i, s) in std.iter().enumerate() {
+      projected[i][i] += s * s;
+    }
+    let Some(inverse) = invert4(projected) else {
+      return;
+    };
+
+    // K = P H^T S^-1，形状为 8 x 4
+    let mut gain = [[0.0; 4]; 8];
+    for (i, row) in gain.iter_mut().enumerate() {
+      for (j, g) in row.iter_mut().enumerate() {
+        *g = (0..4).map(|k| state.covariance[i][k] * inverse[k][j]).sum();
+      }
+    }
+
+    let innovation: [f32; 4] = std::array::from_fn(|i| measurement[i] - state.mean[i]);
+    for (i, row) in gain.iter().enumerate() {
+      state.mean[i] += (0..4).map(|k| row[k] * innovation[k]).sum::<f32>();
+    }
+
+    // P' = P - K S K^T，其中 S K^T = H P
+    let covariance = state.covariance;
+    for (i, row) in state.covariance.iter_mut().enumerate() {
+      for (j, p) in row.iter_mut().enumerate() {
+        *p -= (0..4).map(|k| gain[i][k] * covariance[k][j]).sum::<f32>();
+      }
+    }
+  }
+}
+
+/// 使用带列主元的高斯消元求 4 x 4 矩阵的逆，矩阵奇异时返回 None
+fn invert4(mut m: [[f32; 4]; 4]) -> Option<[[f32; 4]; 4]> {
+  let mut inverse = [[0.0; 4]; 4];
+  for (i, row) in inverse.iter_mut().enumerate() {
+    row[i] = 1.0;
+  }
+
+  for col in 0..4 {
+    let pivot = (col..4).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+    if m[pivot][col] == 0.0 {
+      return None;
+    }
+    m.swap(col, pivot);
+    inverse.swap(col, pivot);
+
+    let scale = 1.0 / m[col][col];
+    for j in 0..4 {
+      m[col][j] *= scale;
+      inverse[col][j] *= scale;
+    }
+    for row in 0..4 {
+      if row != col {
+        let factor = m[row][col];
+        for j in 0..4 {
+          m[row][j] -= factor * m[col][j];
+          inverse[row][j] -= factor * inverse[col][j];
+        }
+      }
+    }
+  }
+  Some(inverse)
+}
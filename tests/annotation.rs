@@ -0,0 +1,210 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/annotation.rs - COCO JSON 与 YOLO txt 导入导出测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use shanan_cv::{
+  annotation::{ClassNames, CocoDataset, CocoImage, YoloLabel, format_yolo, parse_yolo},
+  eval::{EvaluatorConfig, GroundTruth},
+  postprocess::detection::{BoxUnit, Detection, DetectionSet},
+};
+
+const COCO: &str = r#"{
+  "info": {"description": "test"},
+  "images": [
+    {"id": 7, "file_name": "a.jpg", "width": 200, "height": 100},
+    {"id": 9, "file_name": "b.jpg", "width": 400, "height": 400}
+  ],
+  "categories": [
+    {"id": 18, "name": "dog", "supercategory": "animal"},
+    {"id": 1, "name": "person"}
+  ],
+  "annotations": [
+    {"id": 1, "image_id": 7, "category_id": 18, "bbox": [10, 20, 50, 40], "area": 1800, "iscrowd": 0},
+    {"id": 2, "image_id": 7, "category_id": 1, "bbox": [100, 0, 100, 100], "iscrowd": 1},
+    {"id": 3, "image_id": 9, "category_id": 1, "bbox": [0.5, 1.5, 20, 30]}
+  ]
+}"#;
+
+fn assert_bbox(a: [f32; 4], b: [f32; 4]) {
+  assert!(
+    a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-4),
+    "{:?} != {:?}",
+    a,
+    b
+  );
+}
+
+#[test]
+fn test_class_names() {
+  let names = ClassNames::from_lines("person\n  car \n\nbicycle\n");
+  assert_eq!(names.len(), 3);
+  assert_eq!(names.name(1), Some("car"));
+  assert_eq!(names.class_id("bicycle"), Some(2));
+  assert_eq!(names.name(3), None);
+  assert_eq!(names.to_lines(), "person\ncar\nbicycle\n");
+}
+
+#[test]
+fn test_coco_dataset() {
+  let dataset = CocoDataset::parse(COCO).unwrap();
+  assert_eq!(dataset.images.len(), 2);
+  assert_eq!(dataset.annotations.len(), 3);
+
+  // 类别按编号排序后依次对应类别索引
+  assert_eq!(dataset.class_names().names(), &["person", "dog"]);
+  assert_eq!(dataset.class_id(18), Some(1));
+  assert_eq!(dataset.category_id(0), Some(1));
+  assert_eq!(dataset.annotations[1].area, 10000.0);
+
+  let gts = dataset.ground_truths(7, BoxUnit::Pixel).unwrap();
+  assert_eq!(gts.len(), 2);
+  assert_bbox(gts[0].bbox, [10.0, 20.0, 60.0, 60.0]);
+  assert_eq!(gts[0].class_id, 1);
+  assert!(!gts[0].crowd);
+  assert!(gts[1].crowd);
+
+  let gts = dataset.ground_truths(7, BoxUnit::Normalized).unwrap();
+  assert_bbox(gts[0].bbox, [0.05, 0.2, 0.3, 0.6]);
+  assert!(dataset.ground_truths(8, BoxUnit::Pixel).is_err());
+
+  // 写出后重新读取保持一致
+  let reparsed = CocoDataset::parse(&dataset.to_json()).unwrap();
+  assert_eq!(reparsed, dataset);
+
+  // 由类别名称表构造并添加归一化坐标的标注
+  let mut created = CocoDataset::new(&ClassNames::new(vec!["cat".to_string()]));
+  let image = CocoImage {
+    id: 1,
+    file_name: "c.jpg".to_string(),
+    width: 100,
+    height: 50,
+  };
+  created
+    .add_image(
+      image.clone(),
+      &[GroundTruth::new([0.1, 0.2, 0.5, 0.6], 0)],
+      BoxUnit::Normalized,
+    )
+    .unwrap();
+  assert_eq!(created.categories[0].id, 1);
+  assert_bbox(created.annotations[0].bbox, [10.0, 10.0, 40.0, 20.0]);
+  assert!((created.annotations[0].area - 800.0).abs() < 1e-3);
+  assert!(
+    created
+      .add_image(image.clone(), &[], BoxUnit::Pixel)
+      .is_err()
+  );
+  let other = CocoImage { id: 2, ..image };
+  assert!(
+    created
+      .add_image(other, &[GroundTruth::new([0.0; 4], 3)], BoxUnit::Pixel)
+      .is_err()
+  );
+  assert_eq!(created.annotations.len(), 1);
+
+  assert!(CocoDataset::parse("{}").is_err());
+  assert!(CocoDataset::parse("[").is_err());
+
+  // 尺寸为 0 的图像只能输出像素坐标，超出 u32 的尺寸视为解析错误
+  let zero = COCO.replace(r#""width": 200"#, r#""width": 0"#);
+  let dataset = CocoDataset::parse(&zero).unwrap();
+  assert!(dataset.ground_truths(7, BoxUnit::Normalized).is_err());
+  assert_eq!(dataset.ground_truths(7, BoxUnit::Pixel).unwrap().len(), 2);
+  let huge = COCO.replace(r#""width": 200"#, r#""width": 4294967296"#);
+  assert!(CocoDataset::parse(&huge).is_err());
+}
+
+#[test]
+fn test_coco_results() {
+  let dataset = CocoDataset::parse(COCO).unwrap();
+  let set = DetectionSet::new(
+    vec![
+      Detection {
+        bbox: [0.05, 0.2, 0.3, 0.6],
+        score: 0.9,
+        class_id: 1,
+        batch_index: 0,
+      },
+      Detection {
+        bbox: [0.0, 0.0, 0.1, 0.1],
+        score: 0.4,
+        class_id: 0,
+        batch_index: 1,
+      },
+    ],
+    2,
+    BoxUnit::Normalized,
+  );
+
+  let json = dataset.results_to_json(&set, &[7, 9]).unwrap();
+  assert!(json.contains(r#""category_id":18"#));
+  assert!(json.contains(r#""score":0.9"#));
+
+  let parsed = dataset
+    .parse_results(&json, &[7, 9], BoxUnit::Pixel)
+    .unwrap();
+  assert_eq!(parsed.batch_size(), 2);
+  assert_eq!(parsed.unit(), BoxUnit::Pixel);
+  let first = parsed.batch(0)[0];
+  assert_bbox(first.bbox, [10.0, 20.0, 60.0, 60.0]);
+  assert_eq!(first.class_id, 1);
+  assert!((first.score - 0.9).abs() < 1e-6);
+  assert_bbox(parsed.batch(1)[0].bbox, [0.0, 0.0, 40.0, 40.0]);
+
+  assert!(dataset.results_to_json(&set, &[7]).is_err());
+  assert!(dataset.parse_results(&json, &[7], BoxUnit::Pixel).is_err());
+
+  // 读取的标注与检测结果可直接用于评估
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  evaluator.add_image(
+    parsed.batch(0),
+    &dataset.ground_truths(7, BoxUnit::Pixel).unwrap(),
+  );
+  let result = evaluator.evaluate();
+  assert!((result.per_class()[1].ap - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_yolo_labels() {
+  let text = "0 0.5 0.5 0.2 0.4\n\n3 0.25 0.75 0.1 0.1 0.87\n";
+  let labels = parse_yolo(text).unwrap();
+  assert_eq!(labels.len(), 2);
+  assert_eq!(labels[0].class_id, 0);
+  assert_bbox(labels[0].bbox, [0.4, 0.3, 0.6, 0.7]);
+  assert_eq!(labels[0].score, None);
+  assert_eq!(labels[1].score, Some(0.87));
+
+  // 像素坐标与归一化坐标
+  let detection = labels[0].to_detection(2, BoxUnit::Pixel, 640, 480);
+  assert_bbox(detection.bbox, [256.0, 144.0, 384.0, 336.0]);
+  assert_eq!(detection.score, 1.0);
+  assert_eq!(detection.batch_index, 2);
+  let gt = labels[1].to_ground_truth(BoxUnit::Normalized, 640, 480);
+  assert_bbox(gt.bbox, [0.2, 0.7, 0.3, 0.8]);
+
+  let label = YoloLabel::from_detection(&detection, BoxUnit::Pixel, 640, 480).unwrap();
+  assert_bbox(label.bbox, labels[0].bbox);
+  assert_eq!(label.score, Some(1.0));
+  assert!(YoloLabel::from_detection(&detection, BoxUnit::Pixel, 0, 480).is_err());
+  assert!(YoloLabel::from_detection(&detection, BoxUnit::Normalized, 0, 0).is_ok());
+
+  let written = format_yolo(&labels);
+  assert_eq!(
+    written,
+    "0 0.500000 0.500000 0.200000 0.400000\n3 0.250000 0.750000 0.100000 0.100000 0.870000\n"
+  );
+  assert_eq!(parse_yolo(&written).unwrap().len(), 2);
+
+  assert!(parse_yolo("0 0.5 0.5 0.2").is_err());
+  assert!(parse_yolo("a 0.5 0.5 0.2 0.2").is_err());
+  assert!(parse_yolo("0 0.5 x 0.2 0.2").is_err());
+}
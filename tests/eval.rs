@@ -0,0 +1,196 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/eval.rs - 目标检测评估测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use shanan_cv::{
+  eval::{AreaRange, EvaluatorConfig, GroundTruth},
+  postprocess::detection::{BoxUnit, Detection, DetectionSet},
+};
+
+fn detection(bbox: [f32; 4], score: f32, class_id: u32) -> Detection {
+  Detection {
+    bbox,
+    score,
+    class_id,
+    batch_index: 0,
+  }
+}
+
+fn assert_close(a: f32, b: f32) {
+  assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+}
+
+#[test]
+fn test_eval_perfect() {
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  let gts = [
+    GroundTruth::new([0.0, 0.0, 50.0, 50.0], 0),
+    GroundTruth::new([100.0, 100.0, 300.0, 280.0], 1),
+  ];
+  let dets: Vec<Detection> = gts
+    .iter()
+    .map(|g| detection(g.bbox, 0.9, g.class_id))
+    .collect();
+  evaluator.add_image(&dets, &gts);
+  evaluator.add_image(&dets[..1], &gts[..1]);
+  assert_eq!(evaluator.len(), 2);
+
+  let result = evaluator.evaluate();
+  assert_eq!(result.classes(), &[0, 1]);
+  assert_close(result.map(), 1.0);
+  assert_close(result.map50(), 1.0);
+  assert_close(result.map75(), 1.0);
+  assert_close(result.map_area(AreaRange::Small), -1.0);
+  assert_close(result.map_area(AreaRange::Medium), 1.0);
+  assert_close(result.map_area(AreaRange::Large), 1.0);
+}
+
+#[test]
+fn test_eval_pr_curve() {
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  let gts = [
+    GroundTruth::new([0.0, 0.0, 10.0, 10.0], 0),
+    GroundTruth::new([20.0, 20.0, 30.0, 30.0], 0),
+  ];
+  // 得分顺序为 TP、FP、TP: 召回率 0.5/0.5/1.0，精度 1/0.5/0.667
+  let dets = [
+    detection([0.0, 0.0, 10.0, 10.0], 0.9, 0),
+    detection([50.0, 50.0, 60.0, 60.0], 0.8, 0),
+    detection([20.0, 20.0, 30.0, 30.0], 0.7, 0),
+  ];
+  evaluator.add_image(&dets, &gts);
+  let result = evaluator.evaluate();
+
+  // 召回率 [0, 0.5] 的 51 个点精度为 1，(0.5, 1] 的 50 个点为 2/3
+  let ap = (51.0 + 50.0 * 2.0 / 3.0) / 101.0;
+  assert_close(result.map(), ap);
+  assert_close(result.map50(), ap);
+  assert_close(result.recall(0, 0.5).unwrap(), 1.0);
+
+  let curve = result.pr_curve(0, 0.5).unwrap();
+  assert_eq!(curve.recall.len(), 101);
+  assert_close(curve.precision[50], 1.0);
+  assert_close(curve.precision[51], 2.0 / 3.0);
+  assert_close(curve.score[0], 0.9);
+  assert_close(curve.score[100], 0.7);
+  assert!(result.pr_curve(1, 0.5).is_none());
+  assert!(result.pr_curve(0, 0.3).is_none());
+
+  // 每个类别最多保留一个检测时召回率为 0.5
+  let mut evaluator = EvaluatorConfig::default()
+    .with_max_detections(1)
+    .build()
+    .unwrap();
+  evaluator.add_image(&dets, &gts);
+  let result = evaluator.evaluate();
+  assert_close(result.recall(0, 0.5).unwrap(), 0.5);
+  assert_close(result.map(), 51.0 / 101.0);
+}
+
+#[test]
+fn test_eval_iou_thresholds() {
+  // 交并比为 0.72，只在 0.5 到 0.7 的 5 个阈值下匹配
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  evaluator.add_image(
+    &[detection([0.0, 0.0, 10.0, 7.2], 0.8, 0)],
+    &[GroundTruth::new([0.0, 0.0, 10.0, 10.0], 0)],
+  );
+  let result = evaluator.evaluate();
+  assert_close(result.map(), 0.5);
+  assert_close(result.map50(), 1.0);
+  assert_close(result.map75(), 0.0);
+
+  let mut evaluator = EvaluatorConfig::default()
+    .with_iou_thresholds(vec![0.75])
+    .build()
+    .unwrap();
+  evaluator.add_image(
+    &[detection([0.0, 0.0, 10.0, 7.2], 0.8, 0)],
+    &[GroundTruth::new([0.0, 0.0, 10.0, 10.0], 0)],
+  );
+  let result = evaluator.evaluate();
+  assert_close(result.map(), 0.0);
+  assert_close(result.map50(), -1.0);
+
+  assert!(
+    EvaluatorConfig::default()
+      .with_iou_thresholds(vec![])
+      .build()
+      .is_err()
+  );
+}
+
+#[test]
+fn test_eval_area_and_classes() {
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  let gts = [
+    GroundTruth::new([0.0, 0.0, 20.0, 20.0], 0),
+    GroundTruth::new([100.0, 100.0, 300.0, 300.0], 0),
+    GroundTruth::new([400.0, 400.0, 450.0, 450.0], 1),
+  ];
+  // 小目标检出，大目标漏检，类别 1 只有错误的检测
+  let dets = [
+    detection([0.0, 0.0, 20.0, 20.0], 0.9, 0),
+    detection([0.0, 400.0, 50.0, 450.0], 0.6, 1),
+  ];
+  evaluator.add_image(&dets, &gts);
+  let result = evaluator.evaluate();
+
+  assert_close(result.map_area(AreaRange::Small), 1.0);
+  assert_close(result.map_area(AreaRange::Medium), 0.0);
+  assert_close(result.map_area(AreaRange::Large), 0.0);
+
+  let per_class = result.per_class();
+  assert_eq!(per_class.len(), 2);
+  assert_eq!(per_class[0].class_id, 0);
+  assert_close(per_class[0].ap, 51.0 / 101.0);
+  assert_close(per_class[0].ap50, 51.0 / 101.0);
+  assert_close(per_class[1].ap, 0.0);
+  assert_close(result.map(), 51.0 / 101.0 / 2.0);
+}
+
+#[test]
+fn test_eval_crowd() {
+  // 落在人群区域内的检测被忽略，不计为错误
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  let gts = [
+    GroundTruth::new([0.0, 0.0, 10.0, 10.0], 0),
+    GroundTruth {
+      bbox: [50.0, 50.0, 100.0, 100.0],
+      class_id: 0,
+      crowd: true,
+    },
+  ];
+  let dets = [
+    detection([60.0, 60.0, 70.0, 70.0], 0.95, 0),
+    detection([70.0, 70.0, 80.0, 80.0], 0.9, 0),
+    detection([0.0, 0.0, 10.0, 10.0], 0.8, 0),
+  ];
+  evaluator.add_image(&dets, &gts);
+  assert_close(evaluator.evaluate().map(), 1.0);
+
+  // 批次输入
+  let mut evaluator = EvaluatorConfig::default().build().unwrap();
+  let set = DetectionSet::new(
+    vec![Detection {
+      batch_index: 1,
+      ..dets[2]
+    }],
+    2,
+    BoxUnit::Pixel,
+  );
+  assert!(evaluator.add_set(&set, &[gts[..1].to_vec()]).is_err());
+  evaluator
+    .add_set(&set, &[Vec::new(), gts[..1].to_vec()])
+    .unwrap();
+  assert_close(evaluator.evaluate().map(), 1.0);
+}
@@ -0,0 +1,244 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_candidate.rs - 候选框筛选与 Top-K 选择测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{
+    candidate::CandidateConfig,
+    detection::{BoxEncoding, BoxFormat, BoxLayout, BoxUnit},
+    nms::NmsConfig,
+  },
+};
+
+const N: usize = 2;
+const H: usize = 20;
+const W: usize = 20;
+const K: usize = 50;
+const THRESHOLD: f32 = 0.5;
+
+#[test]
+fn test_candidate_box_encoding() {
+  // 单位与是否裁剪不影响压缩与 NMS，其他格式或排列方式在压缩构建时拒绝
+  let pixel = BoxEncoding {
+    unit: BoxUnit::Pixel,
+    clamp: false,
+    ..BoxEncoding::default()
+  };
+  assert!(
+    CandidateConfig::default()
+      .with_box_encoding(pixel)
+      .build()
+      .is_ok()
+  );
+  assert!(
+    NmsConfig::default()
+      .with_box_encoding(pixel)
+      .build()
+      .is_ok()
+  );
+
+  for encoding in [
+    BoxEncoding {
+      format: BoxFormat::Cxcywh,
+      ..BoxEncoding::default()
+    },
+    BoxEncoding {
+      layout: BoxLayout::Interleaved,
+      ..BoxEncoding::default()
+    },
+  ] {
+    assert!(
+      CandidateConfig::default()
+        .with_box_encoding(encoding)
+        .build()
+        .is_err()
+    );
+    // 旋转框 NMS 不读取该设置，因此只在 execute/execute_candidates 时拒绝
+    assert!(
+      NmsConfig::default()
+        .with_box_encoding(encoding)
+        .build()
+        .is_ok()
+    );
+  }
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_candidate_cpu() {
+  test_postprocess_candidate::<cubecl::cpu::CpuRuntime>(true, THRESHOLD);
+  test_postprocess_candidate::<cubecl::cpu::CpuRuntime>(false, THRESHOLD);
+  test_postprocess_candidate::<cubecl::cpu::CpuRuntime>(true, f32::NEG_INFINITY);
+  test_postprocess_candidate::<cubecl::cpu::CpuRuntime>(true, -1e30);
+  test_postprocess_candidate_nms::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_candidate_wgpu() {
+  test_postprocess_candidate::<cubecl::wgpu::WgpuRuntime>(true, THRESHOLD);
+  test_postprocess_candidate::<cubecl::wgpu::WgpuRuntime>(false, THRESHOLD);
+  test_postprocess_candidate::<cubecl::wgpu::WgpuRuntime>(true, f32::NEG_INFINITY);
+  test_postprocess_candidate::<cubecl::wgpu::WgpuRuntime>(true, -1e30);
+  test_postprocess_candidate_nms::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn random_inputs() -> (Vec<f32>, Vec<u32>, Vec<f32>) {
+  let hw = H * W;
+  let score: Vec<f32> = (0..N * hw).map(|_| rand::random::<f32>()).collect();
+  let index: Vec<u32> = (0..N * hw).map(|_| rand::random::<u32>() % 4).collect();
+  let mut bbox = vec![0.0f32; N * 4 * hw];
+  for n in 0..N {
+    for i in 0..hw {
+      let cx = rand::random::<f32>();
+      let cy = rand::random::<f32>();
+      let s = 0.05 + 0.2 * rand::random::<f32>();
+      bbox[n * 4 * hw + i] = cx - s;
+      bbox[n * 4 * hw + hw + i] = cy - s;
+      bbox[n * 4 * hw + 2 * hw + i] = cx + s;
+      bbox[n * 4 * hw + 3 * hw + i] = cy + s;
+    }
+  }
+  (score, index, bbox)
+}
+
+/// threshold 为 -inf 或极小值时全部位置都满足阈值，仍应保留得分最高的 K 个
+fn test_postprocess_candidate<R: Runtime>(top_k: bool, threshold: f32) {
+  let hw = H * W;
+  let (score, index, bbox) = random_inputs();
+
+  let client = R::client(&R::Device::default());
+  let filter = CandidateConfig::default()
+    .with_score_threshold(threshold)
+    .with_max_candidates(K as u32)
+    .with_top_k(top_k)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let score_buf = DataBuffer::<R, f32>::from_slice(&score, &[N, H, W], &client).unwrap();
+  let index_buf = DataBuffer::<R, u32>::from_slice(&index, &[N, H, W], &client).unwrap();
+  let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox, &[N, 4, H, W], &client).unwrap();
+
+  let result = filter
+    .execute(&client, score_buf, index_buf, bbox_buf)
+    .unwrap();
+  assert_eq!(result.score.shape(), &[N, K]);
+  assert_eq!(result.bbox.shape(), &[N, 4, K]);
+
+  let count_cubecl = result.count.into_vec(&client).unwrap();
+  let score_cubecl = result.score.into_vec(&client).unwrap();
+  let index_cubecl = result.index.into_vec(&client).unwrap();
+  let bbox_cubecl = result.bbox.into_vec(&client).unwrap();
+  let source_cubecl = result.source.into_vec(&client).unwrap();
+
+  for n in 0..N {
+    let image_score = &score[n * hw..(n + 1) * hw];
+    let mut expected: Vec<usize> = (0..hw).filter(|&i| image_score[i] >= threshold).collect();
+    if top_k {
+      expected.sort_by(|&a, &b| image_score[b].partial_cmp(&image_score[a]).unwrap());
+    }
+    expected.truncate(K);
+    expected.sort();
+
+    let count = count_cubecl[n] as usize;
+    assert_eq!(count, expected.len(), "第 {} 张图像候选数量不匹配", n);
+
+    // 每个候选的内容应与其 source 指向的位置一致
+    for k in 0..count {
+      let src = source_cubecl[n * K + k] as usize;
+      assert_eq!(score_cubecl[n * K + k], image_score[src]);
+      assert_eq!(index_cubecl[n * K + k], index[n * hw + src]);
+      for c in 0..4 {
+        assert_eq!(
+          bbox_cubecl[n * 4 * K + c * K + k],
+          bbox[n * 4 * hw + c * hw + src]
+        );
+      }
+    }
+
+    let mut selected: Vec<usize> = source_cubecl[n * K..n * K + count]
+      .iter()
+      .map(|&s| s as usize)
+      .collect();
+    if !top_k {
+      // 仅按阈值筛选时应保持输入中的顺序
+      assert!(selected.windows(2).all(|w| w[0] < w[1]));
+    }
+    selected.sort();
+    assert_eq!(selected, expected, "第 {} 张图像候选集合不匹配", n);
+
+    for k in count..K {
+      assert_eq!(score_cubecl[n * K + k], 0.0);
+    }
+  }
+}
+
+fn test_postprocess_candidate_nms<R: Runtime>() {
+  // 先压缩再做 NMS，结果应与直接对稠密结果做 NMS 一致
+  let (score, index, bbox) = random_inputs();
+
+  let client = R::client(&R::Device::default());
+  let filter = CandidateConfig::default()
+    .with_score_threshold(THRESHOLD)
+    .with_max_candidates((H * W) as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let nms = NmsConfig::default()
+    .with_score_threshold(THRESHOLD)
+    .with_iou_threshold(0.5)
+    .with_max_detections(K as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let upload = || {
+    (
+      DataBuffer::<R, f32>::from_slice(&score, &[N, H, W], &client).unwrap(),
+      DataBuffer::<R, u32>::from_slice(&index, &[N, H, W], &client).unwrap(),
+      DataBuffer::<R, f32>::from_slice(&bbox, &[N, 4, H, W], &client).unwrap(),
+    )
+  };
+
+  let (s, i, b) = upload();
+  let dense = nms.execute(&client, s, i, b).unwrap();
+
+  let (s, i, b) = upload();
+  let candidates = filter.execute(&client, s, i, b).unwrap();
+  let compact = nms.execute_candidates(&client, candidates).unwrap();
+
+  assert_eq!(
+    dense.count.into_vec(&client).unwrap(),
+    compact.count.into_vec(&client).unwrap()
+  );
+  assert_eq!(
+    dense.source.into_vec(&client).unwrap(),
+    compact.source.into_vec(&client).unwrap()
+  );
+  assert_eq!(
+    dense.bbox.into_vec(&client).unwrap(),
+    compact.bbox.into_vec(&client).unwrap()
+  );
+  // 非 xyxy 的编码在轴对齐 NMS 中报错
+  let nms = NmsConfig::default()
+    .with_box_encoding(BoxEncoding {
+      format: BoxFormat::Xywh,
+      ..BoxEncoding::default()
+    })
+    .build()
+    .unwrap();
+  let (s, i, b) = upload();
+  assert!(nms.execute(&client, s, i, b).is_err());
+}
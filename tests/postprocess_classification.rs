@@ -0,0 +1,116 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_classification.rs - 图像分类 softmax 与 Top-K 测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{data::DataBuffer, postprocess::classification::ClassificationConfig};
+
+const N: usize = 3;
+const C: usize = 1000;
+const K: usize = 5;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_classification_cpu() {
+  test_postprocess_classification::<cubecl::cpu::CpuRuntime>(true);
+  test_postprocess_classification::<cubecl::cpu::CpuRuntime>(false);
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_classification_wgpu() {
+  test_postprocess_classification::<cubecl::wgpu::WgpuRuntime>(true);
+  test_postprocess_classification::<cubecl::wgpu::WgpuRuntime>(false);
+}
+
+fn test_postprocess_classification<R: Runtime>(softmax: bool) {
+  // 较大的 logits 用于检验 softmax 的数值稳定性
+  let mut logits: Vec<f32> = (0..N * C)
+    .map(|_| rand::random::<f32>() * 200.0 - 100.0)
+    .collect();
+  logits[C + 7] = 1000.0;
+  // 相同得分时类别索引小者在前
+  logits[2 * C + 3] = 150.0;
+  logits[2 * C + 9] = 150.0;
+
+  let client = R::client(&R::Device::default());
+  let classification = ClassificationConfig::default()
+    .with_top_k(K as u32)
+    .with_softmax(softmax)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let probs = classification
+    .softmax(
+      &client,
+      DataBuffer::<R, f32>::from_slice(&logits, &[N, C], &client).unwrap(),
+    )
+    .unwrap()
+    .into_vec(&client)
+    .unwrap();
+  let expected_probs = softmax_manual(&logits);
+  for (i, (p, e)) in probs.iter().zip(expected_probs.iter()).enumerate() {
+    assert!(p.is_finite(), "第 {} 个概率不是有限值", i);
+    assert!(
+      (p - e).abs() < 1e-5,
+      "第 {} 个概率不匹配: cubecl = {}, manual = {}",
+      i,
+      p,
+      e
+    );
+  }
+
+  let logits_buf = DataBuffer::<R, f32>::from_slice(&logits, &[N, C], &client).unwrap();
+  let (score, index) = classification
+    .execute::<R, f32, u32>(&client, logits_buf)
+    .unwrap();
+  assert_eq!(score.shape(), &[N, K]);
+  let score = score.into_vec(&client).unwrap();
+  let index = index.into_vec(&client).unwrap();
+
+  let reference = if softmax { &expected_probs } else { &logits };
+  for n in 0..N {
+    let row = &reference[n * C..(n + 1) * C];
+    let mut order: Vec<usize> = (0..C).collect();
+    order.sort_by(|&a, &b| {
+      logits[n * C + b]
+        .total_cmp(&logits[n * C + a])
+        .then(a.cmp(&b))
+    });
+
+    let expected: Vec<u32> = order[..K].iter().map(|&i| i as u32).collect();
+    assert_eq!(
+      &index[n * K..(n + 1) * K],
+      &expected[..],
+      "第 {} 行类别不匹配",
+      n
+    );
+    for k in 0..K {
+      assert!((score[n * K + k] - row[order[k]]).abs() < 1e-5);
+    }
+  }
+  assert_eq!(index[K], 7);
+  assert_eq!(&index[2 * K..2 * K + 2], &[3, 9]);
+}
+
+fn softmax_manual(logits: &[f32]) -> Vec<f32> {
+  logits
+    .chunks(C)
+    .flat_map(|row| {
+      let max = row.iter().cloned().fold(f32::MIN, f32::max);
+      let exp: Vec<f32> = row.iter().map(|v| (v - max).exp()).collect();
+      let sum: f32 = exp.iter().sum();
+      exp.into_iter().map(move |e| e / sum)
+    })
+    .collect()
+}
@@ -0,0 +1,147 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_ctc.rs - CTC 贪心解码与束搜索解码测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{data::DataBuffer, postprocess::ctc::CtcConfig};
+
+const N: usize = 3;
+const T: usize = 24;
+
+fn dictionary(chars: &str) -> Vec<String> {
+  chars.chars().map(String::from).collect()
+}
+
+#[test]
+fn test_ctc_beam_search() {
+  let ctc = CtcConfig::default()
+    .with_dictionary(dictionary("a"))
+    .with_softmax(false)
+    .build()
+    .unwrap();
+  // P("a") = 0.6 * 0.6 + 0.6 * 0.4 + 0.4 * 0.6
+  let result = ctc.beam_search_sequence(&[0.4, 0.6, 0.4, 0.6]);
+  assert_eq!(result.text, "a");
+  assert!((result.score - 0.84).abs() < 1e-5);
+
+  // 贪心解码得到空文本，束搜索合并路径后得到 "a"
+  let probs = [0.5, 0.4, 0.1, 0.5, 0.4, 0.1];
+  let ctc = CtcConfig::default()
+    .with_dictionary(dictionary("ab"))
+    .with_softmax(false)
+    .build()
+    .unwrap();
+  let result = ctc.beam_search_sequence(&probs);
+  assert_eq!(result.text, "a");
+  assert!((result.score - 0.56).abs() < 1e-5);
+
+  // 词表约束
+  let ctc = CtcConfig::default()
+    .with_dictionary(dictionary("ab"))
+    .with_softmax(false)
+    .with_lexicon(vec!["b".to_string()])
+    .build()
+    .unwrap();
+  let result = ctc.beam_search_sequence(&probs);
+  assert_eq!(result.text, "b");
+  assert!((result.score - 0.11).abs() < 1e-5);
+
+  // blank 不在第一个类别，相邻重复字符之间有 blank 时不合并
+  let ctc = CtcConfig::default()
+    .with_dictionary(dictionary("ab"))
+    .with_blank(2)
+    .with_softmax(false)
+    .with_beam_width(4)
+    .build()
+    .unwrap();
+  let probs = [0.9, 0.05, 0.05, 0.05, 0.05, 0.9, 0.9, 0.05, 0.05];
+  assert_eq!(ctc.beam_search_sequence(&probs).text, "aa");
+
+  // 多字符的字典项与无法组成的词
+  let ctc = CtcConfig::default()
+    .with_dictionary(vec!["ab".to_string(), "a".to_string()])
+    .with_lexicon(vec!["aba".to_string()])
+    .build();
+  assert!(ctc.is_ok());
+  let ctc = CtcConfig::default()
+    .with_dictionary(dictionary("ab"))
+    .with_lexicon(vec!["abc".to_string()])
+    .build();
+  assert!(ctc.is_err());
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_ctc_cpu() {
+  test_postprocess_ctc::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_ctc_wgpu() {
+  test_postprocess_ctc::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_ctc<R: Runtime>() {
+  let chars = dictionary("0123456789");
+  let c = chars.len() + 1;
+  // 放大 blank 的 logit，使解码结果中同时出现 blank 与重复字符
+  let logits: Vec<f32> = (0..N * T * c)
+    .map(|i| rand::random::<f32>() * 4.0 + if i % c == 0 { 1.0 } else { 0.0 })
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let ctc = CtcConfig::default()
+    .with_dictionary(chars.clone())
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let logits_buf = DataBuffer::<R, f32>::from_slice(&logits, &[N, T, c], &client).unwrap();
+  let greedy = ctc.decode_greedy(&client, logits_buf.clone()).unwrap();
+  let beam = ctc.beam_search(&client, logits_buf).unwrap();
+  assert_eq!(greedy.len(), N);
+  assert_eq!(beam.len(), N);
+
+  for n in 0..N {
+    let (text, score) = greedy_manual(&logits[n * T * c..(n + 1) * T * c], c, &chars);
+    assert_eq!(greedy[n].text, text);
+    assert!((greedy[n].score - score).abs() < 1e-5);
+    assert_eq!(
+      beam[n],
+      ctc.beam_search_sequence(&logits[n * T * c..(n + 1) * T * c])
+    );
+  }
+}
+
+fn greedy_manual(logits: &[f32], c: usize, chars: &[String]) -> (String, f32) {
+  let mut text = String::new();
+  let mut probs = Vec::new();
+  let mut previous = 0;
+  for step in logits.chunks_exact(c) {
+    let (best, &max) = step
+      .iter()
+      .enumerate()
+      .fold((0, &step[0]), |a, b| if b.1 > a.1 { b } else { a });
+    if best != 0 && best != previous {
+      text.push_str(&chars[best - 1]);
+      probs.push(1.0 / step.iter().map(|v| (v - max).exp()).sum::<f32>());
+    }
+    previous = best;
+  }
+  let score = if probs.is_empty() {
+    0.0
+  } else {
+    probs.iter().sum::<f32>() / probs.len() as f32
+  };
+  (text, score)
+}
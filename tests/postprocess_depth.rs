@@ -0,0 +1,171 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_depth.rs - 单目深度估计后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::depth::{Colormap, DepthConfig, Normalization},
+};
+
+const N: usize = 2;
+const H: usize = 24;
+const W: usize = 40;
+
+// 多项式拟合与原色表之间存在少量误差
+fn assert_color(a: [u8; 3], b: [u8; 3]) {
+  assert!(
+    a.iter().zip(b.iter()).all(|(x, y)| x.abs_diff(*y) <= 5),
+    "{:?} != {:?}",
+    a,
+    b
+  );
+}
+
+#[test]
+fn test_colormap() {
+  let gray = Colormap::Gray.palette();
+  assert_eq!(gray.len(), 256);
+  for (i, c) in gray.iter().enumerate() {
+    assert_eq!(*c, [i as u8; 3]);
+  }
+
+  let viridis = Colormap::Viridis.palette();
+  assert_color(viridis[0], [68, 1, 84]);
+  assert_color(viridis[255], [253, 231, 37]);
+  let inferno = Colormap::Inferno.palette();
+  assert_color(inferno[0], [0, 0, 4]);
+  assert_color(inferno[255], [252, 255, 164]);
+  let turbo = Colormap::Turbo.palette();
+  assert_color(turbo[0], [35, 23, 27]);
+  assert_eq!(turbo.len(), 256);
+
+  assert!(
+    DepthConfig::default()
+      .with_normalization(Normalization::Percentile {
+        low: 0.9,
+        high: 0.1
+      })
+      .build()
+      .is_err()
+  );
+  assert!(DepthConfig::default().with_dim(100).build().is_err());
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_depth_cpu() {
+  test_postprocess_depth::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_depth_wgpu() {
+  test_postprocess_depth::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_depth<R: Runtime>() {
+  let depth: Vec<f32> = (0..N * H * W)
+    .map(|_| rand::random::<f32>() * 10.0)
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let depth_buf = DataBuffer::<R, f32>::from_slice(&depth, &[N, 1, H, W], &client).unwrap();
+
+  // 最小值与最大值归一化并反转
+  let processor = DepthConfig::default()
+    .with_invert(true)
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let output = processor.execute(&client, depth_buf.clone()).unwrap();
+  assert_eq!(output.shape(), &[N, 1, H, W]);
+  let normalized = output.clone().into_vec(&client).unwrap();
+  for n in 0..N {
+    let image = &depth[n * H * W..(n + 1) * H * W];
+    let min = image.iter().copied().fold(f32::MAX, f32::min);
+    let max = image.iter().copied().fold(f32::MIN, f32::max);
+    for (v, d) in normalized[n * H * W..(n + 1) * H * W].iter().zip(image) {
+      assert!((v - (1.0 - (d - min) / (max - min))).abs() < 1e-5);
+    }
+  }
+
+  // 伪彩色
+  let palette = Colormap::Viridis.palette();
+  let palette_u32: Vec<[u32; 3]> = palette.iter().map(|c| c.map(u32::from)).collect();
+  let rgb = processor
+    .colorize(&client, output, &palette_u32)
+    .unwrap()
+    .into_vec(&client)
+    .unwrap();
+  for n in 0..N {
+    for i in 0..H * W {
+      let v = normalized[n * H * W + i];
+      let color = palette_u32[(v.clamp(0.0, 1.0) * 255.0 + 0.5).floor() as usize];
+      for c in 0..3 {
+        assert_eq!(rgb[(n * 3 + c) * H * W + i], color[c]);
+      }
+    }
+  }
+
+  // 分位数归一化
+  let processor = DepthConfig::default()
+    .with_normalization(Normalization::Percentile {
+      low: 0.1,
+      high: 0.9,
+    })
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let normalized = processor
+    .execute(&client, depth_buf.clone())
+    .unwrap()
+    .into_vec(&client)
+    .unwrap();
+  for n in 0..N {
+    let image = &depth[n * H * W..(n + 1) * H * W];
+    let mut sorted = image.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let low = sorted[(0.1 * (H * W) as f32).ceil() as usize - 1];
+    let high = sorted[(0.9 * (H * W) as f32).ceil() as usize - 1];
+    for (v, d) in normalized[n * H * W..(n + 1) * H * W].iter().zip(image) {
+      assert!((v - ((d - low) / (high - low)).clamp(0.0, 1.0)).abs() < 1e-4);
+    }
+  }
+
+  // 缩放到原图尺寸
+  let processor = DepthConfig::default()
+    .with_resize(W as u32 * 2, H as u32 * 2)
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let output = processor.execute(&client, depth_buf).unwrap();
+  assert_eq!(output.shape(), &[N, 1, H * 2, W * 2]);
+  let resized = output.into_vec(&client).unwrap();
+  assert!(resized.iter().all(|v| (0.0..=1.0).contains(v)));
+  for n in 0..N {
+    let image = &resized[n * 4 * H * W..(n + 1) * 4 * H * W];
+    assert!(image.contains(&0.0));
+    assert!(image.contains(&1.0));
+  }
+  // 空的深度图在计算分位数前被拒绝，这里只检查形状，数据内容无关
+  let processor = DepthConfig::default()
+    .with_normalization(Normalization::Percentile {
+      low: 0.02,
+      high: 0.98,
+    })
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let empty = DataBuffer::<R, f32>::from_slice(&[0.0], &[N, 1, 0, W], &client).unwrap();
+  assert!(processor.execute(&client, empty).is_err());
+}
@@ -0,0 +1,144 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_centernet.rs - CenterNet 峰值提取与解码测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{data::DataBuffer, postprocess::detection::CenterNetConfig};
+
+const N: usize = 2;
+const CLS: usize = 3;
+const H: usize = 16;
+const W: usize = 16;
+const K: usize = 40;
+const STRIDE: f32 = 4.0;
+const IMAGE: f32 = 64.0;
+const THRESHOLD: f32 = 0.6;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_centernet_cpu() {
+  test_postprocess_detection_centernet::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_centernet_wgpu() {
+  test_postprocess_detection_centernet::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_detection_centernet<R: Runtime>() {
+  let hw = H * W;
+  let heatmap: Vec<f32> = (0..N * CLS * hw)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect();
+  let size: Vec<f32> = (0..N * 2 * hw)
+    .map(|_| 1.0 + rand::random::<f32>() * 6.0)
+    .collect();
+  let offset: Vec<f32> = (0..N * 2 * hw).map(|_| rand::random::<f32>()).collect();
+
+  let client = R::client(&R::Device::default());
+  let centernet = CenterNetConfig::default()
+    .with_shape(IMAGE as u32, IMAGE as u32)
+    .with_top_k(K as u32)
+    .with_score_threshold(THRESHOLD)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let heatmap_buf = DataBuffer::<R, f32>::from_slice(&heatmap, &[N, CLS, H, W], &client).unwrap();
+  let size_buf = DataBuffer::<R, f32>::from_slice(&size, &[N, 2, H, W], &client).unwrap();
+  let offset_buf = DataBuffer::<R, f32>::from_slice(&offset, &[N, 2, H, W], &client).unwrap();
+  let candidates = centernet
+    .execute::<R, f32, u32>(&client, heatmap_buf, size_buf, offset_buf, STRIDE)
+    .unwrap();
+  assert_eq!(candidates.bbox.shape(), &[N, 4, K]);
+
+  let score = candidates.score.into_vec(&client).unwrap();
+  let index = candidates.index.into_vec(&client).unwrap();
+  let bbox = candidates.bbox.into_vec(&client).unwrap();
+  let source = candidates.source.into_vec(&client).unwrap();
+  let count = candidates.count.into_vec(&client).unwrap();
+
+  for n in 0..N {
+    let expected = peaks_manual(&heatmap[n * CLS * hw..(n + 1) * CLS * hw]);
+    assert_eq!(
+      count[n] as usize,
+      expected.len(),
+      "第 {} 张图像峰值数量不匹配",
+      n
+    );
+
+    for k in 0..K {
+      let at = n * K + k;
+      let Some(&(value, pos)) = expected.get(k) else {
+        assert_eq!(score[at], 0.0);
+        assert_eq!(source[at], 0);
+        continue;
+      };
+      let rem = pos % hw;
+      let (y, x) = (rem / W, rem % W);
+      assert!((score[at] - value).abs() < 1e-5);
+      assert_eq!(index[at] as usize, pos / hw);
+      assert_eq!(source[at] as usize, rem);
+
+      let cx = x as f32 + offset[n * 2 * hw + rem];
+      let cy = y as f32 + offset[n * 2 * hw + hw + rem];
+      let (w, h) = (size[n * 2 * hw + rem], size[n * 2 * hw + hw + rem]);
+      let expected_bbox = [
+        (cx - w / 2.0) * STRIDE / IMAGE,
+        (cy - h / 2.0) * STRIDE / IMAGE,
+        (cx + w / 2.0) * STRIDE / IMAGE,
+        (cy + h / 2.0) * STRIDE / IMAGE,
+      ];
+      for (c, e) in expected_bbox.iter().enumerate() {
+        let v = bbox[n * 4 * K + c * K + k];
+        assert!(
+          (v - e.clamp(0.0, 1.0)).abs() < 1e-5,
+          "第 {} 张图像第 {} 个峰值的边界框不匹配: cubecl = {}, manual = {}",
+          n,
+          k,
+          v,
+          e
+        );
+      }
+    }
+  }
+}
+
+/// 返回单张图像中得分不低于阈值的 3x3 局部峰值 (score, c * H * W 中的位置)，按得分降序取前 K 个
+fn peaks_manual(heatmap: &[f32]) -> Vec<(f32, usize)> {
+  let hw = H * W;
+  let heat: Vec<f32> = heatmap.iter().map(|v| 1.0 / (1.0 + (-v).exp())).collect();
+  let mut peaks = Vec::new();
+  for c in 0..CLS {
+    for y in 0..H {
+      for x in 0..W {
+        let center = heat[c * hw + y * W + x];
+        let mut is_peak = true;
+        for ny in y.saturating_sub(1)..(y + 2).min(H) {
+          for nx in x.saturating_sub(1)..(x + 2).min(W) {
+            if heat[c * hw + ny * W + nx] > center {
+              is_peak = false;
+            }
+          }
+        }
+        if is_peak {
+          peaks.push((center, c * hw + y * W + x));
+        }
+      }
+    }
+  }
+  peaks.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+  peaks.truncate(K);
+  peaks.retain(|&(v, _)| v >= THRESHOLD);
+  peaks
+}
@@ -0,0 +1,139 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_detr.rs - DETR/RT-DETR 后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::detection::{BoxUnit, DetrConfig},
+};
+
+const N: usize = 2;
+const Q: usize = 50;
+const CLS: usize = 6;
+const K: usize = 30;
+const THRESHOLD: f32 = 0.3;
+const WIDTH: f32 = 640.0;
+const HEIGHT: f32 = 480.0;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_detr_cpu() {
+  test_postprocess_detection_detr::<cubecl::cpu::CpuRuntime>(false);
+  test_postprocess_detection_detr::<cubecl::cpu::CpuRuntime>(true);
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_detr_wgpu() {
+  test_postprocess_detection_detr::<cubecl::wgpu::WgpuRuntime>(false);
+  test_postprocess_detection_detr::<cubecl::wgpu::WgpuRuntime>(true);
+}
+
+fn test_postprocess_detection_detr<R: Runtime>(softmax: bool) {
+  let logits: Vec<f32> = (0..N * Q * CLS)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect();
+  let boxes: Vec<f32> = (0..N * Q * 4)
+    .map(|_| 0.1 + rand::random::<f32>() * 0.8)
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let detr = DetrConfig::default()
+    .with_shape(WIDTH as u32, HEIGHT as u32)
+    .with_top_k(K as u32)
+    .with_score_threshold(THRESHOLD)
+    .with_softmax(softmax)
+    .with_unit(BoxUnit::Pixel)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let logits_buf = DataBuffer::<R, f32>::from_slice(&logits, &[N, Q, CLS], &client).unwrap();
+  let boxes_buf = DataBuffer::<R, f32>::from_slice(&boxes, &[N, Q, 4], &client).unwrap();
+  let candidates = detr
+    .execute::<R, f32, u32>(&client, logits_buf, boxes_buf)
+    .unwrap();
+  assert_eq!(candidates.bbox.shape(), &[N, 4, K]);
+
+  let score = candidates.score.into_vec(&client).unwrap();
+  let index = candidates.index.into_vec(&client).unwrap();
+  let bbox = candidates.bbox.into_vec(&client).unwrap();
+  let source = candidates.source.into_vec(&client).unwrap();
+  let count = candidates.count.into_vec(&client).unwrap();
+
+  for n in 0..N {
+    let expected = top_k_manual(&logits[n * Q * CLS..(n + 1) * Q * CLS], softmax);
+    assert_eq!(
+      count[n] as usize,
+      expected.len(),
+      "第 {} 张图像数量不匹配",
+      n
+    );
+
+    for (k, &(value, q, c)) in expected.iter().enumerate() {
+      let at = n * K + k;
+      assert!((score[at] - value).abs() < 1e-5);
+      assert_eq!(index[at] as usize, c);
+      assert_eq!(source[at] as usize, q);
+
+      let b = &boxes[(n * Q + q) * 4..(n * Q + q + 1) * 4];
+      let expected_bbox = [
+        (b[0] - b[2] / 2.0).clamp(0.0, 1.0) * WIDTH,
+        (b[1] - b[3] / 2.0).clamp(0.0, 1.0) * HEIGHT,
+        (b[0] + b[2] / 2.0).clamp(0.0, 1.0) * WIDTH,
+        (b[1] + b[3] / 2.0).clamp(0.0, 1.0) * HEIGHT,
+      ];
+      for (i, e) in expected_bbox.iter().enumerate() {
+        let v = bbox[n * 4 * K + i * K + k];
+        assert!(
+          (v - e).abs() < 1e-3,
+          "第 {} 张图像第 {} 个结果的边界框不匹配: cubecl = {}, manual = {}",
+          n,
+          k,
+          v,
+          e
+        );
+      }
+    }
+    for k in expected.len()..K {
+      assert_eq!(score[n * K + k], 0.0);
+    }
+  }
+}
+
+/// 返回单张图像得分不低于阈值的 Top-K 结果 (score, query, class)
+fn top_k_manual(logits: &[f32], softmax: bool) -> Vec<(f32, usize, usize)> {
+  let classes = if softmax { CLS - 1 } else { CLS };
+  let mut all = Vec::new();
+  for q in 0..Q {
+    let row = &logits[q * CLS..(q + 1) * CLS];
+    let max = row.iter().copied().fold(f32::MIN, f32::max);
+    let sum: f32 = row.iter().map(|v| (v - max).exp()).sum();
+    for (c, &v) in row.iter().enumerate().take(classes) {
+      let p = if softmax {
+        (v - max).exp() / sum
+      } else {
+        1.0 / (1.0 + (-v).exp())
+      };
+      all.push((p, q, c));
+    }
+  }
+  all.sort_by(|a, b| {
+    b.0
+      .total_cmp(&a.0)
+      .then((a.1 * classes + a.2).cmp(&(b.1 * classes + b.2)))
+  });
+  all.truncate(K);
+  all.retain(|&(v, _, _)| v >= THRESHOLD);
+  all
+}
@@ -0,0 +1,263 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_obb.rs - 旋转框解码与旋转框 NMS 测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use std::f32::consts::PI;
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{detection::ObbConfig, nms::NmsConfig},
+};
+
+const N: usize = 2;
+const CLS: usize = 3;
+const H: usize = 12;
+const W: usize = 12;
+const STRIDE: f32 = 8.0;
+const D: usize = 30;
+const IOU_THRESHOLD: f32 = 0.3;
+
+#[test]
+fn test_rotated_iou_reference() {
+  // 完全重合
+  let a = [0.0, 0.0, 2.0, 2.0, 0.3];
+  assert!((rotated_iou(&a, &a) - 1.0).abs() < 1e-5);
+
+  // 正方形与旋转 45° 的自身，交集为正八边形，面积 8 * (sqrt(2) - 1)
+  let a = [0.0, 0.0, 2.0, 2.0, 0.0];
+  let b = [0.0, 0.0, 2.0, 2.0, PI / 4.0];
+  let inter = 8.0 * (2.0f32.sqrt() - 1.0);
+  assert!((rotated_iou(&a, &b) - inter / (8.0 - inter)).abs() < 1e-5);
+
+  // 不相交
+  let b = [5.0, 5.0, 2.0, 2.0, 0.7];
+  assert_eq!(rotated_iou(&a, &b), 0.0);
+
+  // 包含关系
+  let b = [0.0, 0.0, 1.0, 1.0, 1.1];
+  assert!((rotated_iou(&a, &b) - 0.25).abs() < 1e-5);
+
+  // 旋转 90° 的矩形与交换宽高的矩形重合
+  let a = [3.0, 1.0, 4.0, 2.0, 0.0];
+  let b = [3.0, 1.0, 2.0, 4.0, PI / 2.0];
+  assert!((rotated_iou(&a, &b) - 1.0).abs() < 1e-4);
+
+  // 轴对齐时与普通交并比一致
+  let a = [1.0, 1.0, 2.0, 2.0, 0.0];
+  let b = [2.0, 1.5, 2.0, 3.0, 0.0];
+  assert!((rotated_iou(&a, &b) - 2.0 / 8.0).abs() < 1e-5);
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_obb_cpu() {
+  test_postprocess_detection_obb::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_obb_wgpu() {
+  test_postprocess_detection_obb::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_detection_obb<R: Runtime>() {
+  let hw = H * W;
+  let cls: Vec<f32> = (0..N * CLS * hw)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect();
+  let reg: Vec<f32> = (0..N * 4 * hw)
+    .map(|_| 0.5 + rand::random::<f32>() * 3.0)
+    .collect();
+  let angle: Vec<f32> = (0..N * hw)
+    .map(|_| rand::random::<f32>() * 6.0 - 3.0)
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let obb = ObbConfig::default().with_dim(64).build().unwrap();
+  let nms = NmsConfig::default()
+    .with_score_threshold(0.5)
+    .with_iou_threshold(IOU_THRESHOLD)
+    .with_max_detections(D as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let cls_buf = DataBuffer::<R, f32>::from_slice(&cls, &[N, CLS, H, W], &client).unwrap();
+  let reg_buf = DataBuffer::<R, f32>::from_slice(&reg, &[N, 4, H, W], &client).unwrap();
+  let angle_buf = DataBuffer::<R, f32>::from_slice(&angle, &[N, 1, H, W], &client).unwrap();
+  let (score, index, rbox) = obb
+    .execute::<R, f32, u32>(&client, cls_buf, reg_buf, angle_buf, STRIDE)
+    .unwrap();
+  assert_eq!(rbox.shape(), &[N, 5, H, W]);
+
+  let detections = nms
+    .execute_rotated(&client, score.clone(), index.clone(), rbox.clone())
+    .unwrap();
+  assert_eq!(detections.bbox.shape(), &[N, 5, D]);
+
+  let score = score.into_vec(&client).unwrap();
+  let index = index.into_vec(&client).unwrap();
+  let rbox = rbox.into_vec(&client).unwrap();
+
+  // 解码结果与参考实现一致
+  let expected = decode_manual(&reg, &angle);
+  for (i, (c, m)) in rbox.iter().zip(expected.iter()).enumerate() {
+    assert!(
+      (c - m).abs() < 1e-3,
+      "旋转框张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      c,
+      m
+    );
+  }
+
+  // NMS 结果与 CPU 贪心 NMS 一致
+  let count = detections.count.into_vec(&client).unwrap();
+  let source = detections.source.into_vec(&client).unwrap();
+  let out_bbox = detections.bbox.into_vec(&client).unwrap();
+  for n in 0..N {
+    let boxes: Vec<[f32; 5]> = (0..hw)
+      .map(|i| std::array::from_fn(|c| rbox[n * 5 * hw + c * hw + i]))
+      .collect();
+    let kept = nms_manual(
+      &score[n * hw..(n + 1) * hw],
+      &index[n * hw..(n + 1) * hw],
+      &boxes,
+      0.5,
+    );
+
+    let count = count[n] as usize;
+    let selected: Vec<usize> = source[n * D..n * D + count]
+      .iter()
+      .map(|&s| s as usize)
+      .collect();
+    assert_eq!(selected, kept, "第 {} 张图像保留结果不匹配", n);
+
+    for (d, &src) in selected.iter().enumerate() {
+      for c in 0..5 {
+        assert_eq!(out_bbox[n * 5 * D + c * D + d], boxes[src][c]);
+      }
+    }
+  }
+}
+
+fn decode_manual(reg: &[f32], angle: &[f32]) -> Vec<f32> {
+  let hw = H * W;
+  let mut output = vec![0.0; N * 5 * hw];
+  for n in 0..N {
+    for h in 0..H {
+      for w in 0..W {
+        let i = h * W + w;
+        let at = |c: usize| reg[(n * 4 + c) * hw + i];
+        let (l, t, r, b) = (at(0), at(1), at(2), at(3));
+        let theta = (1.0 / (1.0 + (-angle[n * hw + i]).exp()) - 0.25) * PI;
+        let (dx, dy) = ((r - l) / 2.0, (b - t) / 2.0);
+        let base = n * 5 * hw + i;
+        output[base] = (w as f32 + 0.5 + dx * theta.cos() - dy * theta.sin()) * STRIDE;
+        output[base + hw] = (h as f32 + 0.5 + dx * theta.sin() + dy * theta.cos()) * STRIDE;
+        output[base + 2 * hw] = (l + r) * STRIDE;
+        output[base + 3 * hw] = (t + b) * STRIDE;
+        output[base + 4 * hw] = theta;
+      }
+    }
+  }
+  output
+}
+
+fn nms_manual(score: &[f32], index: &[u32], boxes: &[[f32; 5]], threshold: f32) -> Vec<usize> {
+  let mut order: Vec<usize> = (0..score.len())
+    .filter(|&i| score[i] >= threshold)
+    .collect();
+  order.sort_by(|&a, &b| score[b].total_cmp(&score[a]).then(a.cmp(&b)));
+
+  let mut alive = vec![true; score.len()];
+  let mut kept = Vec::new();
+  for &i in &order {
+    if !alive[i] || kept.len() == D {
+      continue;
+    }
+    kept.push(i);
+    for &j in &order {
+      if j != i
+        && alive[j]
+        && index[j] == index[i]
+        && rotated_iou(&boxes[i], &boxes[j]) > IOU_THRESHOLD
+      {
+        alive[j] = false;
+      }
+    }
+  }
+  kept
+}
+
+fn corners(b: &[f32; 5]) -> Vec<(f32, f32)> {
+  let (cos, sin) = (b[4].cos(), b[4].sin());
+  [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+    .iter()
+    .map(|&(sx, sy)| {
+      let (lx, ly) = (sx * b[2] / 2.0, sy * b[3] / 2.0);
+      (b[0] + lx * cos - ly * sin, b[1] + lx * sin + ly * cos)
+    })
+    .collect()
+}
+
+fn inside(p: (f32, f32), poly: &[(f32, f32)]) -> bool {
+  (0..4).all(|i| {
+    let (a, b) = (poly[i], poly[(i + 1) % 4]);
+    (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0) >= -1e-6
+  })
+}
+
+/// 参考实现: 收集互相包含的顶点与边的交点，按极角排序后用鞋带公式求面积
+fn rotated_iou(a: &[f32; 5], b: &[f32; 5]) -> f32 {
+  let pa = corners(a);
+  let pb = corners(b);
+
+  let mut points: Vec<(f32, f32)> = Vec::new();
+  points.extend(pa.iter().filter(|&&p| inside(p, &pb)));
+  points.extend(pb.iter().filter(|&&p| inside(p, &pa)));
+  for i in 0..4 {
+    let (p1, p2) = (pa[i], pa[(i + 1) % 4]);
+    for j in 0..4 {
+      let (q1, q2) = (pb[j], pb[(j + 1) % 4]);
+      let d = (p2.0 - p1.0) * (q2.1 - q1.1) - (p2.1 - p1.1) * (q2.0 - q1.0);
+      if d.abs() < 1e-12 {
+        continue;
+      }
+      let t = ((q1.0 - p1.0) * (q2.1 - q1.1) - (q1.1 - p1.1) * (q2.0 - q1.0)) / d;
+      let u = ((q1.0 - p1.0) * (p2.1 - p1.1) - (q1.1 - p1.1) * (p2.0 - p1.0)) / d;
+      if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+        points.push((p1.0 + t * (p2.0 - p1.0), p1.1 + t * (p2.1 - p1.1)));
+      }
+    }
+  }
+
+  let mut inter = 0.0;
+  if points.len() >= 3 {
+    let cx = points.iter().map(|p| p.0).sum::<f32>() / points.len() as f32;
+    let cy = points.iter().map(|p| p.1).sum::<f32>() / points.len() as f32;
+    points.sort_by(|p, q| {
+      (p.1 - cy)
+        .atan2(p.0 - cx)
+        .total_cmp(&(q.1 - cy).atan2(q.0 - cx))
+    });
+    for i in 0..points.len() {
+      let (p, q) = (points[i], points[(i + 1) % points.len()]);
+      inter += p.0 * q.1 - q.0 * p.1;
+    }
+    inter = inter.abs() / 2.0;
+  }
+
+  let union = a[2] * a[3] + b[2] * b[3] - inter;
+  if union > 0.0 { inter / union } else { 0.0 }
+}
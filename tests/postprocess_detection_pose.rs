@@ -0,0 +1,153 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_pose.rs - YOLO-pose 关键点解码测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{
+    detection::{PoseConfig, Yolo26Config},
+    nms::NmsConfig,
+  },
+};
+
+const N: usize = 2;
+const CLS: usize = 2;
+const KPT: usize = 5;
+const H: usize = 16;
+const W: usize = 16;
+const STRIDE: f32 = 16.0;
+const D: usize = 20;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_pose_cpu() {
+  test_postprocess_detection_pose::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_pose_wgpu() {
+  test_postprocess_detection_pose::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_detection_pose<R: Runtime>() {
+  let hw = H * W;
+  let cls: Vec<f32> = (0..N * CLS * hw)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect();
+  let reg: Vec<f32> = (0..N * 4 * hw)
+    .map(|_| rand::random::<f32>() * 3.0)
+    .collect();
+  let kpt: Vec<f32> = (0..N * KPT * 3 * hw)
+    .map(|_| rand::random::<f32>() * 4.0 - 2.0)
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let yolo26 = Yolo26Config::default()
+    .with_shape(256, 256)
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let pose = PoseConfig::default()
+    .with_shape(256, 256)
+    .with_num_keypoints(KPT as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let nms = NmsConfig::default()
+    .with_score_threshold(0.5)
+    .with_max_detections(D as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let kpt_buf = DataBuffer::<R, f32>::from_slice(&kpt, &[N, KPT * 3, H, W], &client).unwrap();
+  let keypoints = pose.execute(&client, kpt_buf, STRIDE).unwrap();
+  assert_eq!(keypoints.shape(), &[N, KPT, 3, H, W]);
+
+  let cls_buf = DataBuffer::<R, f32>::from_slice(&cls, &[N, CLS, H, W], &client).unwrap();
+  let reg_buf = DataBuffer::<R, f32>::from_slice(&reg, &[N, 4, H, W], &client).unwrap();
+  let (score, index, bbox) = yolo26
+    .execute::<R, f32, u32>(&client, cls_buf, reg_buf, STRIDE)
+    .unwrap();
+  let detections = nms.execute(&client, score, index, bbox).unwrap();
+
+  let gathered = pose
+    .gather(&client, keypoints.clone(), &detections)
+    .unwrap();
+  assert_eq!(gathered.shape(), &[N, KPT, 3, D]);
+
+  let keypoints = keypoints.into_vec(&client).unwrap();
+  let gathered = gathered.into_vec(&client).unwrap();
+  let source = detections.source.into_vec(&client).unwrap();
+  let count = detections.count.into_vec(&client).unwrap();
+
+  // 稠密解码结果与参考实现一致
+  let expected = run_postprocess_detection_pose_manual(&kpt, STRIDE, 256, 256);
+  for (i, (c, m)) in keypoints.iter().zip(expected.iter()).enumerate() {
+    assert!(
+      (c - m).abs() < 1e-5,
+      "关键点张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      c,
+      m
+    );
+  }
+
+  // NMS 之后的关键点应与其 source 指向的位置一致
+  for n in 0..N {
+    for d in 0..D {
+      for k in 0..KPT {
+        for c in 0..3 {
+          let actual = gathered[((n * KPT + k) * 3 + c) * D + d];
+          let expected = if d < count[n] as usize {
+            keypoints[((n * KPT + k) * 3 + c) * hw + source[n * D + d] as usize]
+          } else {
+            0.0
+          };
+          assert_eq!(
+            actual, expected,
+            "第 {} 张图像第 {} 个检测结果关键点不匹配",
+            n, d
+          );
+        }
+      }
+    }
+  }
+}
+
+fn run_postprocess_detection_pose_manual(
+  kpt: &[f32],
+  stride: f32,
+  width: usize,
+  height: usize,
+) -> Vec<f32> {
+  let hw = H * W;
+  let mut output = vec![0.0; N * KPT * 3 * hw];
+  for n in 0..N {
+    for k in 0..KPT {
+      for h in 0..H {
+        for w in 0..W {
+          let at = |c: usize| kpt[((n * KPT + k) * 3 + c) * hw + h * W + w];
+          let x = (at(0) * 2.0 + w as f32) * stride;
+          let y = (at(1) * 2.0 + h as f32) * stride;
+          let base = (n * KPT + k) * 3 * hw + h * W + w;
+          output[base] = (x / width as f32).clamp(0.0, 1.0);
+          output[base + hw] = (y / height as f32).clamp(0.0, 1.0);
+          output[base + 2 * hw] = 1.0 / (1.0 + (-at(2)).exp());
+        }
+      }
+    }
+  }
+  output
+}
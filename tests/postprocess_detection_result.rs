@@ -0,0 +1,155 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_result.rs - 检测结果类型测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{
+    detection::{BoxUnit, Detection, DetectionSet},
+    nms::NmsConfig,
+  },
+};
+
+fn detection(batch_index: usize, score: f32, class_id: u32) -> Detection {
+  Detection {
+    bbox: [0.1, 0.2, 0.5, 0.6],
+    score,
+    class_id,
+    batch_index,
+  }
+}
+
+#[test]
+fn test_detection_set_grouping_and_sorting() {
+  let mut set = DetectionSet::new(
+    vec![
+      detection(2, 0.3, 0),
+      detection(0, 0.4, 1),
+      detection(2, 0.9, 1),
+      detection(0, 0.8, 2),
+    ],
+    4,
+    BoxUnit::Normalized,
+  );
+  assert_eq!(set.batch_size(), 4);
+  assert_eq!(set.len(), 4);
+
+  set.sort_by_score();
+  let groups = set.group_by_batch();
+  assert_eq!(groups.len(), 4);
+  assert_eq!(
+    groups[0].iter().map(|d| d.score).collect::<Vec<_>>(),
+    vec![0.8, 0.4]
+  );
+  assert!(groups[1].is_empty());
+  assert_eq!(
+    groups[2].iter().map(|d| d.score).collect::<Vec<_>>(),
+    vec![0.9, 0.3]
+  );
+  assert!(groups[3].is_empty());
+
+  let filtered = set.filter_classes(&[1]);
+  assert_eq!(filtered.len(), 2);
+  assert!(filtered.detections().iter().all(|d| d.class_id == 1));
+}
+
+#[test]
+fn test_detection_set_unit_conversion() {
+  let set = DetectionSet::new(vec![detection(0, 0.5, 0)], 1, BoxUnit::Normalized);
+
+  let pixel = set.clone().to_pixel(640, 480);
+  assert_eq!(pixel.unit(), BoxUnit::Pixel);
+  let bbox = pixel.detections()[0].bbox;
+  for (v, e) in bbox.iter().zip([64.0, 96.0, 320.0, 288.0]) {
+    assert!((v - e).abs() < 1e-4, "像素坐标不匹配: {} != {}", v, e);
+  }
+
+  // 重复转换不应再次缩放
+  let pixel = pixel.to_pixel(640, 480);
+  assert!((pixel.detections()[0].bbox[2] - 320.0).abs() < 1e-4);
+
+  // 尺寸为 0 时无法归一化
+  assert!(pixel.clone().to_normalized(0, 480).is_err());
+  assert!(set.clone().to_normalized(0, 0).is_ok());
+
+  let normalized = pixel.to_normalized(640, 480).unwrap();
+  assert_eq!(normalized.unit(), BoxUnit::Normalized);
+  for (v, e) in normalized.detections()[0]
+    .bbox
+    .iter()
+    .zip(set.detections()[0].bbox)
+  {
+    assert!((v - e).abs() < 1e-6);
+  }
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_detection_set_from_buffers_cpu() {
+  test_detection_set_from_buffers::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_detection_set_from_buffers_wgpu() {
+  test_detection_set_from_buffers::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_detection_set_from_buffers<R: Runtime>() {
+  // 两张图像，每张 3 个位置，布局为 score [N, M]、bbox [N, 4, M]
+  let score = [0.9f32, 0.1, 0.6, 0.2, 0.7, 0.8];
+  let index = [0u32, 1, 2, 0, 1, 2];
+  #[rustfmt::skip]
+  let bbox = [
+    // 第 0 张图像: xmin, ymin, xmax, ymax
+    0.0f32, 0.5, 0.6,
+    0.0, 0.5, 0.6,
+    0.4, 0.9, 0.9,
+    0.4, 0.9, 0.9,
+    // 第 1 张图像
+    0.0, 0.1, 0.5,
+    0.0, 0.1, 0.5,
+    0.3, 0.4, 0.9,
+    0.3, 0.4, 0.9,
+  ];
+
+  let client = R::client(&R::Device::default());
+  let upload = || {
+    (
+      DataBuffer::<R, f32>::from_slice(&score, &[2, 3], &client).unwrap(),
+      DataBuffer::<R, u32>::from_slice(&index, &[2, 3], &client).unwrap(),
+      DataBuffer::<R, f32>::from_slice(&bbox, &[2, 4, 3], &client).unwrap(),
+    )
+  };
+
+  let dense = DetectionSet::from_dense(&client, upload(), 0.5, BoxUnit::Normalized).unwrap();
+  assert_eq!(dense.batch_size(), 2);
+  assert_eq!(dense.batch(0).len(), 2);
+  assert_eq!(dense.batch(1).len(), 2);
+  assert_eq!(dense.batch(0)[1].bbox, [0.6, 0.6, 0.9, 0.9]);
+  assert_eq!(dense.batch(1)[0].class_id, 1);
+
+  let nms = NmsConfig::default()
+    .with_score_threshold(0.5)
+    .with_max_detections(4)
+    .with_dim(32)
+    .build()
+    .unwrap();
+  let (s, i, b) = upload();
+  let candidates = nms.execute(&client, s, i, b).unwrap();
+  let kept = DetectionSet::from_candidates(&client, candidates, BoxUnit::Normalized).unwrap();
+  assert_eq!(kept.batch(0).len(), 2);
+  assert_eq!(kept.batch(0)[0].score, 0.9);
+  assert_eq!(kept.batch(1).len(), 2);
+  assert_eq!(kept.batch(1)[0].score, 0.8);
+}
@@ -0,0 +1,183 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_ssd.rs - SSD/RetinaNet 先验框与解码测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::detection::{PriorBox, PriorBoxConfig, SsdConfig},
+};
+
+const N: usize = 2;
+const CLS: usize = 5;
+const VARIANCES: [f32; 4] = [0.1, 0.1, 0.2, 0.2];
+
+fn prior_box() -> PriorBox {
+  PriorBoxConfig::default()
+    .with_shape(300, 300)
+    .with_level(4, 4, vec![60.0, 85.0], vec![1.0, 2.0, 0.5])
+    .with_level(2, 2, vec![150.0], vec![1.0, 3.0])
+    .build()
+    .unwrap()
+}
+
+#[test]
+fn test_prior_box() {
+  let prior_box = prior_box();
+  let priors = prior_box.priors();
+  assert_eq!(prior_box.len(), 4 * 4 * 2 * 3 + 2 * 2 * 2);
+  assert_eq!(priors.len(), prior_box.len());
+
+  // 第一个位置的全部先验框相邻，中心为 (0.5 * 75) / 300
+  for prior in &priors[..6] {
+    assert!((prior[0] - 0.125).abs() < 1e-6);
+    assert!((prior[1] - 0.125).abs() < 1e-6);
+  }
+  assert!((priors[0][2] - 0.2).abs() < 1e-6);
+  assert!((priors[1][2] - 0.2 * 2.0f32.sqrt()).abs() < 1e-6);
+  assert!((priors[1][3] - 0.2 / 2.0f32.sqrt()).abs() < 1e-6);
+  // 第二个位置为 (1, 0)
+  assert!((priors[6][0] - 0.375).abs() < 1e-6);
+  assert!((priors[6][1] - 0.125).abs() < 1e-6);
+
+  // 第二个层级从 4 * 4 * 6 开始
+  let second = &priors[96];
+  assert!((second[0] - 0.25).abs() < 1e-6);
+  assert!((second[2] - 0.5).abs() < 1e-6);
+
+  // 裁剪
+  let clipped = PriorBoxConfig::default()
+    .with_level(1, 1, vec![600.0], vec![1.0])
+    .with_clip(true)
+    .build()
+    .unwrap()
+    .priors();
+  assert_eq!(clipped[0], [0.5, 0.5, 1.0, 1.0]);
+
+  assert!(PriorBoxConfig::default().build().is_err());
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_ssd_cpu() {
+  test_postprocess_detection_ssd::<cubecl::cpu::CpuRuntime>(true);
+  test_postprocess_detection_ssd::<cubecl::cpu::CpuRuntime>(false);
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_ssd_wgpu() {
+  test_postprocess_detection_ssd::<cubecl::wgpu::WgpuRuntime>(true);
+  test_postprocess_detection_ssd::<cubecl::wgpu::WgpuRuntime>(false);
+}
+
+fn test_postprocess_detection_ssd<R: Runtime>(softmax: bool) {
+  let prior_box = prior_box();
+  let priors = prior_box.priors();
+  let m = priors.len();
+
+  let loc: Vec<f32> = (0..N * m * 4)
+    .map(|_| rand::random::<f32>() * 4.0 - 2.0)
+    .collect();
+  let conf: Vec<f32> = (0..N * m * CLS)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let ssd = SsdConfig::default()
+    .with_variances(VARIANCES)
+    .with_softmax(softmax)
+    .with_background(softmax)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let prior_buf = prior_box.generate::<R, f32>(&client).unwrap();
+  assert_eq!(prior_buf.shape(), &[4, m]);
+  let loc_buf = DataBuffer::<R, f32>::from_slice(&loc, &[N, m, 4], &client).unwrap();
+  let conf_buf = DataBuffer::<R, f32>::from_slice(&conf, &[N, m, CLS], &client).unwrap();
+  let (score, index, bbox) = ssd
+    .execute::<R, f32, u32>(&client, loc_buf, conf_buf, &prior_buf)
+    .unwrap();
+  assert_eq!(score.shape(), &[N, m]);
+  assert_eq!(bbox.shape(), &[N, 4, m]);
+
+  let score = score.into_vec(&client).unwrap();
+  let index = index.into_vec(&client).unwrap();
+  let bbox = bbox.into_vec(&client).unwrap();
+
+  let (expected_score, expected_index, expected_bbox) =
+    decode_manual(&loc, &conf, &priors, softmax);
+  assert_eq!(index, expected_index);
+  for (i, (c, e)) in score.iter().zip(expected_score.iter()).enumerate() {
+    assert!(
+      (c - e).abs() < 1e-5,
+      "得分张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      c,
+      e
+    );
+  }
+  for (i, (c, e)) in bbox.iter().zip(expected_bbox.iter()).enumerate() {
+    assert!(
+      (c - e).abs() < 1e-5,
+      "边界框张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      c,
+      e
+    );
+  }
+}
+
+fn decode_manual(
+  loc: &[f32],
+  conf: &[f32],
+  priors: &[[f32; 4]],
+  softmax: bool,
+) -> (Vec<f32>, Vec<u32>, Vec<f32>) {
+  let m = priors.len();
+  let first = softmax as usize;
+  let mut score = vec![0.0; N * m];
+  let mut index = vec![0; N * m];
+  let mut bbox = vec![0.0; N * 4 * m];
+  for n in 0..N {
+    for (i, prior) in priors.iter().enumerate() {
+      let row = &conf[(n * m + i) * CLS..(n * m + i + 1) * CLS];
+      let mut best = first;
+      for c in first + 1..CLS {
+        if row[c] > row[best] {
+          best = c;
+        }
+      }
+      score[n * m + i] = if softmax {
+        let max = row.iter().copied().fold(f32::MIN, f32::max);
+        let sum: f32 = row.iter().map(|v| (v - max).exp()).sum();
+        (row[best] - max).exp() / sum
+      } else {
+        1.0 / (1.0 + (-row[best]).exp())
+      };
+      index[n * m + i] = (best - first) as u32;
+
+      let d = &loc[(n * m + i) * 4..(n * m + i + 1) * 4];
+      let cx = prior[0] + d[0] * VARIANCES[0] * prior[2];
+      let cy = prior[1] + d[1] * VARIANCES[1] * prior[3];
+      let w = prior[2] * (d[2] * VARIANCES[2]).exp();
+      let h = prior[3] * (d[3] * VARIANCES[3]).exp();
+      let base = n * 4 * m + i;
+      bbox[base] = (cx - w / 2.0).clamp(0.0, 1.0);
+      bbox[base + m] = (cy - h / 2.0).clamp(0.0, 1.0);
+      bbox[base + 2 * m] = (cx + w / 2.0).clamp(0.0, 1.0);
+      bbox[base + 3 * m] = (cy + h / 2.0).clamp(0.0, 1.0);
+    }
+  }
+  (score, index, bbox)
+}
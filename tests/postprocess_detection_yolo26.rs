@@ -14,7 +14,12 @@
 use std::vec;
 
 use cubecl::prelude::*;
-use shanan_cv::{data::DataBuffer, postprocess::detection::Yolo26Config};
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::detection::{
+    BoxEncoding, BoxFormat, BoxLayout, BoxUnit, DetectionSet, Yolo26Config,
+  },
+};
 
 const N: usize = 1;
 const CLS: usize = 8;
@@ -33,6 +38,42 @@ fn test_postprocess_detection_yolo26_wgpu() {
   test_postprocess_detection_yolo26::<cubecl::wgpu::WgpuRuntime>();
 }
 
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_yolo26_batch_cpu() {
+  test_postprocess_detection_yolo26_batch::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_yolo26_batch_wgpu() {
+  test_postprocess_detection_yolo26_batch::<cubecl::wgpu::WgpuRuntime>();
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_yolo26_levels_cpu() {
+  test_postprocess_detection_yolo26_levels::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_yolo26_levels_wgpu() {
+  test_postprocess_detection_yolo26_levels::<cubecl::wgpu::WgpuRuntime>();
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_yolo26_encodings_cpu() {
+  test_postprocess_detection_yolo26_encodings::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_yolo26_encodings_wgpu() {
+  test_postprocess_detection_yolo26_encodings::<cubecl::wgpu::WgpuRuntime>();
+}
+
 fn test_postprocess_detection_yolo26<R: Runtime>() {
   let random_cls: Vec<f32> = (0..N * CLS * H * W)
     .map(|_| rand::random::<f32>())
@@ -83,6 +124,334 @@ fn test_postprocess_detection_yolo26<R: Runtime>() {
   }
 }
 
+fn test_postprocess_detection_yolo26_batch<R: Runtime>() {
+  // 多张图像时，输出应为每张图像的 [4, H, W] 依次排列
+  const BATCH: usize = 3;
+  let random_cls: Vec<f32> = (0..BATCH * CLS * H * W)
+    .map(|_| rand::random::<f32>())
+    .collect();
+  let random_reg: Vec<f32> = (0..BATCH * 4 * H * W)
+    .map(|_| rand::random::<f32>())
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let yolo26 = Yolo26Config::default()
+    .with_shape(640, 640)
+    .with_dim(256)
+    .build()
+    .unwrap();
+
+  let cls = DataBuffer::<R, f32>::from_slice(&random_cls, &[BATCH, CLS, H, W], &client).unwrap();
+  let reg = DataBuffer::<R, f32>::from_slice(&random_reg, &[BATCH, 4, H, W], &client).unwrap();
+  let (score, index, bbox) = yolo26
+    .execute::<R, f32, u32>(&client, cls, reg, 32.0)
+    .unwrap();
+  assert_eq!(bbox.shape(), &[BATCH, 4, H, W]);
+
+  let score_cubecl = score.into_vec(&client).unwrap();
+  let index_cubecl = index.into_vec(&client).unwrap();
+  let bbox_cubecl = bbox.into_vec(&client).unwrap();
+
+  // 逐张图像计算参考结果并拼接
+  let mut score_manual = Vec::new();
+  let mut index_manual = Vec::new();
+  let mut bbox_manual = Vec::new();
+  for (cls, reg) in random_cls
+    .chunks(CLS * H * W)
+    .zip(random_reg.chunks(4 * H * W))
+  {
+    let (score, index, bbox) =
+      run_postprocess_detection_yolo26_manual(cls.to_vec(), reg.to_vec(), 32.0, 640, 640);
+    score_manual.extend(score);
+    index_manual.extend(index);
+    bbox_manual.extend(bbox);
+  }
+
+  for (i, (s_cubecl, s_manual)) in score_cubecl.iter().zip(score_manual.iter()).enumerate() {
+    assert!(
+      (s_cubecl - s_manual).abs() < 1e-5,
+      "得分张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      s_cubecl,
+      s_manual
+    );
+  }
+
+  assert_eq!(index_cubecl, index_manual, "类别索引张量不匹配");
+
+  for (i, (b_cubecl, b_manual)) in bbox_cubecl.iter().zip(bbox_manual.iter()).enumerate() {
+    assert!(
+      (b_cubecl - b_manual).abs() < 1e-5,
+      "边界框坐标张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      b_cubecl,
+      b_manual
+    );
+  }
+}
+
+fn test_postprocess_detection_yolo26_levels<R: Runtime>() {
+  // P3/P4/P5 三个层级，输出应为各层级结果按顺序拼接
+  const SIZE: usize = 256;
+  let levels = [(32usize, 8.0f32), (16, 16.0), (8, 32.0)];
+
+  let client = R::client(&R::Device::default());
+  let yolo26 = Yolo26Config::default()
+    .with_shape(SIZE as u32, SIZE as u32)
+    .with_dim(256)
+    .build()
+    .unwrap();
+
+  let mut inputs = Vec::new();
+  let mut score_manual = Vec::new();
+  let mut index_manual = Vec::new();
+  let mut bbox_manual = vec![Vec::new(); 4];
+  for &(grid, stride) in &levels {
+    let hw = grid * grid;
+    let cls: Vec<f32> = (0..N * CLS * hw).map(|_| rand::random::<f32>()).collect();
+    let reg: Vec<f32> = (0..N * 4 * hw).map(|_| rand::random::<f32>()).collect();
+
+    let (score, index, bbox) = run_postprocess_detection_yolo26_level_manual(
+      cls.clone(),
+      reg.clone(),
+      grid,
+      grid,
+      stride,
+      SIZE,
+      SIZE,
+    );
+    score_manual.extend(score);
+    index_manual.extend(index);
+    for (c, coords) in bbox.chunks(hw).enumerate() {
+      bbox_manual[c].extend_from_slice(coords);
+    }
+
+    inputs.push((
+      DataBuffer::<R, f32>::from_slice(&cls, &[N, CLS, grid, grid], &client).unwrap(),
+      DataBuffer::<R, f32>::from_slice(&reg, &[N, 4, grid, grid], &client).unwrap(),
+      stride,
+    ));
+  }
+  let bbox_manual: Vec<f32> = bbox_manual.concat();
+
+  let (score, index, bbox) = yolo26
+    .execute_levels::<R, f32, u32>(&client, inputs)
+    .unwrap();
+  let m = levels.iter().map(|(g, _)| g * g).sum::<usize>();
+  assert_eq!(score.shape(), &[N, m]);
+  assert_eq!(bbox.shape(), &[N, 4, m]);
+
+  let score_cubecl = score.into_vec(&client).unwrap();
+  let index_cubecl = index.into_vec(&client).unwrap();
+  let bbox_cubecl = bbox.into_vec(&client).unwrap();
+
+  for (i, (s_cubecl, s_manual)) in score_cubecl.iter().zip(score_manual.iter()).enumerate() {
+    assert!(
+      (s_cubecl - s_manual).abs() < 1e-5,
+      "得分张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      s_cubecl,
+      s_manual
+    );
+  }
+
+  assert_eq!(index_cubecl, index_manual, "类别索引张量不匹配");
+
+  for (i, (b_cubecl, b_manual)) in bbox_cubecl.iter().zip(bbox_manual.iter()).enumerate() {
+    assert!(
+      (b_cubecl - b_manual).abs() < 1e-5,
+      "边界框坐标张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      b_cubecl,
+      b_manual
+    );
+  }
+}
+
+fn test_postprocess_detection_yolo26_encodings<R: Runtime>() {
+  // 两个层级，回归值放大到 [0, 4) 使部分框超出图像范围以检验裁剪
+  const SIZE: usize = 640;
+  let levels = [(H, 32.0f32), (H / 2, 64.0)];
+  let inputs: Vec<(Vec<f32>, Vec<f32>)> = levels
+    .iter()
+    .map(|&(grid, _)| {
+      let cls = (0..N * CLS * grid * grid)
+        .map(|_| rand::random::<f32>())
+        .collect();
+      let reg = (0..N * 4 * grid * grid)
+        .map(|_| 4.0 * rand::random::<f32>())
+        .collect();
+      (cls, reg)
+    })
+    .collect();
+  let corners: Vec<Vec<[f32; 4]>> = levels
+    .iter()
+    .zip(&inputs)
+    .map(|(&(grid, stride), (_, reg))| yolo26_corners_manual(reg, grid, stride))
+    .collect();
+  let m = corners.iter().map(Vec::len).sum::<usize>();
+
+  let client = R::client(&R::Device::default());
+  for format in [BoxFormat::Xyxy, BoxFormat::Xywh, BoxFormat::Cxcywh] {
+    for unit in [BoxUnit::Normalized, BoxUnit::Pixel] {
+      for clamp in [true, false] {
+        for layout in [BoxLayout::Planar, BoxLayout::Interleaved] {
+          let encoding = BoxEncoding {
+            format,
+            unit,
+            clamp,
+            layout,
+          };
+          let yolo26 = Yolo26Config::default()
+            .with_shape(SIZE as u32, SIZE as u32)
+            .with_box_format(format)
+            .with_box_unit(unit)
+            .with_clamp(clamp)
+            .with_box_layout(layout)
+            .with_dim(256)
+            .build()
+            .unwrap();
+          assert_eq!(yolo26.box_encoding(), encoding);
+
+          let buffers = |level: usize| {
+            let (grid, stride) = levels[level];
+            let (cls, reg) = &inputs[level];
+            (
+              DataBuffer::<R, f32>::from_slice(cls, &[N, CLS, grid, grid], &client).unwrap(),
+              DataBuffer::<R, f32>::from_slice(reg, &[N, 4, grid, grid], &client).unwrap(),
+              stride,
+            )
+          };
+
+          // 单层级
+          let (cls, reg, stride) = buffers(0);
+          let (_, _, bbox) = yolo26
+            .execute::<R, f32, u32>(&client, cls, reg, stride)
+            .unwrap();
+          let expected_shape = match layout {
+            BoxLayout::Planar => [N, 4, H, W],
+            BoxLayout::Interleaved => [N, H, W, 4],
+          };
+          assert_eq!(bbox.shape(), &expected_shape);
+          let expected = encode_manual(&corners[0], encoding, SIZE, SIZE);
+          assert_bbox_close(&bbox.into_vec(&client).unwrap(), &expected, encoding);
+
+          // 多层级拼接
+          let (score, index, bbox) = yolo26
+            .execute_levels::<R, f32, u32>(&client, vec![buffers(0), buffers(1)])
+            .unwrap();
+          let expected_shape = match layout {
+            BoxLayout::Planar => [N, 4, m],
+            BoxLayout::Interleaved => [N, m, 4],
+          };
+          assert_eq!(bbox.shape(), &expected_shape);
+          let all: Vec<[f32; 4]> = corners.concat();
+          let expected = encode_manual(&all, encoding, SIZE, SIZE);
+          let bbox_cubecl = bbox.into_vec(&client).unwrap();
+          assert_bbox_close(&bbox_cubecl, &expected, encoding);
+
+          // 按编码读取后统一为 xyxy
+          let bbox =
+            DataBuffer::<R, f32>::from_slice(&bbox_cubecl, &expected_shape, &client).unwrap();
+          let set =
+            DetectionSet::from_dense_encoded(&client, (score, index, bbox), 0.0, encoding).unwrap();
+          assert_eq!(set.len(), m);
+          assert_eq!(set.unit(), unit);
+          let xyxy = BoxEncoding {
+            format: BoxFormat::Xyxy,
+            layout: BoxLayout::Planar,
+            ..encoding
+          };
+          let expected = encode_manual(&all, xyxy, SIZE, SIZE);
+          for (i, detection) in set.detections().iter().enumerate() {
+            let reference = [
+              expected[i],
+              expected[m + i],
+              expected[2 * m + i],
+              expected[3 * m + i],
+            ];
+            assert_bbox_close(&detection.bbox, &reference, encoding);
+          }
+        }
+      }
+    }
+  }
+}
+
+/// 单张图像各位置在网络输入上的像素角点 (xmin, ymin, xmax, ymax)，未裁剪
+fn yolo26_corners_manual(reg: &[f32], grid: usize, stride: f32) -> Vec<[f32; 4]> {
+  let spatial = grid * grid;
+  (0..spatial)
+    .map(|idx| {
+      let grid_x = (idx % grid) as f32 + 0.5;
+      let grid_y = (idx / grid) as f32 + 0.5;
+      [
+        (grid_x - reg[idx]) * stride,
+        (grid_y - reg[spatial + idx]) * stride,
+        (grid_x + reg[2 * spatial + idx]) * stride,
+        (grid_y + reg[3 * spatial + idx]) * stride,
+      ]
+    })
+    .collect()
+}
+
+/// 按编码输出边界框的 CPU 参考实现
+fn encode_manual(
+  corners: &[[f32; 4]],
+  encoding: BoxEncoding,
+  width: usize,
+  height: usize,
+) -> Vec<f32> {
+  let (width, height) = (width as f32, height as f32);
+  let m = corners.len();
+  let mut output = vec![0.0; 4 * m];
+  for (i, &[mut xmin, mut ymin, mut xmax, mut ymax]) in corners.iter().enumerate() {
+    if encoding.clamp {
+      xmin = xmin.clamp(0.0, width);
+      ymin = ymin.clamp(0.0, height);
+      xmax = xmax.clamp(0.0, width);
+      ymax = ymax.clamp(0.0, height);
+    }
+    if encoding.unit == BoxUnit::Normalized {
+      xmin /= width;
+      ymin /= height;
+      xmax /= width;
+      ymax /= height;
+    }
+    let values = match encoding.format {
+      BoxFormat::Xyxy => [xmin, ymin, xmax, ymax],
+      BoxFormat::Xywh => [xmin, ymin, xmax - xmin, ymax - ymin],
+      BoxFormat::Cxcywh => [
+        (xmin + xmax) / 2.0,
+        (ymin + ymax) / 2.0,
+        xmax - xmin,
+        ymax - ymin,
+      ],
+    };
+    for (c, v) in values.into_iter().enumerate() {
+      match encoding.layout {
+        BoxLayout::Planar => output[c * m + i] = v,
+        BoxLayout::Interleaved => output[i * 4 + c] = v,
+      }
+    }
+  }
+  output
+}
+
+fn assert_bbox_close(cubecl: &[f32], manual: &[f32], encoding: BoxEncoding) {
+  assert_eq!(cubecl.len(), manual.len());
+  for (i, (b_cubecl, b_manual)) in cubecl.iter().zip(manual).enumerate() {
+    assert!(
+      (b_cubecl - b_manual).abs() < 1e-5 * b_manual.abs().max(1.0),
+      "{:?} 边界框坐标第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      encoding,
+      i,
+      b_cubecl,
+      b_manual
+    );
+  }
+}
+
 fn run_postprocess_detection_yolo26_cubecl<R: Runtime>(
   cls: Vec<f32>,
   reg: Vec<f32>,
@@ -124,23 +493,35 @@ fn run_postprocess_detection_yolo26_manual(
   stride: f32,
   width: usize,
   height: usize,
+) -> (Vec<f32>, Vec<u32>, Vec<f32>) {
+  run_postprocess_detection_yolo26_level_manual(cls, reg, H, W, stride, width, height)
+}
+
+fn run_postprocess_detection_yolo26_level_manual(
+  cls: Vec<f32>,
+  reg: Vec<f32>,
+  grid_h: usize,
+  grid_w: usize,
+  stride: f32,
+  width: usize,
+  height: usize,
 ) -> (Vec<f32>, Vec<u32>, Vec<f32>) {
   assert_eq!(N, 1);
 
-  let mut score_tensor = vec![0.0; N * H * W];
-  let mut index_tensor = vec![0u32; N * H * W];
-  let mut bbox_tensor = vec![0.0; N * 4 * H * W];
+  let spatial = grid_h * grid_w;
 
-  let spatial = H * W;
+  let mut score_tensor = vec![0.0; N * spatial];
+  let mut index_tensor = vec![0u32; N * spatial];
+  let mut bbox_tensor = vec![0.0; N * 4 * spatial];
 
-  for h in 0..H {
-    for w in 0..W {
-      let idx = h * W + w;
+  for h in 0..grid_h {
+    for w in 0..grid_w {
+      let idx = h * grid_w + w;
 
       let (score, class_id) = {
         let mut max_logit = f32::MIN;
         let mut cls_idx = 0usize;
-        for c in 0..CLS as usize {
+        for c in 0..CLS {
           let logit = cls[c * spatial + idx];
           if logit > max_logit {
             max_logit = logit;
@@ -167,9 +548,9 @@ fn run_postprocess_detection_yolo26_manual(
       let ymax = ((grid_y + ch) * stride).clamp(0.0, height as f32);
 
       bbox_tensor[idx] = (xmin / width as f32).clamp(0.0, 1.0);
-      bbox_tensor[idx + H * W] = (ymin / height as f32).clamp(0.0, 1.0);
-      bbox_tensor[idx + 2 * H * W] = (xmax / width as f32).clamp(0.0, 1.0);
-      bbox_tensor[idx + 3 * H * W] = (ymax / height as f32).clamp(0.0, 1.0);
+      bbox_tensor[idx + spatial] = (ymin / height as f32).clamp(0.0, 1.0);
+      bbox_tensor[idx + 2 * spatial] = (xmax / width as f32).clamp(0.0, 1.0);
+      bbox_tensor[idx + 3 * spatial] = (ymax / height as f32).clamp(0.0, 1.0);
     }
   }
 
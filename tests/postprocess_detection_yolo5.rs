@@ -0,0 +1,222 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_yolo5.rs - YOLOv5/v7 锚框后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{data::DataBuffer, postprocess::detection::Yolo5Config};
+
+const N: usize = 2;
+const CLS: usize = 8;
+const A: usize = 3;
+
+const ANCHORS: [[[f32; 2]; A]; 2] = [
+  [[10.0, 13.0], [16.0, 30.0], [33.0, 23.0]],
+  [[30.0, 61.0], [62.0, 45.0], [59.0, 119.0]],
+];
+// 两个层级的 (H, W, stride)
+const LEVELS: [(usize, usize, f32); 2] = [(16, 16, 8.0), (8, 8, 16.0)];
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_yolo5_cpu() {
+  test_postprocess_detection_yolo5::<cubecl::cpu::CpuRuntime>();
+  test_postprocess_detection_yolo5_levels::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_yolo5_wgpu() {
+  test_postprocess_detection_yolo5::<cubecl::wgpu::WgpuRuntime>();
+  test_postprocess_detection_yolo5_levels::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn random_pred(h: usize, w: usize) -> Vec<f32> {
+  (0..N * A * (5 + CLS) * h * w)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect()
+}
+
+fn build_yolo5() -> shanan_cv::postprocess::detection::Yolo5 {
+  Yolo5Config::default()
+    .with_shape(256, 256)
+    .with_anchors(ANCHORS.iter().map(|a| a.to_vec()).collect())
+    .with_dim(256)
+    .build()
+    .unwrap()
+}
+
+fn assert_close(name: &str, cubecl: &[f32], manual: &[f32]) {
+  assert_eq!(cubecl.len(), manual.len());
+  for (i, (c, m)) in cubecl.iter().zip(manual.iter()).enumerate() {
+    assert!(
+      (c - m).abs() < 1e-5,
+      "{}第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      name,
+      i,
+      c,
+      m
+    );
+  }
+}
+
+fn test_postprocess_detection_yolo5<R: Runtime>() {
+  let (h, w, stride) = LEVELS[0];
+  let pred = random_pred(h, w);
+
+  let client = R::client(&R::Device::default());
+  let yolo5 = build_yolo5();
+
+  let pred_buf =
+    DataBuffer::<R, f32>::from_slice(&pred, &[N, A * (5 + CLS), h, w], &client).unwrap();
+  let (score, index, bbox) = yolo5
+    .execute::<R, f32, u32>(&client, pred_buf, 0, stride)
+    .unwrap();
+  assert_eq!(score.shape(), &[N, A, h, w]);
+  assert_eq!(bbox.shape(), &[N, 4, A, h, w]);
+
+  let (score_manual, index_manual, bbox_manual) =
+    run_postprocess_detection_yolo5_manual(&pred, h, w, stride, &ANCHORS[0], 256, 256);
+
+  assert_close("得分张量", &score.into_vec(&client).unwrap(), &score_manual);
+  assert_eq!(
+    index.into_vec(&client).unwrap(),
+    index_manual,
+    "类别索引张量不匹配"
+  );
+  assert_close(
+    "边界框坐标张量",
+    &bbox.into_vec(&client).unwrap(),
+    &bbox_manual,
+  );
+}
+
+fn test_postprocess_detection_yolo5_levels<R: Runtime>() {
+  let preds: Vec<Vec<f32>> = LEVELS.iter().map(|&(h, w, _)| random_pred(h, w)).collect();
+
+  let client = R::client(&R::Device::default());
+  let yolo5 = build_yolo5();
+
+  let levels = LEVELS
+    .iter()
+    .zip(preds.iter())
+    .map(|(&(h, w, stride), pred)| {
+      (
+        DataBuffer::<R, f32>::from_slice(pred, &[N, A * (5 + CLS), h, w], &client).unwrap(),
+        stride,
+      )
+    })
+    .collect();
+  let (score, index, bbox) = yolo5
+    .execute_levels::<R, f32, u32>(&client, levels)
+    .unwrap();
+
+  let m: usize = LEVELS.iter().map(|&(h, w, _)| A * h * w).sum();
+  assert_eq!(score.shape(), &[N, m]);
+  assert_eq!(bbox.shape(), &[N, 4, m]);
+
+  // 逐层级计算参考结果，再按 [N, M] / [N, 4, M] 拼接
+  let manual: Vec<_> = LEVELS
+    .iter()
+    .zip(preds.iter())
+    .zip(ANCHORS.iter())
+    .map(|((&(h, w, stride), pred), anchors)| {
+      run_postprocess_detection_yolo5_manual(pred, h, w, stride, anchors, 256, 256)
+    })
+    .collect();
+  let mut score_manual = Vec::new();
+  let mut index_manual = Vec::new();
+  let mut bbox_manual = Vec::new();
+  for n in 0..N {
+    for (s, i, _) in &manual {
+      let size = s.len() / N;
+      score_manual.extend_from_slice(&s[n * size..(n + 1) * size]);
+      index_manual.extend_from_slice(&i[n * size..(n + 1) * size]);
+    }
+    for c in 0..4 {
+      for (s, _, b) in &manual {
+        let size = s.len() / N;
+        let begin = n * 4 * size + c * size;
+        bbox_manual.extend_from_slice(&b[begin..begin + size]);
+      }
+    }
+  }
+
+  assert_close("得分张量", &score.into_vec(&client).unwrap(), &score_manual);
+  assert_eq!(
+    index.into_vec(&client).unwrap(),
+    index_manual,
+    "类别索引张量不匹配"
+  );
+  assert_close(
+    "边界框坐标张量",
+    &bbox.into_vec(&client).unwrap(),
+    &bbox_manual,
+  );
+}
+
+fn run_postprocess_detection_yolo5_manual(
+  pred: &[f32],
+  h_dim: usize,
+  w_dim: usize,
+  stride: f32,
+  anchors: &[[f32; 2]],
+  width: usize,
+  height: usize,
+) -> (Vec<f32>, Vec<u32>, Vec<f32>) {
+  let hw = h_dim * w_dim;
+  let per = 5 + CLS;
+  let size = A * hw;
+
+  let mut score_tensor = vec![0.0; N * size];
+  let mut index_tensor = vec![0u32; N * size];
+  let mut bbox_tensor = vec![0.0; N * 4 * size];
+
+  for n in 0..N {
+    for (a, anchor) in anchors.iter().enumerate() {
+      for h in 0..h_dim {
+        for w in 0..w_dim {
+          let at = |c: usize| sigmoid(pred[((n * A + a) * per + c) * hw + h * w_dim + w]);
+
+          let mut best = at(5);
+          let mut best_c = 0;
+          for c in 1..CLS {
+            if at(5 + c) > best {
+              best = at(5 + c);
+              best_c = c;
+            }
+          }
+
+          let cx = (2.0 * at(0) - 0.5 + w as f32) * stride;
+          let cy = (2.0 * at(1) - 0.5 + h as f32) * stride;
+          let bw = (2.0 * at(2)).powi(2) * anchor[0];
+          let bh = (2.0 * at(3)).powi(2) * anchor[1];
+
+          let i = a * hw + h * w_dim + w;
+          score_tensor[n * size + i] = at(4) * best;
+          index_tensor[n * size + i] = best_c as u32;
+
+          let base = n * 4 * size + i;
+          bbox_tensor[base] = ((cx - bw / 2.0) / width as f32).clamp(0.0, 1.0);
+          bbox_tensor[base + size] = ((cy - bh / 2.0) / height as f32).clamp(0.0, 1.0);
+          bbox_tensor[base + 2 * size] = ((cx + bw / 2.0) / width as f32).clamp(0.0, 1.0);
+          bbox_tensor[base + 3 * size] = ((cy + bh / 2.0) / height as f32).clamp(0.0, 1.0);
+        }
+      }
+    }
+  }
+
+  (score_tensor, index_tensor, bbox_tensor)
+}
+
+fn sigmoid(x: f32) -> f32 {
+  1.0 / (1.0 + (-x).exp())
+}
@@ -0,0 +1,160 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_detection_yolo8.rs - YOLOv8 DFL 后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{data::DataBuffer, postprocess::detection::Yolo8Config};
+
+const N: usize = 1;
+const CLS: usize = 8;
+const REG_MAX: usize = 16;
+const H: usize = 20;
+const W: usize = 20;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_detection_yolo8_cpu() {
+  test_postprocess_detection_yolo8::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_detection_yolo8_wgpu() {
+  test_postprocess_detection_yolo8::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_detection_yolo8<R: Runtime>() {
+  let random_cls: Vec<f32> = (0..N * CLS * H * W)
+    .map(|_| rand::random::<f32>())
+    .collect();
+  // 分布 logits 取值范围放大，使 softmax 更接近真实模型输出
+  let random_reg: Vec<f32> = (0..N * 4 * REG_MAX * H * W)
+    .map(|_| rand::random::<f32>() * 8.0 - 4.0)
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let yolo8 = Yolo8Config::default()
+    .with_shape(640, 640)
+    .with_reg_max(REG_MAX as u32)
+    .with_dim(256)
+    .build()
+    .unwrap();
+
+  let cls = DataBuffer::<R, f32>::from_slice(&random_cls, &[N, CLS, H, W], &client).unwrap();
+  let reg =
+    DataBuffer::<R, f32>::from_slice(&random_reg, &[N, 4 * REG_MAX, H, W], &client).unwrap();
+  let (score, index, bbox) = yolo8
+    .execute::<R, f32, u32>(&client, cls, reg, 32.0)
+    .unwrap();
+  assert_eq!(bbox.shape(), &[N, 4, H, W]);
+
+  let score_cubecl = score.into_vec(&client).unwrap();
+  let index_cubecl = index.into_vec(&client).unwrap();
+  let bbox_cubecl = bbox.into_vec(&client).unwrap();
+
+  let (score_manual, index_manual, bbox_manual) =
+    run_postprocess_detection_yolo8_manual(&random_cls, &random_reg, 32.0, 640, 640);
+
+  for (i, (s_cubecl, s_manual)) in score_cubecl.iter().zip(score_manual.iter()).enumerate() {
+    assert!(
+      (s_cubecl - s_manual).abs() < 1e-5,
+      "得分张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      s_cubecl,
+      s_manual
+    );
+  }
+
+  assert_eq!(index_cubecl, index_manual, "类别索引张量不匹配");
+
+  for (i, (b_cubecl, b_manual)) in bbox_cubecl.iter().zip(bbox_manual.iter()).enumerate() {
+    assert!(
+      (b_cubecl - b_manual).abs() < 1e-5,
+      "边界框坐标张量第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      b_cubecl,
+      b_manual
+    );
+  }
+}
+
+fn run_postprocess_detection_yolo8_manual(
+  cls: &[f32],
+  reg: &[f32],
+  stride: f32,
+  width: usize,
+  height: usize,
+) -> (Vec<f32>, Vec<u32>, Vec<f32>) {
+  let spatial = H * W;
+
+  let mut score_tensor = vec![0.0; N * spatial];
+  let mut index_tensor = vec![0u32; N * spatial];
+  let mut bbox_tensor = vec![0.0; N * 4 * spatial];
+
+  for h in 0..H {
+    for w in 0..W {
+      let idx = h * W + w;
+
+      let (score, class_id) = {
+        let mut max_logit = f32::MIN;
+        let mut cls_idx = 0usize;
+        for c in 0..CLS {
+          let logit = cls[c * spatial + idx];
+          if logit > max_logit {
+            max_logit = logit;
+            cls_idx = c;
+          }
+        }
+        (sigmoid(max_logit), cls_idx as u32)
+      };
+
+      score_tensor[idx] = score;
+      index_tensor[idx] = class_id;
+
+      // DFL: 对每条边的分布做 softmax 后求期望
+      let mut dist = [0.0f32; 4];
+      for (side, d) in dist.iter_mut().enumerate() {
+        let logits: Vec<f32> = (0..REG_MAX)
+          .map(|i| reg[(side * REG_MAX + i) * spatial + idx])
+          .collect();
+        let max = logits.iter().cloned().fold(f32::MIN, f32::max);
+        let exp: Vec<f32> = logits.iter().map(|l| (l - max).exp()).collect();
+        let sum: f32 = exp.iter().sum();
+        *d = exp
+          .iter()
+          .enumerate()
+          .map(|(i, e)| i as f32 * e)
+          .sum::<f32>()
+          / sum;
+      }
+
+      let grid_x = (w as f32) + 0.5;
+      let grid_y = (h as f32) + 0.5;
+
+      let xmin = (grid_x - dist[0]) * stride;
+      let ymin = (grid_y - dist[1]) * stride;
+      let xmax = (grid_x + dist[2]) * stride;
+      let ymax = (grid_y + dist[3]) * stride;
+
+      bbox_tensor[idx] = (xmin / width as f32).clamp(0.0, 1.0);
+      bbox_tensor[idx + spatial] = (ymin / height as f32).clamp(0.0, 1.0);
+      bbox_tensor[idx + 2 * spatial] = (xmax / width as f32).clamp(0.0, 1.0);
+      bbox_tensor[idx + 3 * spatial] = (ymax / height as f32).clamp(0.0, 1.0);
+    }
+  }
+
+  (score_tensor, index_tensor, bbox_tensor)
+}
+
+fn sigmoid(x: f32) -> f32 {
+  1.0 / (1.0 + (-x).exp())
+}
@@ -0,0 +1,249 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_face.rs - SCRFD 人脸检测与关键点后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{
+    face::{ScrfdConfig, ScrfdLayout},
+    nms::NmsConfig,
+  },
+};
+
+const N: usize = 2;
+const A: usize = 2;
+const L: usize = 5;
+const SIZE: f32 = 128.0;
+const LEVELS: [(usize, f32); 2] = [(16, 8.0), (8, 16.0)];
+const D: usize = 20;
+
+struct Level {
+  grid: usize,
+  stride: f32,
+  score: Vec<f32>,
+  bbox: Vec<f32>,
+  kps: Vec<f32>,
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_face_cpu() {
+  test_postprocess_face::<cubecl::cpu::CpuRuntime>(ScrfdLayout::Native);
+  test_postprocess_face::<cubecl::cpu::CpuRuntime>(ScrfdLayout::Nchw);
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_face_wgpu() {
+  test_postprocess_face::<cubecl::wgpu::WgpuRuntime>(ScrfdLayout::Native);
+  test_postprocess_face::<cubecl::wgpu::WgpuRuntime>(ScrfdLayout::Nchw);
+}
+
+fn test_postprocess_face<R: Runtime>(layout: ScrfdLayout) {
+  let levels: Vec<Level> = LEVELS
+    .iter()
+    .map(|&(grid, stride)| {
+      let hw = grid * grid;
+      Level {
+        grid,
+        stride,
+        score: (0..N * A * hw).map(|_| rand::random::<f32>()).collect(),
+        bbox: (0..N * A * 4 * hw)
+          .map(|_| 0.5 + rand::random::<f32>() * 2.0)
+          .collect(),
+        kps: (0..N * A * L * 2 * hw)
+          .map(|_| rand::random::<f32>() * 2.0 - 1.0)
+          .collect(),
+      }
+    })
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  let scrfd = ScrfdConfig::default()
+    .with_shape(SIZE as u32, SIZE as u32)
+    .with_layout(layout)
+    .with_dim(64)
+    .build()
+    .unwrap();
+  let nms = NmsConfig::default()
+    .with_score_threshold(0.5)
+    .with_iou_threshold(0.4)
+    .with_max_detections(D as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  // Native 布局将 [N, A * C, H, W] 重排为 [N, H * W * A, C]，batch 为 None 时只取第一张图像且不带批次维
+  let head = |data: &[f32], c: usize, g: usize, batch: Option<usize>| match layout {
+    ScrfdLayout::Native => {
+      let rows = g * g * A;
+      let data = to_native(data, c, g);
+      match batch {
+        Some(n) => DataBuffer::<R, f32>::from_slice(&data, &[n, rows, c], &client).unwrap(),
+        None => DataBuffer::<R, f32>::from_slice(&data[..rows * c], &[rows, c], &client).unwrap(),
+      }
+    }
+    ScrfdLayout::Nchw => {
+      DataBuffer::<R, f32>::from_slice(data, &[N, A * c, g, g], &client).unwrap()
+    }
+  };
+  let buffers = |levels: &[Level], batch: Option<usize>| {
+    levels
+      .iter()
+      .map(|l| {
+        let g = l.grid;
+        (
+          head(&l.score, 1, g, batch),
+          head(&l.bbox, 4, g, batch),
+          head(&l.kps, L * 2, g, batch),
+          l.stride,
+        )
+      })
+      .collect::<Vec<_>>()
+  };
+
+  let (score, index, bbox, landmarks) = scrfd
+    .decode_levels::<R, f32, u32>(&client, buffers(&levels, Some(N)))
+    .unwrap();
+  let m = score.shape()[1];
+  assert_eq!(landmarks.shape(), &[N, L, 2, m]);
+
+  let score = score.into_vec(&client).unwrap();
+  let index = index.into_vec(&client).unwrap();
+  let bbox = bbox.into_vec(&client).unwrap();
+  let landmarks = landmarks.into_vec(&client).unwrap();
+
+  let (expected_score, expected_bbox, expected_landmarks) = decode_manual(&levels);
+  assert_eq!(score, expected_score);
+  assert!(index.iter().all(|&i| i == 0));
+  for (i, (c, e)) in bbox.iter().zip(expected_bbox.iter()).enumerate() {
+    assert!((c - e).abs() < 1e-5, "边界框第 {} 个元素不匹配", i);
+  }
+  for (i, (c, e)) in landmarks.iter().zip(expected_landmarks.iter()).enumerate() {
+    assert!((c - e).abs() < 1e-5, "关键点第 {} 个元素不匹配", i);
+  }
+
+  if layout == ScrfdLayout::Native {
+    // 不带批次维的 [H * W * A, C] 输入按单张图像解码
+    let (single_score, _, single_bbox, single_landmarks) = scrfd
+      .decode_levels::<R, f32, u32>(&client, buffers(&levels, None))
+      .unwrap();
+    assert_eq!(single_score.shape(), &[1, m]);
+    assert_eq!(single_score.into_vec(&client).unwrap(), score[..m]);
+    assert_eq!(single_bbox.into_vec(&client).unwrap(), bbox[..4 * m]);
+    assert_eq!(
+      single_landmarks.into_vec(&client).unwrap(),
+      landmarks[..L * 2 * m]
+    );
+
+    // 行数与输入尺寸和 stride 不符时报错
+    let wrong = levels
+      .iter()
+      .map(|l| Level {
+        grid: l.grid,
+        stride: l.stride * 2.0,
+        score: l.score.clone(),
+        bbox: l.bbox.clone(),
+        kps: l.kps.clone(),
+      })
+      .collect::<Vec<_>>();
+    assert!(
+      scrfd
+        .decode_levels::<R, f32, u32>(&client, buffers(&wrong, Some(N)))
+        .is_err()
+    );
+  }
+
+  // NMS 之后的关键点与保留框在稠密结果中的位置一致
+  let (faces, kept_landmarks) = scrfd
+    .execute::<R, f32, u32>(&client, buffers(&levels, Some(N)), &nms)
+    .unwrap();
+  assert_eq!(kept_landmarks.shape(), &[N, L, 2, D]);
+  let count = faces.count.into_vec(&client).unwrap();
+  let source = faces.source.into_vec(&client).unwrap();
+  let kept_bbox = faces.bbox.into_vec(&client).unwrap();
+  let kept_landmarks = kept_landmarks.into_vec(&client).unwrap();
+  for n in 0..N {
+    assert!(count[n] > 0);
+    for d in 0..D {
+      let src = source[n * D + d] as usize;
+      for c in 0..L * 2 {
+        let kept = kept_landmarks[(n * L * 2 + c) * D + d];
+        if d < count[n] as usize {
+          assert_eq!(kept, landmarks[(n * L * 2 + c) * m + src]);
+        } else {
+          assert_eq!(kept, 0.0);
+        }
+      }
+      if d < count[n] as usize {
+        for c in 0..4 {
+          assert_eq!(kept_bbox[(n * 4 + c) * D + d], bbox[(n * 4 + c) * m + src]);
+        }
+      }
+    }
+  }
+}
+
+fn decode_manual(levels: &[Level]) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+  let m: usize = levels.iter().map(|l| A * l.grid * l.grid).sum();
+  let mut score = vec![0.0; N * m];
+  let mut bbox = vec![0.0; N * 4 * m];
+  let mut landmarks = vec![0.0; N * L * 2 * m];
+  for n in 0..N {
+    let mut offset = 0;
+    for level in levels {
+      let g = level.grid;
+      let hw = g * g;
+      for a in 0..A {
+        for y in 0..g {
+          for x in 0..g {
+            let rem = y * g + x;
+            let pos = offset + a * hw + rem;
+            let (cx, cy) = (x as f32 * level.stride, y as f32 * level.stride);
+            score[n * m + pos] = level.score[(n * A + a) * hw + rem];
+
+            let at = |c: usize| level.bbox[((n * A + a) * 4 + c) * hw + rem] * level.stride;
+            let b = [cx - at(0), cy - at(1), cx + at(2), cy + at(3)];
+            for (c, v) in b.iter().enumerate() {
+              bbox[(n * 4 + c) * m + pos] = (v / SIZE).clamp(0.0, 1.0);
+            }
+
+            for c in 0..L * 2 {
+              let center = if c % 2 == 0 { cx } else { cy };
+              let v = center + level.kps[((n * A + a) * L * 2 + c) * hw + rem] * level.stride;
+              landmarks[(n * L * 2 + c) * m + pos] = (v / SIZE).clamp(0.0, 1.0);
+            }
+          }
+        }
+      }
+      offset += A * hw;
+    }
+  }
+  (score, bbox, landmarks)
+}
+
+/// 将 [N, A * C, H, W] 重排为官方导出的 [N, H * W * A, C]
+fn to_native(data: &[f32], c: usize, g: usize) -> Vec<f32> {
+  let hw = g * g;
+  let mut native = vec![0.0; data.len()];
+  for n in 0..N {
+    for a in 0..A {
+      for ch in 0..c {
+        for p in 0..hw {
+          native[((n * hw + p) * A + a) * c + ch] = data[((n * A + a) * c + ch) * hw + p];
+        }
+      }
+    }
+  }
+  native
+}
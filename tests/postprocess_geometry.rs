@@ -0,0 +1,27 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_geometry.rs - 主机端边界框几何计算测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use shanan_cv::postprocess::geometry::{box_area, box_intersection, box_iou};
+
+#[test]
+fn test_box_iou() {
+  let a = [0.0, 0.0, 10.0, 10.0];
+  let b = [5.0, 0.0, 15.0, 10.0];
+  assert_eq!(box_area(a), 100.0);
+  assert_eq!(box_area([10.0, 0.0, 0.0, 10.0]), 0.0);
+  assert_eq!(box_intersection(a, b), 50.0);
+  assert!((box_iou(a, b) - 50.0 / 150.0).abs() < 1e-6);
+  assert_eq!(box_iou(a, a), 1.0);
+  assert_eq!(box_iou(a, [20.0, 20.0, 30.0, 30.0]), 0.0);
+  assert_eq!(box_iou([0.0; 4], [0.0; 4]), 0.0);
+}
@@ -0,0 +1,193 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_instance.rs - 实例分割掩码组装测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{
+    detection::{BoxUnit, Candidates},
+    instance::MaskAssemblyConfig,
+  },
+};
+
+const N: usize = 2;
+const P: usize = 4;
+const K: usize = 3;
+// 两个层级的系数 (H, W)
+const LEVELS: [(usize, usize); 2] = [(8, 8), (4, 4)];
+const PH: usize = 8;
+const PW: usize = 8;
+const OH: usize = 32;
+const OW: usize = 24;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_instance_cpu() {
+  test_postprocess_instance::<cubecl::cpu::CpuRuntime>(false, BoxUnit::Normalized);
+  test_postprocess_instance::<cubecl::cpu::CpuRuntime>(true, BoxUnit::Normalized);
+  test_postprocess_instance::<cubecl::cpu::CpuRuntime>(false, BoxUnit::Pixel);
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_instance_wgpu() {
+  test_postprocess_instance::<cubecl::wgpu::WgpuRuntime>(false, BoxUnit::Normalized);
+  test_postprocess_instance::<cubecl::wgpu::WgpuRuntime>(true, BoxUnit::Normalized);
+  test_postprocess_instance::<cubecl::wgpu::WgpuRuntime>(false, BoxUnit::Pixel);
+}
+
+fn test_postprocess_instance<R: Runtime>(binary: bool, unit: BoxUnit) {
+  let m: usize = LEVELS.iter().map(|&(h, w)| h * w).sum();
+  let coefs: Vec<Vec<f32>> = LEVELS
+    .iter()
+    .map(|&(h, w)| {
+      (0..N * P * h * w)
+        .map(|_| rand::random::<f32>() * 2.0 - 1.0)
+        .collect()
+    })
+    .collect();
+  let proto: Vec<f32> = (0..N * P * PH * PW)
+    .map(|_| rand::random::<f32>() * 4.0 - 2.0)
+    .collect();
+
+  // 第 0 张图像有 3 个检测结果 (跨两个层级)，第 1 张图像有 2 个，
+  // 其中第 2 个的 source 超出所有层级，系数应为 0
+  let count = vec![3u32, 2];
+  let source: Vec<u32> = vec![5, 70, 63, (m - 1) as u32, (m + 10) as u32, 0];
+  let mut bbox = vec![0.0f32; N * 4 * K];
+  for n in 0..N {
+    for k in 0..K {
+      let x0 = 0.5 * rand::random::<f32>();
+      let y0 = 0.5 * rand::random::<f32>();
+      bbox[n * 4 * K + k] = x0;
+      bbox[n * 4 * K + K + k] = y0;
+      bbox[n * 4 * K + 2 * K + k] = x0 + 0.1 + 0.4 * rand::random::<f32>();
+      bbox[n * 4 * K + 3 * K + k] = y0 + 0.1 + 0.4 * rand::random::<f32>();
+    }
+  }
+
+  // 像素坐标的边界框相对于输出尺寸
+  let input_bbox: Vec<f32> = match unit {
+    BoxUnit::Normalized => bbox.clone(),
+    BoxUnit::Pixel => bbox
+      .iter()
+      .enumerate()
+      .map(|(i, &b)| {
+        // 第 0/2 个分量为 x，第 1/3 个为 y
+        match i / K % 4 {
+          0 | 2 => b * OW as f32,
+          _ => b * OH as f32,
+        }
+      })
+      .collect(),
+  };
+
+  let client = R::client(&R::Device::default());
+  let candidates = Candidates::<R, f32, u32> {
+    score: DataBuffer::from_slice(&[0.9; N * K], &[N, K], &client).unwrap(),
+    index: DataBuffer::from_slice(&[0; N * K], &[N, K], &client).unwrap(),
+    bbox: DataBuffer::from_slice(&input_bbox, &[N, 4, K], &client).unwrap(),
+    source: DataBuffer::from_slice(&source, &[N, K], &client).unwrap(),
+    count: DataBuffer::from_slice(&count, &[N], &client).unwrap(),
+  };
+  let assembly = MaskAssemblyConfig::default()
+    .with_shape(OW as u32, OH as u32)
+    .with_binary(binary)
+    .with_threshold(0.5)
+    .with_box_unit(unit)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let coef_bufs = LEVELS
+    .iter()
+    .zip(coefs.iter())
+    .map(|(&(h, w), c)| DataBuffer::<R, f32>::from_slice(c, &[N, P, h, w], &client).unwrap())
+    .collect();
+  let proto_buf = DataBuffer::<R, f32>::from_slice(&proto, &[N, P, PH, PW], &client).unwrap();
+  let masks = assembly
+    .execute_levels(&client, coef_bufs, proto_buf, &candidates)
+    .unwrap();
+  assert_eq!(masks.shape(), &[N, K, OH, OW]);
+  let masks = masks.into_vec(&client).unwrap();
+
+  // 参考实现: 按拼接后的位置取系数
+  let coef_at = |n: usize, p: usize, src: usize| {
+    let mut pos = src;
+    for (&(h, w), c) in LEVELS.iter().zip(coefs.iter()) {
+      if pos < h * w {
+        return c[(n * P + p) * h * w + pos];
+      }
+      pos -= h * w;
+    }
+    0.0
+  };
+  let proto_mask = |n: usize, k: usize, y: usize, x: usize| {
+    let src = source[n * K + k] as usize;
+    let logit: f32 = (0..P)
+      .map(|p| coef_at(n, p, src) * proto[((n * P + p) * PH + y) * PW + x])
+      .sum();
+    1.0 / (1.0 + (-logit).exp())
+  };
+
+  for n in 0..N {
+    for k in 0..K {
+      let b = |c: usize| bbox[n * 4 * K + c * K + k];
+      for y in 0..OH {
+        for x in 0..OW {
+          let u = (x as f32 + 0.5) / OW as f32;
+          let v = (y as f32 + 0.5) / OH as f32;
+          let inside = k < count[n] as usize && u >= b(0) && v >= b(1) && u < b(2) && v < b(3);
+
+          let soft = if inside {
+            let sx = (u * PW as f32 - 0.5).clamp(0.0, (PW - 1) as f32);
+            let sy = (v * PH as f32 - 0.5).clamp(0.0, (PH - 1) as f32);
+            let (x0, y0) = (sx.floor() as usize, sy.floor() as usize);
+            let (x1, y1) = ((x0 + 1).min(PW - 1), (y0 + 1).min(PH - 1));
+            let (fx, fy) = (sx - x0 as f32, sy - y0 as f32);
+            let top = proto_mask(n, k, y0, x0) * (1.0 - fx) + proto_mask(n, k, y0, x1) * fx;
+            let bottom = proto_mask(n, k, y1, x0) * (1.0 - fx) + proto_mask(n, k, y1, x1) * fx;
+            top * (1.0 - fy) + bottom * fy
+          } else {
+            0.0
+          };
+
+          let actual = masks[((n * K + k) * OH + y) * OW + x];
+          if binary {
+            // 阈值附近的浮点误差可能导致个别像素翻转，跳过这些位置
+            if (soft - 0.5).abs() < 1e-5 {
+              continue;
+            }
+            let expected = if soft > 0.5 { 1.0 } else { 0.0 };
+            assert_eq!(
+              actual, expected,
+              "二值掩码 ({}, {}, {}, {}) 不匹配",
+              n, k, y, x
+            );
+          } else {
+            assert!(
+              (actual - soft).abs() < 1e-5,
+              "掩码 ({}, {}, {}, {}) 不匹配: cubecl = {}, manual = {}",
+              n,
+              k,
+              y,
+              x,
+              actual,
+              soft
+            );
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,222 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_letterbox.rs - 检测框坐标反投影测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::{
+    detection::{BoxEncoding, BoxFormat, BoxLayout, BoxUnit},
+    letterbox::{BackProjectConfig, Letterbox},
+  },
+};
+
+const N: usize = 2;
+const M: usize = 64;
+const INPUT: u32 = 640;
+
+#[test]
+fn test_letterbox_fit() {
+  let letterbox = Letterbox::fit(1920, 1080, INPUT, INPUT);
+  assert!((letterbox.scale_x - 1.0 / 3.0).abs() < 1e-6);
+  assert_eq!(letterbox.scale_x, letterbox.scale_y);
+  assert!(letterbox.pad_x.abs() < 1e-4);
+  assert!((letterbox.pad_y - 140.0).abs() < 1e-4);
+
+  let stretch = Letterbox::stretch(1280, 320, INPUT, INPUT);
+  assert_eq!(stretch.scale_x, 0.5);
+  assert_eq!(stretch.scale_y, 2.0);
+  assert_eq!(stretch.pad_x, 0.0);
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_letterbox_cpu() {
+  test_postprocess_letterbox::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_letterbox_wgpu() {
+  test_postprocess_letterbox::<cubecl::wgpu::WgpuRuntime>();
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_letterbox_encodings_cpu() {
+  test_postprocess_letterbox_encodings::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_letterbox_encodings_wgpu() {
+  test_postprocess_letterbox_encodings::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_letterbox<R: Runtime>() {
+  let bbox: Vec<f32> = (0..N * 4 * M).map(|_| rand::random::<f32>()).collect();
+  let letterbox = [
+    Letterbox::fit(1920, 1080, INPUT, INPUT),
+    Letterbox::fit(480, 640, INPUT, INPUT),
+  ];
+
+  let client = R::client(&R::Device::default());
+  let project = BackProjectConfig::default()
+    .with_shape(INPUT, INPUT)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox, &[N, 4, M], &client).unwrap();
+  let output = project.execute(&client, bbox_buf, &letterbox).unwrap();
+  assert_eq!(output.shape(), &[N, 4, M]);
+  let output = output.into_vec(&client).unwrap();
+
+  for (n, lb) in letterbox.iter().enumerate() {
+    for c in 0..4 {
+      let (scale, pad, limit) = if c % 2 == 0 {
+        (lb.scale_x, lb.pad_x, lb.source_width as f32)
+      } else {
+        (lb.scale_y, lb.pad_y, lb.source_height as f32)
+      };
+      for i in 0..M {
+        let idx = n * 4 * M + c * M + i;
+        let expected = ((bbox[idx] * INPUT as f32 - pad) / scale).clamp(0.0, limit);
+        assert!(
+          (output[idx] - expected).abs() < 1e-2,
+          "第 {} 张图像第 {} 个框坐标不匹配: cubecl = {}, manual = {}",
+          n,
+          i,
+          output[idx],
+          expected
+        );
+      }
+    }
+  }
+  // 原始图像或网络输入尺寸为 0 时参数无效
+  for invalid in [
+    Letterbox::fit(0, 1080, INPUT, INPUT),
+    Letterbox::stretch(1920, 0, INPUT, INPUT),
+    Letterbox::fit(1920, 1080, 0, 0),
+  ] {
+    let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox, &[N, 4, M], &client).unwrap();
+    assert!(project.execute(&client, bbox_buf, &[invalid]).is_err());
+  }
+}
+
+/// 将 xyxy 边界框按编码写为 [N, 4, M] 或 [N, M, 4]
+fn encode(boxes: &[[f32; 4]], encoding: BoxEncoding) -> Vec<f32> {
+  let mut output = vec![0.0; N * 4 * M];
+  for (j, &[xmin, ymin, xmax, ymax]) in boxes.iter().enumerate() {
+    let (n, i) = (j / M, j % M);
+    let values = match encoding.format {
+      BoxFormat::Xyxy => [xmin, ymin, xmax, ymax],
+      BoxFormat::Xywh => [xmin, ymin, xmax - xmin, ymax - ymin],
+      BoxFormat::Cxcywh => [
+        (xmin + xmax) / 2.0,
+        (ymin + ymax) / 2.0,
+        xmax - xmin,
+        ymax - ymin,
+      ],
+    };
+    for (c, v) in values.into_iter().enumerate() {
+      match encoding.layout {
+        BoxLayout::Planar => output[(n * 4 + c) * M + i] = v,
+        BoxLayout::Interleaved => output[(n * M + i) * 4 + c] = v,
+      }
+    }
+  }
+  output
+}
+
+fn test_postprocess_letterbox_encodings<R: Runtime>() {
+  // 网络输入上的 xyxy 像素角点，部分超出填充区域
+  let boxes: Vec<[f32; 4]> = (0..N * M)
+    .map(|_| {
+      let (x, y) = (rand::random::<f32>(), rand::random::<f32>());
+      let (w, h) = (0.5 * rand::random::<f32>(), 0.5 * rand::random::<f32>());
+      let input = INPUT as f32;
+      [x * input, y * input, (x + w) * input, (y + h) * input]
+    })
+    .collect();
+  let letterbox = [
+    Letterbox::fit(1920, 1080, INPUT, INPUT),
+    Letterbox::fit(480, 640, INPUT, INPUT),
+  ];
+
+  // 参考结果为原始图像上裁剪后的 xyxy 像素坐标
+  let projected: Vec<[f32; 4]> = boxes
+    .iter()
+    .enumerate()
+    .map(|(j, b)| {
+      let lb = &letterbox[j / M];
+      let x = |v: f32| ((v - lb.pad_x) / lb.scale_x).clamp(0.0, lb.source_width as f32);
+      let y = |v: f32| ((v - lb.pad_y) / lb.scale_y).clamp(0.0, lb.source_height as f32);
+      [x(b[0]), y(b[1]), x(b[2]), y(b[3])]
+    })
+    .collect();
+
+  let client = R::client(&R::Device::default());
+  for format in [BoxFormat::Xyxy, BoxFormat::Xywh, BoxFormat::Cxcywh] {
+    for unit in [BoxUnit::Normalized, BoxUnit::Pixel] {
+      for layout in [BoxLayout::Planar, BoxLayout::Interleaved] {
+        let encoding = BoxEncoding {
+          format,
+          unit,
+          clamp: false,
+          layout,
+        };
+        let project = BackProjectConfig::default()
+          .with_shape(INPUT, INPUT)
+          .with_box_encoding(encoding)
+          .with_dim(64)
+          .build()
+          .unwrap();
+
+        let scale = match unit {
+          BoxUnit::Normalized => INPUT as f32,
+          BoxUnit::Pixel => 1.0,
+        };
+        let input: Vec<[f32; 4]> = boxes.iter().map(|b| b.map(|v| v / scale)).collect();
+        let shape = match layout {
+          BoxLayout::Planar => [N, 4, M],
+          BoxLayout::Interleaved => [N, M, 4],
+        };
+        let bbox_buf =
+          DataBuffer::<R, f32>::from_slice(&encode(&input, encoding), &shape, &client).unwrap();
+        let output = project.execute(&client, bbox_buf, &letterbox).unwrap();
+        assert_eq!(output.shape(), &shape);
+        let output = output.into_vec(&client).unwrap();
+
+        let expected = encode(&projected, encoding);
+        for (i, (value, expected)) in output.iter().zip(&expected).enumerate() {
+          assert!(
+            (value - expected).abs() < 1e-2,
+            "{:?} 第 {} 个坐标不匹配: cubecl = {}, manual = {}",
+            encoding,
+            i,
+            value,
+            expected
+          );
+        }
+
+        // 形状与排列方式不符时报错
+        let wrong = match layout {
+          BoxLayout::Planar => [N, M, 4],
+          BoxLayout::Interleaved => [N, 4, M],
+        };
+        let bbox_buf = DataBuffer::<R, f32>::from_slice(&expected, &wrong, &client).unwrap();
+        assert!(project.execute(&client, bbox_buf, &letterbox).is_err());
+      }
+    }
+  }
+}
@@ -0,0 +1,281 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_nms.rs - 非极大值抑制测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::nms::{NmsConfig, NmsMethod},
+};
+
+const N: usize = 2;
+const CLS: u32 = 3;
+const H: usize = 12;
+const W: usize = 12;
+const MAX_DET: usize = 20;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_nms_cpu() {
+  test_postprocess_nms::<cubecl::cpu::CpuRuntime>(false);
+  test_postprocess_nms::<cubecl::cpu::CpuRuntime>(true);
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_nms_wgpu() {
+  test_postprocess_nms::<cubecl::wgpu::WgpuRuntime>(false);
+  test_postprocess_nms::<cubecl::wgpu::WgpuRuntime>(true);
+}
+
+fn test_postprocess_nms<R: Runtime>(class_agnostic: bool) {
+  let hw = H * W;
+  let score: Vec<f32> = (0..N * hw).map(|_| rand::random::<f32>()).collect();
+  let index: Vec<u32> = (0..N * hw).map(|_| rand::random::<u32>() % CLS).collect();
+
+  // 随机生成较大的框，保证存在足够多的重叠
+  let mut bbox = vec![0.0f32; N * 4 * hw];
+  for n in 0..N {
+    for i in 0..hw {
+      let cx = rand::random::<f32>();
+      let cy = rand::random::<f32>();
+      let w = 0.1 + 0.3 * rand::random::<f32>();
+      let h = 0.1 + 0.3 * rand::random::<f32>();
+      bbox[n * 4 * hw + i] = cx - w / 2.0;
+      bbox[n * 4 * hw + hw + i] = cy - h / 2.0;
+      bbox[n * 4 * hw + 2 * hw + i] = cx + w / 2.0;
+      bbox[n * 4 * hw + 3 * hw + i] = cy + h / 2.0;
+    }
+  }
+
+  let client = R::client(&R::Device::default());
+  let nms = NmsConfig::default()
+    .with_score_threshold(0.3)
+    .with_iou_threshold(0.5)
+    .with_max_detections(MAX_DET as u32)
+    .with_class_agnostic(class_agnostic)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let score_buf = DataBuffer::<R, f32>::from_slice(&score, &[N, H, W], &client).unwrap();
+  let index_buf = DataBuffer::<R, u32>::from_slice(&index, &[N, H, W], &client).unwrap();
+  let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox, &[N, 4, H, W], &client).unwrap();
+
+  let result = nms
+    .execute(&client, score_buf, index_buf, bbox_buf)
+    .unwrap();
+  assert_eq!(result.score.shape(), &[N, MAX_DET]);
+  assert_eq!(result.bbox.shape(), &[N, 4, MAX_DET]);
+
+  let count_cubecl = result.count.into_vec(&client).unwrap();
+  let score_cubecl = result.score.into_vec(&client).unwrap();
+  let index_cubecl = result.index.into_vec(&client).unwrap();
+  let bbox_cubecl = result.bbox.into_vec(&client).unwrap();
+  let source_cubecl = result.source.into_vec(&client).unwrap();
+
+  for n in 0..N {
+    let kept = run_nms_manual(
+      &score[n * hw..(n + 1) * hw],
+      &index[n * hw..(n + 1) * hw],
+      &bbox[n * 4 * hw..(n + 1) * 4 * hw],
+      0.3,
+      0.5,
+      class_agnostic,
+    );
+
+    assert_eq!(
+      count_cubecl[n] as usize,
+      kept.len(),
+      "第 {} 张图像保留数量不匹配",
+      n
+    );
+
+    for (k, &src) in kept.iter().enumerate() {
+      assert_eq!(source_cubecl[n * MAX_DET + k] as usize, src);
+      assert_eq!(index_cubecl[n * MAX_DET + k], index[n * hw + src]);
+      assert!((score_cubecl[n * MAX_DET + k] - score[n * hw + src]).abs() < 1e-6);
+      for c in 0..4 {
+        let b_cubecl = bbox_cubecl[n * 4 * MAX_DET + c * MAX_DET + k];
+        let b_manual = bbox[n * 4 * hw + c * hw + src];
+        assert!(
+          (b_cubecl - b_manual).abs() < 1e-6,
+          "第 {} 张图像第 {} 个框坐标不匹配: cubecl = {}, manual = {}",
+          n,
+          k,
+          b_cubecl,
+          b_manual
+        );
+      }
+    }
+
+    for k in kept.len()..MAX_DET {
+      assert_eq!(score_cubecl[n * MAX_DET + k], 0.0);
+    }
+  }
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_soft_nms_cpu() {
+  test_postprocess_soft_nms::<cubecl::cpu::CpuRuntime>(NmsMethod::Linear);
+  test_postprocess_soft_nms::<cubecl::cpu::CpuRuntime>(NmsMethod::Gaussian { sigma: 0.5 });
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_soft_nms_wgpu() {
+  test_postprocess_soft_nms::<cubecl::wgpu::WgpuRuntime>(NmsMethod::Linear);
+  test_postprocess_soft_nms::<cubecl::wgpu::WgpuRuntime>(NmsMethod::Gaussian { sigma: 0.5 });
+}
+
+fn test_postprocess_soft_nms<R: Runtime>(method: NmsMethod) {
+  let hw = H * W;
+  let score: Vec<f32> = (0..N * hw).map(|_| rand::random::<f32>()).collect();
+  let index: Vec<u32> = (0..N * hw).map(|_| rand::random::<u32>() % CLS).collect();
+  let mut bbox = vec![0.0f32; N * 4 * hw];
+  for n in 0..N {
+    for i in 0..hw {
+      let cx = rand::random::<f32>();
+      let cy = rand::random::<f32>();
+      let w = 0.1 + 0.3 * rand::random::<f32>();
+      let h = 0.1 + 0.3 * rand::random::<f32>();
+      bbox[n * 4 * hw + i] = cx - w / 2.0;
+      bbox[n * 4 * hw + hw + i] = cy - h / 2.0;
+      bbox[n * 4 * hw + 2 * hw + i] = cx + w / 2.0;
+      bbox[n * 4 * hw + 3 * hw + i] = cy + h / 2.0;
+    }
+  }
+
+  let client = R::client(&R::Device::default());
+  let nms = NmsConfig::default()
+    .with_score_threshold(0.3)
+    .with_iou_threshold(0.5)
+    .with_max_detections(MAX_DET as u32)
+    .with_method(method)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let score_buf = DataBuffer::<R, f32>::from_slice(&score, &[N, H, W], &client).unwrap();
+  let index_buf = DataBuffer::<R, u32>::from_slice(&index, &[N, H, W], &client).unwrap();
+  let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox, &[N, 4, H, W], &client).unwrap();
+
+  let result = nms
+    .execute(&client, score_buf, index_buf, bbox_buf)
+    .unwrap();
+  let count_cubecl = result.count.into_vec(&client).unwrap();
+  let score_cubecl = result.score.into_vec(&client).unwrap();
+  let source_cubecl = result.source.into_vec(&client).unwrap();
+
+  for n in 0..N {
+    let kept = run_soft_nms_manual(
+      &score[n * hw..(n + 1) * hw],
+      &index[n * hw..(n + 1) * hw],
+      &bbox[n * 4 * hw..(n + 1) * 4 * hw],
+      0.3,
+      0.5,
+      method,
+    );
+    assert_eq!(count_cubecl[n] as usize, kept.len());
+    for (k, &(src, s)) in kept.iter().enumerate() {
+      assert_eq!(source_cubecl[n * MAX_DET + k] as usize, src);
+      assert!((score_cubecl[n * MAX_DET + k] - s).abs() < 1e-5);
+    }
+  }
+}
+
+/// 返回保留框的 (位置, 衰减后的得分)
+fn run_soft_nms_manual(
+  score: &[f32],
+  index: &[u32],
+  bbox: &[f32],
+  score_threshold: f32,
+  iou_threshold: f32,
+  method: NmsMethod,
+) -> Vec<(usize, f32)> {
+  let m = score.len();
+  let get = |i: usize| [bbox[i], bbox[m + i], bbox[2 * m + i], bbox[3 * m + i]];
+
+  let mut alive: Vec<Option<f32>> = score
+    .iter()
+    .map(|&s| (s >= score_threshold).then_some(s))
+    .collect();
+  let mut kept = Vec::new();
+  while kept.len() < MAX_DET {
+    let Some((top, top_score)) = alive
+      .iter()
+      .enumerate()
+      .filter_map(|(i, s)| s.map(|s| (i, s)))
+      .fold(None, |best: Option<(usize, f32)>, (i, s)| match best {
+        Some((_, b)) if b >= s => best,
+        _ => Some((i, s)),
+      })
+    else {
+      break;
+    };
+    kept.push((top, top_score));
+    alive[top] = None;
+    for i in 0..m {
+      let Some(s) = alive[i] else { continue };
+      if index[i] != index[top] {
+        continue;
+      }
+      let overlap = iou(get(top), get(i));
+      let decayed = match method {
+        NmsMethod::Gaussian { sigma } => s * (-(overlap * overlap) / sigma).exp(),
+        NmsMethod::Linear if overlap > iou_threshold => s * (1.0 - overlap),
+        NmsMethod::Hard if overlap > iou_threshold => 0.0,
+        _ => s,
+      };
+      alive[i] = (decayed >= score_threshold).then_some(decayed);
+    }
+  }
+  kept
+}
+
+fn run_nms_manual(
+  score: &[f32],
+  index: &[u32],
+  bbox: &[f32],
+  score_threshold: f32,
+  iou_threshold: f32,
+  class_agnostic: bool,
+) -> Vec<usize> {
+  let m = score.len();
+  let get = |i: usize| [bbox[i], bbox[m + i], bbox[2 * m + i], bbox[3 * m + i]];
+
+  let mut order: Vec<usize> = (0..m).filter(|&i| score[i] >= score_threshold).collect();
+  order.sort_by(|&a, &b| score[b].partial_cmp(&score[a]).unwrap().then(a.cmp(&b)));
+
+  let mut kept: Vec<usize> = Vec::new();
+  for i in order {
+    if kept.len() == MAX_DET {
+      break;
+    }
+    let suppressed = kept
+      .iter()
+      .any(|&j| (class_agnostic || index[i] == index[j]) && iou(get(i), get(j)) > iou_threshold);
+    if !suppressed {
+      kept.push(i);
+    }
+  }
+  kept
+}
+
+fn iou(a: [f32; 4], b: [f32; 4]) -> f32 {
+  let inter_w = (a[2].min(b[2]) - a[0].max(b[0])).max(0.0);
+  let inter_h = (a[3].min(b[3]) - a[1].max(b[1])).max(0.0);
+  let inter = inter_w * inter_h;
+  let union = (a[2] - a[0]) * (a[3] - a[1]) + (b[2] - b[0]) * (b[3] - b[1]) - inter;
+  if union > 0.0 { inter / union } else { 0.0 }
+}
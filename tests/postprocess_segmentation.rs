@@ -0,0 +1,175 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_segmentation.rs - 语义分割后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{data::DataBuffer, postprocess::segmentation::SegmentationConfig};
+
+const N: usize = 2;
+const C: usize = 5;
+const H: usize = 16;
+const W: usize = 12;
+const OH: usize = 40;
+const OW: usize = 30;
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_segmentation_cpu() {
+  test_postprocess_segmentation::<cubecl::cpu::CpuRuntime>();
+  test_postprocess_segmentation_resize::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_segmentation_wgpu() {
+  test_postprocess_segmentation::<cubecl::wgpu::WgpuRuntime>();
+  test_postprocess_segmentation_resize::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn random_logits() -> Vec<f32> {
+  (0..N * C * H * W)
+    .map(|_| rand::random::<f32>() * 10.0 - 5.0)
+    .collect()
+}
+
+fn test_postprocess_segmentation<R: Runtime>() {
+  let logits = random_logits();
+
+  let client = R::client(&R::Device::default());
+  let segmentation = SegmentationConfig::default().with_dim(64).build().unwrap();
+
+  let logits_buf = DataBuffer::<R, f32>::from_slice(&logits, &[N, C, H, W], &client).unwrap();
+  let (score, label) = segmentation
+    .execute::<R, f32, u32>(&client, logits_buf)
+    .unwrap();
+  assert_eq!(label.shape(), &[N, H, W]);
+
+  // 调色板只覆盖前 4 个类别，类别 4 应输出为黑色
+  let palette: [[f32; 3]; 4] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 0.0, 1.0],
+  ];
+  let rgb = segmentation
+    .colorize(&client, label.clone(), &palette)
+    .unwrap();
+  assert_eq!(rgb.shape(), &[N, 3, H, W]);
+
+  let score = score.into_vec(&client).unwrap();
+  let label = label.into_vec(&client).unwrap();
+  let rgb = rgb.into_vec(&client).unwrap();
+
+  let (score_manual, label_manual) = argmax_manual(&logits, H, W);
+  assert_eq!(label, label_manual, "类别索引不匹配");
+  assert_eq!(score, score_manual, "得分不匹配");
+
+  let hw = H * W;
+  for n in 0..N {
+    for i in 0..hw {
+      let color = palette
+        .get(label[n * hw + i] as usize)
+        .copied()
+        .unwrap_or([0.0; 3]);
+      for (c, &value) in color.iter().enumerate() {
+        assert_eq!(rgb[n * 3 * hw + c * hw + i], value);
+      }
+    }
+  }
+}
+
+fn test_postprocess_segmentation_resize<R: Runtime>() {
+  let logits = random_logits();
+
+  let client = R::client(&R::Device::default());
+  let segmentation = SegmentationConfig::default()
+    .with_resize(OW as u32, OH as u32)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let logits_buf = DataBuffer::<R, f32>::from_slice(&logits, &[N, C, H, W], &client).unwrap();
+  let (score, label) = segmentation
+    .execute::<R, f32, u32>(&client, logits_buf)
+    .unwrap();
+  assert_eq!(label.shape(), &[N, OH, OW]);
+
+  let score = score.into_vec(&client).unwrap();
+  let label = label.into_vec(&client).unwrap();
+
+  let resized = resize_manual(&logits);
+  let (score_manual, label_manual) = argmax_manual(&resized, OH, OW);
+  for (i, (s, m)) in score.iter().zip(score_manual.iter()).enumerate() {
+    assert!(
+      (s - m).abs() < 1e-4,
+      "得分第 {} 个元素不匹配: cubecl = {}, manual = {}",
+      i,
+      s,
+      m
+    );
+  }
+  // 浮点误差可能使得分非常接近的类别互换，只检查差距明显的位置
+  let mismatched = label
+    .iter()
+    .zip(label_manual.iter())
+    .enumerate()
+    .filter(|&(_, (a, b))| a != b)
+    .filter(|&(i, (&a, _))| {
+      let n = i / (OH * OW);
+      let rem = i % (OH * OW);
+      (resized[(n * C + a as usize) * OH * OW + rem] - score_manual[i]).abs() > 1e-4
+    })
+    .count();
+  assert_eq!(mismatched, 0, "缩放后的类别索引不匹配");
+}
+
+fn argmax_manual(logits: &[f32], h: usize, w: usize) -> (Vec<f32>, Vec<u32>) {
+  let hw = h * w;
+  let mut score = vec![0.0; N * hw];
+  let mut label = vec![0u32; N * hw];
+  for n in 0..N {
+    for i in 0..hw {
+      let mut best = logits[n * C * hw + i];
+      let mut best_c = 0;
+      for c in 1..C {
+        let v = logits[(n * C + c) * hw + i];
+        if v > best {
+          best = v;
+          best_c = c;
+        }
+      }
+      score[n * hw + i] = best;
+      label[n * hw + i] = best_c as u32;
+    }
+  }
+  (score, label)
+}
+
+fn resize_manual(logits: &[f32]) -> Vec<f32> {
+  let mut output = vec![0.0; N * C * OH * OW];
+  for nc in 0..N * C {
+    let plane = &logits[nc * H * W..(nc + 1) * H * W];
+    for y in 0..OH {
+      for x in 0..OW {
+        let sx = ((x as f32 + 0.5) * W as f32 / OW as f32 - 0.5).clamp(0.0, (W - 1) as f32);
+        let sy = ((y as f32 + 0.5) * H as f32 / OH as f32 - 0.5).clamp(0.0, (H - 1) as f32);
+        let (x0, y0) = (sx.floor() as usize, sy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(W - 1), (y0 + 1).min(H - 1));
+        let (fx, fy) = (sx - x0 as f32, sy - y0 as f32);
+        let top = plane[y0 * W + x0] * (1.0 - fx) + plane[y0 * W + x1] * fx;
+        let bottom = plane[y1 * W + x0] * (1.0 - fx) + plane[y1 * W + x1] * fx;
+        output[(nc * OH + y) * OW + x] = top * (1.0 - fy) + bottom * fy;
+      }
+    }
+  }
+  output
+}
@@ -0,0 +1,124 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_text.rs - DBNet 文本检测后处理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::text::{DbNetConfig, min_area_rect},
+};
+
+const W: usize = 64;
+const H: usize = 32;
+
+fn assert_close(a: [f32; 2], b: [f32; 2]) {
+  assert!(
+    (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3,
+    "{:?} != {:?}",
+    a,
+    b
+  );
+}
+
+#[test]
+fn test_min_area_rect() {
+  assert!(min_area_rect(&[]).is_none());
+
+  // 沿 (0.8, 0.6) 方向排列的 20 x 5 个点
+  let points: Vec<[f32; 2]> = (0..20)
+    .flat_map(|i| {
+      (0..5).map(move |j| {
+        let (i, j) = (i as f32, j as f32);
+        [10.0 + i * 0.8 - j * 0.6, 10.0 + i * 0.6 + j * 0.8]
+      })
+    })
+    .collect();
+  let rect = min_area_rect(&points).unwrap();
+  let mut size = rect.size;
+  size.sort_by(f32::total_cmp);
+  assert!((size[0] - 4.0).abs() < 1e-3);
+  assert!((size[1] - 19.0).abs() < 1e-3);
+  assert_close(rect.center, [16.4, 17.3]);
+
+  let corners = rect.points();
+  assert_close(corners[0], [10.0, 10.0]);
+  assert_close(corners[1], [25.2, 21.4]);
+  assert_close(corners[2], [22.8, 24.6]);
+  assert_close(corners[3], [7.6, 13.2]);
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_postprocess_text_cpu() {
+  test_postprocess_text::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_postprocess_text_wgpu() {
+  test_postprocess_text::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_postprocess_text<R: Runtime>() {
+  let mut prob = vec![0.0f32; 2 * H * W];
+  let mut fill = |b: usize, xs: std::ops::Range<usize>, ys: std::ops::Range<usize>, v: f32| {
+    for y in ys {
+      for x in xs.clone() {
+        prob[b * H * W + y * W + x] = v;
+      }
+    }
+  };
+  // 第一张图像: 文本区域、过小的区域与平均概率过低的区域
+  fill(0, 10..30, 5..15, 0.9);
+  fill(0, 40..42, 20..22, 0.9);
+  fill(0, 50..60, 20..30, 0.5);
+  // 第二张图像: 两个文本区域
+  fill(1, 2..12, 2..8, 0.8);
+  fill(1, 20..60, 20..28, 1.0);
+
+  let client = R::client(&R::Device::default());
+  let dbnet = DbNetConfig::default()
+    .with_shape(W as u32 * 4, H as u32 * 4)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let prob_buf = DataBuffer::<R, f32>::from_slice(&prob, &[2, 1, H, W], &client).unwrap();
+  let mask = dbnet.binarize(&client, prob_buf.clone()).unwrap();
+  assert_eq!(mask.shape(), &[2, H, W]);
+  let mask = mask.into_vec(&client).unwrap();
+  for (m, p) in mask.iter().zip(prob.iter()) {
+    assert_eq!(*m, (*p > 0.3) as u32);
+  }
+
+  let texts = dbnet.execute(&client, prob_buf).unwrap();
+  assert_eq!(texts.len(), 2);
+  assert_eq!(texts[0].len(), 1);
+  assert_eq!(texts[1].len(), 2);
+
+  // 像素中心 x ∈ [10, 29]，y ∈ [5, 14] 的矩形，外扩距离 d = 19 * 9 * 1.5 / 56
+  let text = &texts[0][0];
+  assert!((text.score - 0.9).abs() < 1e-4);
+  let d = 19.0 * 9.0 * 1.5 / 56.0;
+  let expected = [
+    [10.0 - d, 5.0 - d],
+    [29.0 + d, 5.0 - d],
+    [29.0 + d, 14.0 + d],
+    [10.0 - d, 14.0 + d],
+  ];
+  for (p, e) in text.points.iter().zip(expected.iter()) {
+    assert_close(*p, [e[0] * 4.0, e[1] * 4.0]);
+  }
+
+  assert!((texts[1][0].score - 0.8).abs() < 1e-4);
+  assert!((texts[1][1].score - 1.0).abs() < 1e-4);
+}
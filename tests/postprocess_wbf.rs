@@ -0,0 +1,111 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/postprocess_wbf.rs - 加权框融合测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use shanan_cv::postprocess::{
+  detection::{BoxUnit, Detection, DetectionSet},
+  wbf::WbfConfig,
+};
+
+fn detection(bbox: [f32; 4], score: f32, class_id: u32, batch_index: usize) -> Detection {
+  Detection {
+    bbox,
+    score,
+    class_id,
+    batch_index,
+  }
+}
+
+fn assert_detection(d: &Detection, bbox: [f32; 4], score: f32, class_id: u32) {
+  assert_eq!(d.class_id, class_id);
+  assert!((d.score - score).abs() < 1e-5, "{} != {}", d.score, score);
+  for (a, b) in d.bbox.iter().zip(bbox.iter()) {
+    assert!((a - b).abs() < 1e-5, "{:?} != {:?}", d.bbox, bbox);
+  }
+}
+
+fn models() -> Vec<DetectionSet> {
+  let a = DetectionSet::new(
+    vec![
+      detection([0.0, 0.0, 10.0, 10.0], 0.9, 0, 0),
+      detection([20.0, 20.0, 30.0, 30.0], 0.8, 0, 0),
+      detection([5.0, 5.0, 8.0, 8.0], 0.5, 2, 1),
+    ],
+    2,
+    BoxUnit::Pixel,
+  );
+  let b = DetectionSet::new(
+    vec![
+      detection([1.0, 1.0, 11.0, 11.0], 0.6, 0, 0),
+      detection([0.0, 0.0, 10.0, 10.0], 0.7, 1, 0),
+      detection([5.0, 5.0, 8.0, 8.0], 0.05, 2, 1),
+    ],
+    2,
+    BoxUnit::Pixel,
+  );
+  vec![a, b]
+}
+
+#[test]
+fn test_wbf() {
+  let wbf = WbfConfig::default()
+    .with_skip_box_threshold(0.1)
+    .build()
+    .unwrap();
+  let fused = wbf.execute(&models()).unwrap();
+  assert_eq!(fused.batch_size(), 2);
+  assert_eq!(fused.unit(), BoxUnit::Pixel);
+
+  // 第一张图像: 两个模型检出的框合并，只被一个模型检出的框得分减半
+  let image = fused.batch(0);
+  assert_eq!(image.len(), 3);
+  assert_detection(&image[0], [0.4, 0.4, 10.4, 10.4], 0.75, 0);
+  assert_detection(&image[1], [20.0, 20.0, 30.0, 30.0], 0.4, 0);
+  assert_detection(&image[2], [0.0, 0.0, 10.0, 10.0], 0.35, 1);
+
+  // 第二张图像: 低于 skip_box_threshold 的框不参与融合
+  let image = fused.batch(1);
+  assert_eq!(image.len(), 1);
+  assert_detection(&image[0], [5.0, 5.0, 8.0, 8.0], 0.25, 2);
+
+  // 模型权重
+  let wbf = WbfConfig::default()
+    .with_skip_box_threshold(0.1)
+    .with_weights(vec![2.0, 1.0])
+    .build()
+    .unwrap();
+  let fused = wbf.execute(&models()).unwrap();
+  let image = fused.batch(0);
+  assert_detection(&image[0], [0.25, 0.25, 10.25, 10.25], 0.8, 0);
+  assert_detection(&image[1], [20.0, 20.0, 30.0, 30.0], 1.6 / 3.0, 0);
+  assert_detection(&image[2], [0.0, 0.0, 10.0, 10.0], 0.7 / 3.0, 1);
+
+  // 交并比阈值高于两框的交并比时不合并
+  let wbf = WbfConfig::default()
+    .with_iou_threshold(0.7)
+    .build()
+    .unwrap();
+  let fused = wbf.execute(&models()).unwrap();
+  assert_eq!(fused.batch(0).len(), 4);
+
+  // 无效输入
+  assert!(wbf.execute(&[]).is_err());
+  let mut sets = models();
+  sets.push(DetectionSet::new(Vec::new(), 2, BoxUnit::Normalized));
+  assert!(wbf.execute(&sets).is_err());
+  let wbf = WbfConfig::default()
+    .with_weights(vec![1.0])
+    .build()
+    .unwrap();
+  assert!(wbf.execute(&models()).is_err());
+  assert!(WbfConfig::default().with_weights(vec![]).build().is_err());
+}
@@ -0,0 +1,289 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/slicing.rs - 切片推理测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::detection::{BoxUnit, Detection, DetectionSet},
+  slicing::{MatchMetric, MergeMethod, SlicingConfig, Tile},
+};
+
+const N: usize = 2;
+const C: usize = 3;
+const H: usize = 50;
+const W: usize = 70;
+const TILE: u32 = 32;
+
+fn detection(bbox: [f32; 4], score: f32, class_id: u32, batch_index: usize) -> Detection {
+  Detection {
+    bbox,
+    score,
+    class_id,
+    batch_index,
+  }
+}
+
+#[test]
+fn test_slicing_tiles() {
+  let slicing = SlicingConfig::default()
+    .with_tile_size(640, 640)
+    .with_overlap(0.2, 0.2)
+    .build()
+    .unwrap();
+
+  // 步长为 512，最后一列与最后一行向内对齐
+  let tiles = slicing.tiles(1, 1920, 1080);
+  let xs: Vec<u32> = tiles.iter().take(4).map(|t| t.x).collect();
+  assert_eq!(xs, [0, 512, 1024, 1280]);
+  let ys: Vec<u32> = tiles.iter().step_by(4).map(|t| t.y).collect();
+  assert_eq!(ys, [0, 440]);
+  assert_eq!(tiles.len(), 8);
+  assert!(tiles.iter().all(|t| t.width == 640 && t.height == 640));
+  assert!(
+    tiles
+      .iter()
+      .all(|t| t.x + t.width <= 1920 && t.y + t.height <= 1080)
+  );
+
+  // 小于图块的图像只生成一个覆盖整幅图像的图块
+  let tiles = slicing.tiles(2, 320, 200);
+  assert_eq!(tiles.len(), 2);
+  assert_eq!(tiles[1].image, 1);
+  assert_eq!((tiles[1].width, tiles[1].height), (320, 200));
+
+  let slicing = SlicingConfig::default()
+    .with_full_image(true)
+    .build()
+    .unwrap();
+  let tiles = slicing.tiles(2, 1920, 1080);
+  assert_eq!(tiles.len(), 18);
+  assert_eq!(tiles[8].width, 1920);
+  assert_eq!(tiles[17].image, 1);
+
+  // 尺寸为 0 的图像没有图块
+  assert!(slicing.tiles(2, 0, 1080).is_empty());
+  assert!(slicing.tiles(2, 1920, 0).is_empty());
+
+  // 图块的预处理参数将图块输入坐标映射回原图
+  let tile = Tile {
+    image: 0,
+    x: 100,
+    y: 50,
+    width: 320,
+    height: 320,
+    source_width: 1920,
+    source_height: 1080,
+  };
+  let letterbox = tile.letterbox(640, 640);
+  assert_eq!(letterbox.scale_x, 2.0);
+  assert!(((640.0 - letterbox.pad_x) / letterbox.scale_x - 420.0).abs() < 1e-4);
+  assert!(((0.0 - letterbox.pad_y) / letterbox.scale_y - 50.0).abs() < 1e-4);
+
+  assert!(
+    SlicingConfig::default()
+      .with_overlap(1.0, 0.2)
+      .build()
+      .is_err()
+  );
+  assert!(
+    SlicingConfig::default()
+      .with_tile_size(0, 640)
+      .build()
+      .is_err()
+  );
+}
+
+#[test]
+fn test_slicing_merge() {
+  let slicing = SlicingConfig::default().build().unwrap();
+  let tiles = slicing.tiles(2, 1280, 640);
+  assert_eq!(tiles.len(), 6);
+
+  // 跨越图块边界的目标被两个图块分别截断检出
+  let set = DetectionSet::new(
+    vec![
+      detection([500.0, 100.0, 640.0, 200.0], 0.9, 0, 0),
+      detection([512.0, 100.0, 600.0, 200.0], 0.7, 0, 1),
+      detection([530.0, 120.0, 560.0, 150.0], 0.6, 1, 1),
+      detection([10.0, 10.0, 20.0, 20.0], 0.5, 0, 4),
+    ],
+    6,
+    BoxUnit::Pixel,
+  );
+
+  let merged = slicing.merge(&set, &tiles).unwrap();
+  assert_eq!(merged.batch_size(), 2);
+  let first = merged.batch(0);
+  assert_eq!(first.len(), 2);
+  assert_eq!(first[0].bbox, [500.0, 100.0, 640.0, 200.0]);
+  assert_eq!(first[0].score, 0.9);
+  assert_eq!(first[1].class_id, 1);
+  assert_eq!(merged.batch(1).len(), 1);
+
+  // 非极大值合并扩展为并集，交并比度量下截断的框不被合并
+  let set = DetectionSet::new(
+    vec![
+      detection([500.0, 100.0, 620.0, 200.0], 0.9, 0, 0),
+      detection([512.0, 90.0, 660.0, 200.0], 0.8, 0, 1),
+    ],
+    2,
+    BoxUnit::Pixel,
+  );
+  let merged = slicing.merge(&set, &tiles).unwrap();
+  assert_eq!(merged.len(), 1);
+  assert_eq!(merged.detections()[0].bbox, [500.0, 90.0, 660.0, 200.0]);
+
+  let nms = SlicingConfig::default()
+    .with_merge_method(MergeMethod::Nms)
+    .build()
+    .unwrap();
+  let merged = nms.merge(&set, &tiles).unwrap();
+  assert_eq!(merged.detections()[0].bbox, [500.0, 100.0, 620.0, 200.0]);
+
+  let iou = SlicingConfig::default()
+    .with_match(MatchMetric::Iou, 0.7)
+    .build()
+    .unwrap();
+  assert_eq!(iou.merge(&set, &tiles).unwrap().len(), 2);
+
+  // 类别无关时不同类别之间也会合并
+  let set = DetectionSet::new(
+    vec![
+      detection([0.0, 0.0, 100.0, 100.0], 0.9, 0, 0),
+      detection([10.0, 10.0, 90.0, 90.0], 0.8, 1, 0),
+    ],
+    1,
+    BoxUnit::Pixel,
+  );
+  assert_eq!(slicing.merge(&set, &tiles).unwrap().len(), 2);
+  let agnostic = SlicingConfig::default()
+    .with_class_agnostic(true)
+    .build()
+    .unwrap();
+  let merged = agnostic.merge(&set, &tiles).unwrap();
+  assert_eq!(merged.len(), 1);
+  assert_eq!(merged.detections()[0].class_id, 0);
+
+  assert!(
+    slicing
+      .merge(&set.clone().to_normalized(1280, 640).unwrap(), &tiles)
+      .is_err()
+  );
+  assert!(slicing.merge(&set, &tiles[..0]).is_err());
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_slicing_cpu() {
+  test_slicing::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_slicing_wgpu() {
+  test_slicing::<cubecl::wgpu::WgpuRuntime>();
+}
+
+/// 双线性裁剪缩放的 CPU 参考实现
+fn crop_reference(image: &[f32], tile: &Tile, c: usize, y: usize, x: usize) -> f32 {
+  let (rw, rh) = (tile.width as f32, tile.height as f32);
+  let sx = ((x as f32 + 0.5) * rw / TILE as f32 - 0.5).clamp(0.0, rw - 1.0);
+  let sy = ((y as f32 + 0.5) * rh / TILE as f32 - 0.5).clamp(0.0, rh - 1.0);
+  let (x0, y0) = (sx.floor() as usize, sy.floor() as usize);
+  let x1 = (x0 + 1).min(tile.width as usize - 1);
+  let y1 = (y0 + 1).min(tile.height as usize - 1);
+  let (fx, fy) = (sx - x0 as f32, sy - y0 as f32);
+
+  let at = |yy: usize, xx: usize| {
+    image[((tile.image * C + c) * H + tile.y as usize + yy) * W + tile.x as usize + xx]
+  };
+  let top = at(y0, x0) + (at(y0, x1) - at(y0, x0)) * fx;
+  let bottom = at(y1, x0) + (at(y1, x1) - at(y1, x0)) * fx;
+  top + (bottom - top) * fy
+}
+
+fn test_slicing<R: Runtime>() {
+  let image: Vec<f32> = (0..N * C * H * W).map(|_| rand::random::<f32>()).collect();
+
+  let client = R::client(&R::Device::default());
+  let slicing = SlicingConfig::default()
+    .with_tile_size(TILE, TILE)
+    .with_overlap(0.25, 0.25)
+    .with_full_image(true)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  let image_buf = DataBuffer::<R, f32>::from_slice(&image, &[N, C, H, W], &client).unwrap();
+  let (batch, tiles) = slicing.crop(&client, image_buf).unwrap();
+  // 列起点 0/24/38，行起点 0/18，再加整图
+  assert_eq!(tiles.len(), N * 7);
+  let t = tiles.len();
+  let tile_size = TILE as usize;
+  assert_eq!(batch.shape(), &[t, C, tile_size, tile_size]);
+  let batch = batch.into_vec(&client).unwrap();
+
+  for (i, tile) in tiles.iter().enumerate() {
+    for c in 0..C {
+      for y in 0..tile_size {
+        for x in 0..tile_size {
+          let value = batch[((i * C + c) * tile_size + y) * tile_size + x];
+          let expected = crop_reference(&image, tile, c, y, x);
+          assert!(
+            (value - expected).abs() < 1e-4,
+            "第 {} 个图块 ({}, {}, {}) 不匹配: cubecl = {}, manual = {}",
+            i,
+            c,
+            y,
+            x,
+            value,
+            expected
+          );
+        }
+      }
+    }
+  }
+
+  // 各图块归一化的边界框映射回原图像素坐标
+  const M: usize = 16;
+  let bbox: Vec<f32> = (0..t * 4 * M).map(|_| rand::random::<f32>()).collect();
+  let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox, &[t, 4, M], &client).unwrap();
+  let output = slicing.remap(&client, bbox_buf, &tiles).unwrap();
+  assert_eq!(output.shape(), &[t, 4, M]);
+  let output = output.into_vec(&client).unwrap();
+
+  for (i, tile) in tiles.iter().enumerate() {
+    for c in 0..4 {
+      let (offset, size) = if c % 2 == 0 {
+        (tile.x, tile.width)
+      } else {
+        (tile.y, tile.height)
+      };
+      for m in 0..M {
+        let idx = (i * 4 + c) * M + m;
+        let expected = bbox[idx] * size as f32 + offset as f32;
+        assert!(
+          (output[idx] - expected).abs() < 1e-3,
+          "第 {} 个图块第 {} 个框坐标不匹配: cubecl = {}, manual = {}",
+          i,
+          m,
+          output[idx],
+          expected
+        );
+      }
+    }
+  }
+
+  let bbox_buf = DataBuffer::<R, f32>::from_slice(&bbox[..4 * M], &[1, 4, M], &client).unwrap();
+  assert!(slicing.remap(&client, bbox_buf, &tiles).is_err());
+}
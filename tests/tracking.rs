@@ -0,0 +1,194 @@
+// 该文件是 Shanan CV 项目的一部分。
+// tests/tracking.rs - 多目标跟踪测试
+//
+// 本文件根据 Apache 许可证第 2.0 版（以下简称“许可证”）授权使用；
+// 除非遵守该许可证条款，否则您不得使用本文件。
+// 您可通过以下网址获取许可证副本：
+// http://www.apache.org/licenses/LICENSE-2.0
+// 除非适用法律要求或书面同意，根据本许可协议分发的软件均按“原样”提供，
+// 不附带任何形式的明示或暗示的保证或条件。
+// 有关许可权限与限制的具体条款，请参阅本许可协议。
+//
+// Copyright (C) 2026 Johann Li <me@qinka.pro>, Wareless Group
+
+use cubecl::prelude::*;
+use shanan_cv::{
+  data::DataBuffer,
+  postprocess::detection::Detection,
+  tracking::{ByteTrackConfig, KalmanFilter, TrackState, bbox_to_xyah, linear_assignment},
+};
+
+fn detection(bbox: [f32; 4], score: f32) -> Detection {
+  Detection {
+    bbox,
+    score,
+    class_id: 0,
+    batch_index: 0,
+  }
+}
+
+#[test]
+fn test_linear_assignment() {
+  let cost = [4.0, 1.0, 3.0, 2.0, 0.0, 5.0, 3.0, 2.0, 2.0];
+  let result = linear_assignment(&cost, 3, 3, 10.0).unwrap();
+  assert_eq!(result.matches, vec![(0, 1), (1, 0), (2, 2)]);
+  assert!(result.unmatched_rows.is_empty());
+  assert!(result.unmatched_cols.is_empty());
+
+  // 代价超过上限的配对不匹配
+  let cost = [0.1, 0.9, 0.2, 0.95, 0.9, 0.9];
+  let result = linear_assignment(&cost, 3, 2, 0.5).unwrap();
+  assert_eq!(result.matches, vec![(0, 0)]);
+  assert_eq!(result.unmatched_rows, vec![1, 2]);
+  assert_eq!(result.unmatched_cols, vec![1]);
+
+  // 行数少于列数
+  let cost = [0.1, 0.3, 0.05, 0.2, 0.9, 0.8];
+  let result = linear_assignment(&cost, 2, 3, 0.5).unwrap();
+  assert_eq!(result.matches, vec![(0, 2), (1, 0)]);
+  assert_eq!(result.unmatched_cols, vec![1]);
+
+  let result = linear_assignment(&[], 0, 2, 0.5).unwrap();
+  assert_eq!(result.unmatched_cols, vec![0, 1]);
+  assert!(linear_assignment(&[0.0; 3], 2, 2, 0.5).is_err());
+}
+
+#[test]
+fn test_kalman_filter() {
+  let kalman = KalmanFilter::default();
+  let bbox = [10.0, 20.0, 30.0, 60.0];
+  let mut state = kalman.initiate(bbox_to_xyah(bbox));
+  for (a, b) in state.bbox().iter().zip(bbox.iter()) {
+    assert!((a - b).abs() < 1e-4);
+  }
+
+  // 匀速运动，每帧向右移动 2 个像素
+  for frame in 1..30 {
+    kalman.predict(&mut state);
+    let shift = frame as f32 * 2.0;
+    let measurement = [bbox[0] + shift, bbox[1], bbox[2] + shift, bbox[3]];
+    kalman.update(&mut state, bbox_to_xyah(measurement));
+  }
+  assert!((state.mean[4] - 2.0).abs() < 0.1, "vx = {}", state.mean[4]);
+  assert!(state.mean[5].abs() < 0.1);
+
+  kalman.predict(&mut state);
+  assert!((state.mean[0] - (20.0 + 60.0)).abs() < 0.5);
+  assert!((state.mean[3] - 40.0).abs() < 0.5);
+}
+
+#[cfg(feature = "cpu")]
+#[test]
+fn test_tracking_cpu() {
+  test_tracking::<cubecl::cpu::CpuRuntime>();
+}
+
+#[cfg(feature = "wgpu")]
+#[test]
+fn test_tracking_wgpu() {
+  test_tracking::<cubecl::wgpu::WgpuRuntime>();
+}
+
+fn test_tracking<R: Runtime>() {
+  let client = R::client(&R::Device::default());
+  let mut tracker = ByteTrackConfig::default()
+    .with_max_lost(5)
+    .with_dim(64)
+    .build()
+    .unwrap();
+
+  // 交并比矩阵
+  let a = [[0.0, 0.0, 10.0, 10.0], [5.0, 5.0, 15.0, 15.0]];
+  let b = [
+    [0.0, 0.0, 10.0, 10.0],
+    [20.0, 20.0, 30.0, 30.0],
+    [0.0, 5.0, 10.0, 15.0],
+  ];
+  let planar = |boxes: &[[f32; 4]]| -> Vec<f32> {
+    (0..4)
+      .flat_map(|c| boxes.iter().map(move |b| b[c]))
+      .collect()
+  };
+  let a_buf = DataBuffer::<R, f32>::from_slice(&planar(&a), &[4, 2], &client).unwrap();
+  let b_buf = DataBuffer::<R, f32>::from_slice(&planar(&b), &[4, 3], &client).unwrap();
+  let overlap = tracker
+    .iou_matrix(&client, a_buf, b_buf)
+    .unwrap()
+    .into_vec(&client)
+    .unwrap();
+  let expected = [1.0, 0.0, 1.0 / 3.0, 25.0 / 175.0, 0.0, 50.0 / 150.0];
+  for (v, e) in overlap.iter().zip(expected.iter()) {
+    assert!((v - e).abs() < 1e-5);
+  }
+
+  // 两个向相反方向运动的目标
+  let object = |frame: usize, id: usize| -> [f32; 4] {
+    let x = if id == 0 {
+      10.0 + frame as f32 * 3.0
+    } else {
+      200.0 - frame as f32 * 3.0
+    };
+    [x, 50.0, x + 30.0, 110.0]
+  };
+
+  let mut ids = [0u64; 2];
+  for frame in 0..20 {
+    let mut detections = vec![detection(object(frame, 0), 0.9)];
+    // 第二个目标在第 5 帧得分较低，第 10 到 12 帧被遮挡
+    match frame {
+      5 => detections.push(detection(object(frame, 1), 0.3)),
+      10..=12 => {}
+      _ => detections.push(detection(object(frame, 1), 0.8)),
+    }
+    // 第 15 帧出现新的目标
+    if frame >= 15 {
+      detections.push(detection([300.0, 300.0, 340.0, 380.0], 0.85));
+    }
+
+    let tracks = tracker.update(&client, &detections).unwrap();
+    let find = |bbox: [f32; 4]| {
+      tracks.iter().find(|t| {
+        t.bbox
+          .iter()
+          .zip(bbox.iter())
+          .all(|(a, b)| (a - b).abs() < 5.0)
+      })
+    };
+
+    let first = find(object(frame, 0)).expect("第一个目标应被跟踪");
+    if frame == 0 {
+      ids[0] = first.id;
+    }
+    assert_eq!(first.id, ids[0]);
+
+    match frame {
+      10..=12 => {
+        assert!(find(object(frame, 1)).is_none());
+        let lost = tracker.tracks();
+        assert!(
+          lost
+            .iter()
+            .any(|t| t.id == ids[1] && t.state == TrackState::Lost)
+        );
+      }
+      _ => {
+        let second = find(object(frame, 1)).expect("第二个目标应被跟踪");
+        if frame == 0 {
+          ids[1] = second.id;
+        }
+        assert_eq!(second.id, ids[1], "第 {} 帧编号不一致", frame);
+      }
+    }
+
+    // 新目标在出现的下一帧确认
+    let third = tracks.iter().find(|t| t.id > ids[1]);
+    match frame {
+      0..=15 => assert!(third.is_none()),
+      _ => assert_eq!(third.unwrap().id, 3),
+    }
+  }
+  assert_eq!(tracker.frame(), 20);
+
+  tracker.reset();
+  assert!(tracker.tracks().is_empty());
+}